@@ -31,6 +31,7 @@
             p7zip
             lzip
             lz4
+            ncompress
 
             # Additional utilities
             tree # For viewing directory structure
@@ -58,6 +59,7 @@
             echo "  - zstd"
             echo "  - 7z"
             echo "  - lzip/lz4"
+            echo "  - compress"
             echo ""
             echo "Run 'nix run .#generateTestArchives' to create all test archives"
           '';
@@ -79,6 +81,7 @@
               pkgs.p7zip
               pkgs.lzip
               pkgs.lz4
+              pkgs.ncompress
               pkgs.tree
               pkgs.file
               pkgs.coreutils
@@ -178,6 +181,28 @@
           echo "Creating: archive.tar.zst"
           tar -c test-data/ | zstd -q -o archive.tar.zst
 
+          echo ""
+          echo "=== Creating TAR.Z archives ==="
+
+          # TAR.Z (Unix compress)
+          echo "Creating: archive.tar.Z"
+          tar -c test-data/ | compress -c > archive.tar.Z
+
+          # Alternative naming
+          echo "Creating: archive.taz"
+          cp archive.tar.Z archive.taz
+
+          echo ""
+          echo "=== Creating TAR.LZMA and TAR.LZ archives ==="
+
+          # TAR.LZMA (legacy lzma-alone)
+          echo "Creating: archive.tar.lzma"
+          tar -c test-data/ | xz --format=lzma > archive.tar.lzma
+
+          # TAR.LZ (lzip)
+          echo "Creating: archive.tar.lz"
+          tar -c test-data/ | lzip -c > archive.tar.lz
+
           echo ""
           echo "=== Creating compressed single files ==="
 
@@ -201,6 +226,18 @@
           echo "Creating: hello.txt.lz4"
           lz4 -q test-data/hello.txt hello.txt.lz4
 
+          # Unix compress single file
+          echo "Creating: hello.txt.Z"
+          compress -c test-data/hello.txt > hello.txt.Z
+
+          # Legacy LZMA single file
+          echo "Creating: hello.txt.lzma"
+          xz --format=lzma -c test-data/hello.txt > hello.txt.lzma
+
+          # LZIP single file
+          echo "Creating: hello.txt.lz"
+          lzip -c test-data/hello.txt > hello.txt.lz
+
           echo ""
           echo "=== Creating 7z archives ==="
 
@@ -302,6 +339,9 @@
           - \`archive.tar.bz2\` / \`archive.tbz2\` - TAR with bzip2 compression
           - \`archive.tar.xz\` / \`archive.txz\` - TAR with xz compression
           - \`archive.tar.zst\` - TAR with zstd compression
+          - \`archive.tar.Z\` / \`archive.taz\` - TAR with Unix compress
+          - \`archive.tar.lzma\` - TAR with legacy lzma compression
+          - \`archive.tar.lz\` - TAR with lzip compression
           - \`nested.tar.gz\` - Compressed TAR containing other archives
 
           ### Single File Compression
@@ -310,6 +350,9 @@
           - \`hello.txt.xz\` - xz compressed file
           - \`hello.txt.zst\` - zstd compressed file
           - \`hello.txt.lz4\` - lz4 compressed file
+          - \`hello.txt.Z\` - Unix compress file
+          - \`hello.txt.lzma\` - legacy lzma compressed file
+          - \`hello.txt.lz\` - lzip compressed file
 
           ### Other Formats
           - \`archive.7z\` - 7-Zip archive
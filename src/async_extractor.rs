@@ -0,0 +1,170 @@
+//! Async extraction for tokio-based code, behind the `async` feature.
+//!
+//! Decoding is CPU-bound, so it runs on tokio's blocking thread pool while
+//! entries are handed back to the async side as soon as each one has been
+//! read. Streamable formats (see [`ArchiveFormat::is_streamable`]) are decoded
+//! while the input is still arriving; ZIP, 7z, CAB and RAR are read into
+//! memory first. Size limits, patterns, filters, progress and cancellation all
+//! behave exactly as with [`ArchiveExtractor::extract_with_format`].
+
+use crate::error::{ArchiveError, Result};
+use crate::extractor::{ArchiveExtractor, ExtractedFile};
+use crate::format::ArchiveFormat;
+use std::io::{self, BufReader, Read};
+use tokio::io::AsyncRead;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::io::SyncIoBridge;
+
+/// How many extracted entries may wait to be picked up before extraction
+/// pauses.
+const CHANNEL_CAPACITY: usize = 4;
+
+/// Entries of an archive being extracted in the background.
+///
+/// Created by [`ArchiveExtractor::entries_async`]. Dropping it stops the
+/// extraction at the next entry or chunk of contents.
+///
+/// # Examples
+///
+/// ```no_run
+/// use archive::{ArchiveExtractor, ArchiveFormat};
+///
+/// # async fn run() -> archive::Result<()> {
+/// let file = tokio::fs::File::open("backup.tar.gz").await?;
+/// let mut entries = ArchiveExtractor::new().entries_async(file, ArchiveFormat::TarGz);
+/// while let Some(entry) = entries.next_entry().await {
+///     let entry = entry?;
+///     println!("{}: {} bytes", entry.path, entry.data.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsyncEntries {
+    receiver: mpsc::Receiver<Result<ExtractedFile>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl AsyncEntries {
+    /// Waits for the next entry.
+    ///
+    /// Returns `None` once every entry has been returned, or after an error
+    /// has been returned.
+    pub async fn next_entry(&mut self) -> Option<Result<ExtractedFile>> {
+        if let Some(entry) = self.receiver.recv().await {
+            return Some(entry);
+        }
+        // The worker has finished; only a panic is left to report.
+        match self.worker.take()?.await {
+            Ok(()) => None,
+            Err(err) => Some(Err(ArchiveError::Io(io::Error::other(err)))),
+        }
+    }
+
+    /// Waits for all remaining entries.
+    pub async fn collect(mut self) -> Result<Vec<ExtractedFile>> {
+        let mut files = Vec::new();
+        while let Some(entry) = self.next_entry().await {
+            files.push(entry?);
+        }
+        Ok(files)
+    }
+}
+
+impl ArchiveExtractor {
+    /// Extracts the archive read from `reader` as a stream of entries.
+    ///
+    /// Each entry is returned as soon as it has been read, so for streamable
+    /// formats the first entries are available before the rest of the input
+    /// has arrived.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a tokio runtime.
+    pub fn entries_async<R>(&self, reader: R, format: ArchiveFormat) -> AsyncEntries
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let extractor = self.clone();
+        let mut input = SyncIoBridge::new(reader);
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+
+        let worker = tokio::task::spawn_blocking(move || {
+            let entries = sender.clone();
+            let sink = Box::new(move |file| entries.blocking_send(Ok(file)).is_ok());
+            let result = if format.is_streamable() {
+                extractor.extract_reader_into(BufReader::new(input), format, sink)
+            } else {
+                let mut data = Vec::new();
+                match input.read_to_end(&mut data) {
+                    Ok(_) => extractor.extract_into(&data, format, sink),
+                    Err(err) => Err(err.into()),
+                }
+            };
+            if let Err(err) = result {
+                // Nobody is listening any more if this fails.
+                let _ = sender.blocking_send(Err(err));
+            }
+        });
+
+        AsyncEntries {
+            receiver,
+            worker: Some(worker),
+        }
+    }
+
+    /// Extracts all files from `reader` using the builder-configured format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveError::UnknownFormat`] if no format has been
+    /// configured. See
+    /// [`extract_with_format`](Self::extract_with_format) for other possible
+    /// errors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a tokio runtime.
+    pub async fn extract_async<R>(&self, reader: R) -> Result<Vec<ExtractedFile>>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let format = self.format.ok_or(ArchiveError::UnknownFormat)?;
+        self.extract_with_format_async(reader, format).await
+    }
+
+    /// Extracts all files from `reader` with an explicitly specified format.
+    ///
+    /// # Errors
+    ///
+    /// See [`extract_with_format`](Self::extract_with_format).
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use archive::{ArchiveExtractor, ArchiveFormat};
+    ///
+    /// # async fn run() -> archive::Result<()> {
+    /// let file = tokio::fs::File::open("example.zip").await?;
+    /// let files = ArchiveExtractor::new()
+    ///     .extract_with_format_async(file, ArchiveFormat::Zip)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn extract_with_format_async<R>(
+        &self,
+        reader: R,
+        format: ArchiveFormat,
+    ) -> Result<Vec<ExtractedFile>>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        self.entries_async(reader, format).collect().await
+    }
+}
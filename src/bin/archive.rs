@@ -0,0 +1,559 @@
+//! The `archive` command-line tool: lists, extracts, tests, detects and
+//! converts archives with the same rules as [`ArchiveExtractor`].
+//!
+//! Built with the `cli` feature.
+
+use archive::{
+    ArchiveConverter, ArchiveExtractor, ArchiveFormat, ArchiveWriter, CompressionLevel,
+    ConversionLoss, EntryHeader, ExtractedFile, MetadataField,
+};
+use clap::{Args, Parser, Subcommand};
+use serde_json::{Value, json};
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "archive",
+    version,
+    about = "List, extract, test and convert archives"
+)]
+struct Cli {
+    #[command(flatten)]
+    options: Options,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct Options {
+    /// Archive format as a file extension, such as `zip` or `tar.gz`
+    /// [default: detected from the file name]
+    #[arg(long, short, global = true, value_parser = parse_format)]
+    format: Option<ArchiveFormat>,
+
+    /// Largest entry to extract, such as `512K`, `100M` or `2G`
+    #[arg(long, global = true, value_parser = parse_size)]
+    max_file_size: Option<usize>,
+
+    /// Largest total size of all entries to extract
+    #[arg(long, global = true, value_parser = parse_size)]
+    max_total_size: Option<usize>,
+
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the entries of an archive
+    List {
+        /// The archive to read
+        archive: PathBuf,
+    },
+
+    /// Extract an archive into a directory
+    Extract {
+        /// The archive to read
+        archive: PathBuf,
+
+        /// Directory to extract into
+        #[arg(short = 'C', long = "directory", default_value = ".")]
+        directory: PathBuf,
+    },
+
+    /// Check every entry against the checksums stored in the archive
+    Test {
+        /// The archive to read
+        archive: PathBuf,
+    },
+
+    /// Print the format of an archive
+    Detect {
+        /// The archive to read
+        archive: PathBuf,
+    },
+
+    /// Convert an archive to another format
+    Convert {
+        /// The archive to read
+        input: PathBuf,
+
+        /// Where to write the converted archive
+        output: PathBuf,
+
+        /// Format to convert to [default: detected from the output file name]
+        #[arg(long, value_parser = parse_format)]
+        to: Option<ArchiveFormat>,
+
+        /// Compression level: `store`, `fastest`, `default`, `best` or a
+        /// number on the format's own scale
+        #[arg(long, value_parser = parse_level, default_value = "default")]
+        level: CompressionLevel,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(code) => code,
+        // Output piped into `head` and the like.
+        Err(err)
+            if err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("archive: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> CliResult<ExitCode> {
+    let options = &cli.options;
+    match &cli.command {
+        Command::List { archive } => list(options, archive),
+        Command::Extract { archive, directory } => extract(options, archive, directory),
+        Command::Test { archive } => test(options, archive),
+        Command::Detect { archive } => detect(options, archive),
+        Command::Convert {
+            input,
+            output,
+            to,
+            level,
+        } => convert(options, input, output, *to, *level),
+    }
+}
+
+fn list(options: &Options, path: &Path) -> CliResult<ExitCode> {
+    let mut out = io::stdout().lock();
+    let (data, extractor) = open(options, path)?;
+
+    // Rejecting every entry collects the headers without decompressing
+    // anything that can be skipped.
+    let headers = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&headers);
+    extractor
+        .with_filter(move |header| {
+            seen.lock().unwrap().push(header.clone());
+            false
+        })
+        .extract(&data)?;
+    let headers: Vec<EntryHeader> = std::mem::take(&mut headers.lock().unwrap());
+
+    if options.json {
+        let entries: Vec<Value> = headers.iter().map(header_json).collect();
+        writeln!(out, "{}", Value::Array(entries))?;
+    } else {
+        for header in &headers {
+            let size = header.size.map_or("-".to_string(), |size| size.to_string());
+            let (kind, link) = match &header.metadata.link_target {
+                _ if header.is_directory => ('d', String::new()),
+                Some(target) => ('l', format!(" -> {}", target)),
+                None => ('-', String::new()),
+            };
+            writeln!(out, "{} {:>12}  {}{}", kind, size, header.path, link)?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn extract(options: &Options, path: &Path, directory: &Path) -> CliResult<ExitCode> {
+    let mut out = io::stdout().lock();
+    let (data, extractor) = open(options, path)?;
+    let files = extractor.extract(&data)?;
+
+    // Every path is checked before anything is written, and links are
+    // created last so that no entry is written through one.
+    let targets = files
+        .iter()
+        .map(|file| output_path(directory, &file.path))
+        .collect::<CliResult<Vec<_>>>()?;
+    let (links, others): (Vec<_>, Vec<_>) = files
+        .iter()
+        .zip(&targets)
+        .partition(|(file, _)| file.is_symlink());
+    for (file, target) in others.into_iter().chain(links) {
+        write_entry(file, target)?;
+    }
+
+    if options.json {
+        let entries: Vec<Value> = files
+            .iter()
+            .zip(&targets)
+            .map(|(file, target)| {
+                json!({
+                    "path": file.path,
+                    "output": target.display().to_string(),
+                    "type": file_kind(file),
+                    "size": file.data.len(),
+                })
+            })
+            .collect();
+        writeln!(out, "{}", Value::Array(entries))?;
+    } else {
+        for target in &targets {
+            writeln!(out, "{}", target.display())?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn test(options: &Options, path: &Path) -> CliResult<ExitCode> {
+    let mut out = io::stdout().lock();
+    let (data, extractor) = open(options, path)?;
+    let report = extractor.test(&data)?;
+
+    if options.json {
+        let entries: Vec<Value> = report
+            .entries
+            .iter()
+            .map(|check| {
+                json!({
+                    "path": check.path,
+                    "size": check.size,
+                    "error": check.error.as_ref().map(ToString::to_string),
+                })
+            })
+            .collect();
+        writeln!(
+            out,
+            "{}",
+            json!({
+                "ok": report.is_ok(),
+                "entries": entries,
+                "error": report.error.as_ref().map(ToString::to_string),
+            })
+        )?;
+    } else {
+        for check in &report.entries {
+            match &check.error {
+                None => writeln!(out, "{}: OK", check.path)?,
+                Some(err) => writeln!(out, "{}: FAILED ({})", check.path, err)?,
+            }
+        }
+        if let Some(err) = &report.error {
+            writeln!(out, "{}", err)?;
+        }
+        match report.failures().count() {
+            _ if report.is_ok() => writeln!(out, "No errors detected in {}", path.display())?,
+            0 => {}
+            failed => writeln!(out, "{} of {} entries failed", failed, report.entries.len())?,
+        }
+    }
+
+    Ok(if report.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn detect(options: &Options, path: &Path) -> CliResult<ExitCode> {
+    let mut out = io::stdout().lock();
+    let data = read(path)?;
+    let format = resolve_format(options.format, path, &data)?;
+    if options.json {
+        writeln!(out, "{}", json!({ "format": format.name() }))?;
+    } else {
+        writeln!(out, "{}", format.name())?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn convert(
+    options: &Options,
+    input: &Path,
+    output: &Path,
+    to: Option<ArchiveFormat>,
+    level: CompressionLevel,
+) -> CliResult<ExitCode> {
+    let mut out = io::stdout().lock();
+    let data = read(input)?;
+    let from = resolve_format(options.format, input, &data)?;
+    let to = match to {
+        Some(format) => format,
+        None => ArchiveFormat::from_filename(&file_name(output)).map_err(|_| {
+            format!(
+                "{}: cannot tell the output format; pass --to",
+                output.display()
+            )
+        })?,
+    };
+
+    let conversion = ArchiveConverter::new()
+        .with_extractor(extractor(options, input, from))
+        .with_writer(ArchiveWriter::new().with_compression_level(level))
+        .convert(&data, from, to)?;
+    fs::write(output, &conversion.data).map_err(|err| format!("{}: {}", output.display(), err))?;
+
+    if options.json {
+        let losses: Vec<Value> = conversion.losses.iter().map(loss_json).collect();
+        writeln!(
+            out,
+            "{}",
+            json!({
+                "output": output.display().to_string(),
+                "format": to.name(),
+                "size": conversion.data.len(),
+                "losses": losses,
+            })
+        )?;
+    } else {
+        for loss in &conversion.losses {
+            eprintln!("warning: {}", describe_loss(loss));
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Reads an archive and sets up an extractor for it from the options.
+fn open(options: &Options, path: &Path) -> CliResult<(Vec<u8>, ArchiveExtractor)> {
+    let data = read(path)?;
+    let format = resolve_format(options.format, path, &data)?;
+    Ok((data, extractor(options, path, format)))
+}
+
+fn extractor(options: &Options, path: &Path, format: ArchiveFormat) -> ArchiveExtractor {
+    let mut extractor = ArchiveExtractor::new()
+        .with_source_filename(file_name(path))
+        .with_format(format);
+    if let Some(size) = options.max_file_size {
+        extractor = extractor.with_max_file_size(size);
+    }
+    if let Some(size) = options.max_total_size {
+        extractor = extractor.with_max_total_size(size);
+    }
+    extractor
+}
+
+fn read(path: &Path) -> CliResult<Vec<u8>> {
+    fs::read(path).map_err(|err| format!("{}: {}", path.display(), err).into())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Takes the format from `--format`, the file name, or, with a detection
+/// feature enabled, the contents.
+#[cfg_attr(
+    not(any(feature = "detect-libmagic", feature = "detect-infer")),
+    allow(unused_variables)
+)]
+fn resolve_format(
+    format: Option<ArchiveFormat>,
+    path: &Path,
+    data: &[u8],
+) -> CliResult<ArchiveFormat> {
+    if let Some(format) = format {
+        return Ok(format);
+    }
+    if let Ok(format) = ArchiveFormat::from_filename(&file_name(path)) {
+        return Ok(format);
+    }
+    #[cfg(any(feature = "detect-libmagic", feature = "detect-infer"))]
+    if let Ok(format) = ArchiveFormat::from_bytes(data) {
+        return Ok(format);
+    }
+    Err(format!(
+        "{}: cannot tell the archive format; pass --format",
+        path.display()
+    )
+    .into())
+}
+
+/// Maps an entry path into `root`, refusing anything that would end up
+/// outside it.
+fn output_path(root: &Path, entry: &str) -> CliResult<PathBuf> {
+    let mut path = root.to_path_buf();
+    for component in Path::new(entry).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(format!(
+                    "refusing to extract {:?} outside the target directory",
+                    entry
+                )
+                .into());
+            }
+        }
+    }
+    if path == root {
+        return Err(format!("refusing to extract {:?} onto the target directory", entry).into());
+    }
+    Ok(path)
+}
+
+fn write_entry(file: &ExtractedFile, target: &Path) -> CliResult<()> {
+    let context = |err: std::io::Error| format!("{}: {}", target.display(), err);
+    if file.is_directory {
+        return Ok(fs::create_dir_all(target).map_err(context)?);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(context)?;
+    }
+    if let Some(link_target) = &file.metadata.link_target {
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(link_target, target).map_err(context)?;
+        #[cfg(not(unix))]
+        eprintln!(
+            "warning: skipping symbolic link {} -> {}",
+            file.path, link_target
+        );
+        return Ok(());
+    }
+    fs::write(target, &file.data).map_err(context)?;
+    #[cfg(unix)]
+    if let Some(mode) = file.metadata.mode {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(target, fs::Permissions::from_mode(mode)).map_err(context)?;
+    }
+    Ok(())
+}
+
+fn file_kind(file: &ExtractedFile) -> &'static str {
+    if file.is_directory {
+        "directory"
+    } else if file.is_symlink() {
+        "symlink"
+    } else {
+        "file"
+    }
+}
+
+fn header_json(header: &EntryHeader) -> Value {
+    let metadata = &header.metadata;
+    let kind = if header.is_directory {
+        "directory"
+    } else if metadata.link_target.is_some() {
+        "symlink"
+    } else {
+        "file"
+    };
+    json!({
+        "path": header.path,
+        "type": kind,
+        "size": header.size,
+        "mtime": metadata.mtime,
+        "mode": metadata.mode,
+        "uid": metadata.uid,
+        "gid": metadata.gid,
+        "owner": metadata.owner,
+        "group": metadata.group,
+        "link_target": metadata.link_target,
+    })
+}
+
+fn field_name(field: MetadataField) -> &'static str {
+    match field {
+        MetadataField::Mtime => "mtime",
+        MetadataField::Mode => "mode",
+        MetadataField::Uid => "uid",
+        MetadataField::Gid => "gid",
+        MetadataField::Owner => "owner",
+        MetadataField::Group => "group",
+    }
+}
+
+fn loss_json(loss: &ConversionLoss) -> Value {
+    match loss {
+        ConversionLoss::Directory { path } => json!({ "type": "directory", "path": path }),
+        ConversionLoss::Symlink { path, target } => {
+            json!({ "type": "symlink", "path": path, "target": target })
+        }
+        ConversionLoss::Metadata { path, fields } => json!({
+            "type": "metadata",
+            "path": path,
+            "fields": fields.iter().map(|field| field_name(*field)).collect::<Vec<_>>(),
+        }),
+        ConversionLoss::Mtime {
+            path,
+            original,
+            stored,
+        } => json!({
+            "type": "mtime",
+            "path": path,
+            "original": original,
+            "stored": stored,
+        }),
+        ConversionLoss::Path { path, stored } => {
+            json!({ "type": "path", "path": path, "stored": stored })
+        }
+    }
+}
+
+fn describe_loss(loss: &ConversionLoss) -> String {
+    match loss {
+        ConversionLoss::Directory { path } => format!("{}: directory entry dropped", path),
+        ConversionLoss::Symlink { path, target } => {
+            format!("{}: symbolic link to {} dropped", path, target)
+        }
+        ConversionLoss::Metadata { path, fields } => {
+            let fields: Vec<&str> = fields.iter().map(|field| field_name(*field)).collect();
+            format!("{}: {} dropped", path, fields.join(", "))
+        }
+        ConversionLoss::Mtime {
+            path,
+            original,
+            stored,
+        } => format!(
+            "{}: modification time {} stored as {}",
+            path, original, stored
+        ),
+        ConversionLoss::Path { path, stored } => format!("{}: stored as {}", path, stored),
+    }
+}
+
+fn parse_format(name: &str) -> Result<ArchiveFormat, String> {
+    ArchiveFormat::from_filename(&format!("archive.{}", name.trim_start_matches('.')))
+        .map_err(|_| format!("unknown archive format {:?}", name))
+}
+
+/// Parses a byte count with an optional binary unit: `K`, `M`, `G` or `T`.
+fn parse_size(size: &str) -> Result<usize, String> {
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (digits, unit) = size.split_at(split);
+    let shift = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        "T" | "TB" | "TIB" => 40,
+        _ => return Err(format!("unknown size unit {:?}", unit)),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|count| count.checked_mul(1 << shift))
+        .ok_or_else(|| format!("invalid size {:?}", size))
+}
+
+fn parse_level(level: &str) -> Result<CompressionLevel, String> {
+    match level.to_ascii_lowercase().as_str() {
+        "store" => Ok(CompressionLevel::Store),
+        "fastest" => Ok(CompressionLevel::Fastest),
+        "default" => Ok(CompressionLevel::Default),
+        "best" => Ok(CompressionLevel::Best),
+        number => number
+            .parse()
+            .map(CompressionLevel::Level)
+            .map_err(|_| format!("invalid compression level {:?}", level)),
+    }
+}
@@ -0,0 +1,404 @@
+//! Conversion between archive formats.
+//!
+//! The main entry point is [`ArchiveConverter`], which reads an archive with
+//! an [`ArchiveExtractor`] and writes its entries back out with an
+//! [`ArchiveWriter`], reporting anything the target format cannot store.
+
+use crate::error::Result;
+use crate::extractor::{ArchiveExtractor, ExtractedFile};
+use crate::format::ArchiveFormat;
+use crate::timestamp;
+use crate::writer::ArchiveWriter;
+
+/// A metadata field that can be lost in a conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MetadataField {
+    /// The modification time.
+    Mtime,
+    /// The Unix permission bits.
+    Mode,
+    /// The numeric user ID.
+    Uid,
+    /// The numeric group ID.
+    Gid,
+    /// The owner's user name.
+    Owner,
+    /// The owner's group name.
+    Group,
+}
+
+/// Something the source archive held that the target format cannot store.
+/// With the `serde` feature, losses serialize with a `kind` tag of
+/// `"directory"`, `"symlink"`, `"metadata"`, `"mtime"` or `"path"`.
+///
+/// Hard links in TAR archives are not reported: extraction already turns
+/// them into empty regular files, which is what the target archive gets.
+/// Neither are the normalizations of the writer's deterministic mode, which
+/// are asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum ConversionLoss {
+    /// A directory entry was dropped. Files inside it are kept.
+    Directory {
+        /// Path of the dropped directory.
+        path: String,
+    },
+
+    /// A symbolic link was dropped.
+    Symlink {
+        /// Path of the dropped link.
+        path: String,
+        /// Target the link pointed at.
+        target: String,
+    },
+
+    /// An entry was kept, but some of its metadata was dropped.
+    Metadata {
+        /// Path of the entry.
+        path: String,
+        /// The fields that were dropped.
+        fields: Vec<MetadataField>,
+    },
+
+    /// An entry's modification time was kept, but changed to one the target
+    /// format can store: ZIP and CAB store times from 1980 to 2107 in
+    /// two-second steps, and RAR stores 32-bit Unix times.
+    Mtime {
+        /// Path of the entry.
+        path: String,
+        /// The modification time in the source archive.
+        original: u64,
+        /// The modification time as stored.
+        stored: u64,
+    },
+
+    /// An entry was stored under a different path. Gzip stores only the
+    /// file's base name.
+    Path {
+        /// Path of the entry in the source archive.
+        path: String,
+        /// The path as stored.
+        stored: String,
+    },
+}
+
+/// The result of converting an archive.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conversion {
+    /// The archive in the target format. Serialized as base64.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::base64_bytes"))]
+    pub data: Vec<u8>,
+
+    /// Everything that was lost on the way, in entry order. Empty if the
+    /// conversion was lossless.
+    pub losses: Vec<ConversionLoss>,
+}
+
+/// Converts archives from one format to another.
+///
+/// Entries are extracted with the configured [`ArchiveExtractor`], so its
+/// size limits apply, and written with the configured [`ArchiveWriter`],
+/// so its compression level and deterministic mode apply. Metadata is kept
+/// wherever both formats support it. Directories, symlinks and metadata
+/// fields the target format cannot represent are dropped and reported in
+/// [`Conversion::losses`].
+///
+/// Entries are handed to the writer one at a time, as they are extracted, so
+/// under [`DuplicatePolicy::KeepLast`](crate::DuplicatePolicy::KeepLast) a
+/// duplicate path fails the conversion.
+///
+/// # Memory Use
+///
+/// The source and target archives are held in memory. ZIP and TAR targets,
+/// compressed or not, are built as entries arrive, and each entry's contents
+/// are dropped once written; the XZ, LZMA, lzip and compress encoders take
+/// all of their input at once, so those TAR variants hold the uncompressed
+/// TAR stream until the end. Other targets, and deterministic output, which
+/// sorts the entries, hold the contents of every entry until all have been
+/// extracted; the extractor's
+/// [`with_max_total_size`](ArchiveExtractor::with_max_total_size) bounds
+/// those.
+///
+/// # Examples
+///
+/// ```
+/// use archive::{
+///     ArchiveConverter, ArchiveExtractor, ArchiveFormat, ArchiveWriter, ConversionLoss,
+///     ExtractedFile,
+/// };
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let entries = vec![
+///     ExtractedFile::file("hello.txt", b"Hello".to_vec()),
+///     ExtractedFile::symlink("link.txt", "hello.txt"),
+/// ];
+/// let tar = ArchiveWriter::new().write_with_format(&entries, ArchiveFormat::Tar)?;
+///
+/// let converter = ArchiveConverter::new()
+///     .with_extractor(ArchiveExtractor::new().with_max_total_size(10 * 1024 * 1024));
+/// let conversion = converter.convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Cab)?;
+///
+/// // CAB cannot store symlinks.
+/// assert!(matches!(
+///     &conversion.losses[..],
+///     [.., ConversionLoss::Symlink { path, .. }] if path == "link.txt"
+/// ));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveConverter {
+    extractor: ArchiveExtractor,
+    writer: ArchiveWriter,
+}
+
+impl ArchiveConverter {
+    /// Creates a converter with a default extractor and writer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the extractor used to read the source archive.
+    ///
+    /// Its total size limit bounds the decompressed contents a conversion
+    /// holds in memory; the source and target archives come on top of that.
+    pub fn with_extractor(mut self, extractor: ArchiveExtractor) -> Self {
+        self.extractor = extractor;
+        self
+    }
+
+    /// Sets the writer used to write the target archive.
+    pub fn with_writer(mut self, writer: ArchiveWriter) -> Self {
+        self.writer = writer;
+        self
+    }
+
+    /// Converts `data` from one archive format to another.
+    ///
+    /// See [Memory Use](Self#memory-use) for what is held in memory while
+    /// converting.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from extracting the source archive, including the
+    /// size limit errors, or from writing the target archive. Converting to
+    /// a single-file format fails with
+    /// [`ArchiveError::InvalidInput`](crate::ArchiveError::InvalidInput)
+    /// unless the source holds exactly one regular file.
+    pub fn convert(
+        &self,
+        data: &[u8],
+        from: ArchiveFormat,
+        to: ArchiveFormat,
+    ) -> Result<Conversion> {
+        let capabilities = Capabilities::of(to, self.writer.is_deterministic());
+        let mut losses = Vec::new();
+        let mut writer = self.writer.entry_writer(to)?;
+        let mut write_error = None;
+
+        let sink = Box::new(|entry| {
+            let Some(entry) = capabilities.adapt(entry, &mut losses) else {
+                return true;
+            };
+            match writer.add(entry) {
+                Ok(()) => true,
+                Err(err) => {
+                    write_error = Some(err);
+                    false
+                }
+            }
+        });
+        let extracted = self.extractor.extract_into(data, from, sink);
+        // A failed write stops the extraction, which then reports itself
+        // cancelled.
+        if let Some(err) = write_error {
+            return Err(err);
+        }
+        extracted?;
+
+        let data = writer.finish()?;
+        Ok(Conversion { data, losses })
+    }
+}
+
+/// Converts `data` from one archive format to another with default settings.
+///
+/// This is a shorthand for [`ArchiveConverter::convert`] with the default
+/// extractor limits and compression level.
+///
+/// # Examples
+///
+/// ```no_run
+/// use archive::ArchiveFormat;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let zip = std::fs::read("upload.zip")?;
+/// let conversion = archive::convert(&zip, ArchiveFormat::Zip, ArchiveFormat::TarZst)?;
+/// std::fs::write("upload.tar.zst", &conversion.data)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn convert(data: &[u8], from: ArchiveFormat, to: ArchiveFormat) -> Result<Conversion> {
+    ArchiveConverter::new().convert(data, from, to)
+}
+
+/// What a format can store, as far as [`ArchiveWriter`] writes it.
+struct Capabilities {
+    format: ArchiveFormat,
+    /// Whether the writer is in deterministic mode, which leaves gzip
+    /// headers without a name.
+    deterministic: bool,
+    directories: bool,
+    symlinks: bool,
+    fields: &'static [MetadataField],
+}
+
+impl Capabilities {
+    fn of(format: ArchiveFormat, deterministic: bool) -> Self {
+        use MetadataField::*;
+
+        let (directories, symlinks, fields): (bool, bool, &'static [MetadataField]) = match format {
+            ArchiveFormat::Tar
+            | ArchiveFormat::TarGz
+            | ArchiveFormat::TarBz2
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarZst
+            | ArchiveFormat::TarLz4
+            | ArchiveFormat::TarZ
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::TarLzip
+            | ArchiveFormat::TarBr
+            | ArchiveFormat::TarSz => (true, true, &[Mtime, Mode, Uid, Gid, Owner, Group]),
+            ArchiveFormat::Zip | ArchiveFormat::SevenZ | ArchiveFormat::Rar => {
+                (true, true, &[Mtime, Mode])
+            }
+            ArchiveFormat::Ar | ArchiveFormat::Deb => (false, false, &[Mtime, Mode, Uid, Gid]),
+            ArchiveFormat::Cab => (false, false, &[Mtime]),
+            ArchiveFormat::Gz => (false, false, &[Mtime]),
+            ArchiveFormat::Bz2
+            | ArchiveFormat::Xz
+            | ArchiveFormat::Lz4
+            | ArchiveFormat::Zst
+            | ArchiveFormat::Z
+            | ArchiveFormat::Lzma
+            | ArchiveFormat::Lzip
+            | ArchiveFormat::Br
+            | ArchiveFormat::Sz => (false, false, &[]),
+        };
+        Self {
+            format,
+            deterministic,
+            directories,
+            symlinks,
+            fields,
+        }
+    }
+
+    /// Prepares an extracted entry for the format, recording what is lost.
+    /// Returns `None` if the entry cannot be stored at all.
+    fn adapt(
+        &self,
+        mut entry: ExtractedFile,
+        losses: &mut Vec<ConversionLoss>,
+    ) -> Option<ExtractedFile> {
+        if entry.is_directory && !self.directories {
+            losses.push(ConversionLoss::Directory { path: entry.path });
+            return None;
+        }
+        if let Some(target) = entry.metadata.link_target.take() {
+            if !self.symlinks {
+                losses.push(ConversionLoss::Symlink {
+                    path: entry.path,
+                    target,
+                });
+                return None;
+            }
+            entry.metadata.link_target = Some(target);
+        }
+
+        let mut fields = self.drop_unsupported(&mut entry);
+        if let Some(original) = entry.metadata.mtime {
+            match self.stored_mtime(original) {
+                Some(stored) if stored != original => losses.push(ConversionLoss::Mtime {
+                    path: entry.path.clone(),
+                    original,
+                    stored,
+                }),
+                Some(_) => {}
+                None => {
+                    entry.metadata.mtime = None;
+                    fields.push(MetadataField::Mtime);
+                }
+            }
+        }
+        if !fields.is_empty() {
+            losses.push(ConversionLoss::Metadata {
+                path: entry.path.clone(),
+                fields,
+            });
+        }
+        if self.format == ArchiveFormat::Gz && !self.deterministic {
+            let name = entry.path.rsplit('/').next().unwrap_or_default();
+            if name != entry.path {
+                losses.push(ConversionLoss::Path {
+                    path: entry.path.clone(),
+                    stored: name.to_string(),
+                });
+            }
+        }
+        Some(entry)
+    }
+
+    /// Returns the modification time the format stores for `mtime`, or
+    /// `None` if it cannot store it at all.
+    fn stored_mtime(&self, mtime: u64) -> Option<u64> {
+        match self.format {
+            ArchiveFormat::Zip | ArchiveFormat::Cab => Some(timestamp::dos_round_trip(mtime)),
+            ArchiveFormat::Rar => Some(mtime.min(u64::from(u32::MAX))),
+            // Gzip headers hold 32-bit times, and zero means "not set".
+            ArchiveFormat::Gz => u32::try_from(mtime)
+                .ok()
+                .filter(|&mtime| mtime != 0)
+                .map(u64::from),
+            _ => Some(mtime),
+        }
+    }
+
+    /// Clears the metadata fields the format cannot store, returning the
+    /// ones that were set.
+    fn drop_unsupported(&self, entry: &mut ExtractedFile) -> Vec<MetadataField> {
+        let metadata = &mut entry.metadata;
+        let mut dropped = Vec::new();
+        let mut check = |field: MetadataField, is_set: bool| {
+            let lost = is_set && !self.fields.contains(&field);
+            if lost {
+                dropped.push(field);
+            }
+            lost
+        };
+
+        if check(MetadataField::Mtime, metadata.mtime.is_some()) {
+            metadata.mtime = None;
+        }
+        if check(MetadataField::Mode, metadata.mode.is_some()) {
+            metadata.mode = None;
+        }
+        if check(MetadataField::Uid, metadata.uid.is_some()) {
+            metadata.uid = None;
+        }
+        if check(MetadataField::Gid, metadata.gid.is_some()) {
+            metadata.gid = None;
+        }
+        if check(MetadataField::Owner, metadata.owner.is_some()) {
+            metadata.owner = None;
+        }
+        if check(MetadataField::Group, metadata.group.is_some()) {
+            metadata.group = None;
+        }
+        dropped
+    }
+}
@@ -0,0 +1,130 @@
+//! Handling of entries that share a path.
+//!
+//! ZIP and TAR archives may store the same path more than once, and paths
+//! that differ only in case or Unicode normalization end up as the same file
+//! on many file systems. Which copy a tool shows depends on the tool, so
+//! [`ArchiveExtractor::with_duplicate_policy`](crate::ArchiveExtractor::with_duplicate_policy)
+//! makes the choice explicit.
+
+use crate::extractor::entry_key;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
+
+/// What to do with an entry whose path an earlier entry already has.
+///
+/// Repeated directory entries never count as duplicates; a directory and a
+/// file at the same path do.
+///
+/// # Examples
+///
+/// ```
+/// use archive::{ArchiveExtractor, ArchiveFormat, ArchiveWriter, DuplicatePolicy, ExtractedFile};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = ArchiveWriter::new().write_with_format(
+///     &[
+///         ExtractedFile::file("README", b"first".to_vec()),
+///         ExtractedFile::file("readme", b"second".to_vec()),
+///     ],
+///     ArchiveFormat::Tar,
+/// )?;
+///
+/// let files = ArchiveExtractor::new()
+///     .with_duplicate_policy(DuplicatePolicy::KeepLast)
+///     .with_case_folding(true)
+///     .extract_with_format(&data, ArchiveFormat::Tar)?;
+/// assert_eq!(files.len(), 1);
+/// assert_eq!(files[0].data, b"second");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Returns every entry as stored.
+    #[default]
+    KeepAll,
+
+    /// Fails with [`ArchiveError::DuplicateEntry`](crate::ArchiveError::DuplicateEntry)
+    /// as soon as a duplicate is found, before its contents are read.
+    Error,
+
+    /// Keeps the first entry with a path and skips the others without
+    /// reading them.
+    KeepFirst,
+
+    /// Keeps the last entry with a path, as extracting to disk in archive
+    /// order would. Earlier copies are still read, and count toward the
+    /// size limits.
+    KeepLast,
+}
+
+/// How paths are compared when looking for duplicates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct PathFolding {
+    pub(crate) case: bool,
+    pub(crate) unicode: bool,
+}
+
+impl PathFolding {
+    /// Returns the form of `path` that equal paths share.
+    pub(crate) fn key(self, path: &str) -> String {
+        let path = entry_key(path);
+        match (self.unicode, self.case) {
+            (false, false) => path.to_string(),
+            (false, true) => path.to_lowercase(),
+            (true, false) => path.nfc().collect(),
+            // Lowercasing can denormalize, so normalize on both sides.
+            (true, true) => path
+                .nfc()
+                .collect::<String>()
+                .to_lowercase()
+                .nfc()
+                .collect(),
+        }
+    }
+}
+
+/// The paths seen so far in an extraction.
+#[derive(Debug)]
+pub(crate) struct SeenPaths {
+    folding: PathFolding,
+    paths: HashMap<String, (String, bool)>,
+}
+
+impl SeenPaths {
+    pub(crate) fn new(folding: PathFolding) -> Self {
+        Self {
+            folding,
+            paths: HashMap::new(),
+        }
+    }
+
+    /// Records an entry, returning the path of the earlier entry it
+    /// duplicates, if any.
+    pub(crate) fn insert(&mut self, path: &str, is_directory: bool) -> Option<String> {
+        match self.paths.entry(self.folding.key(path)) {
+            Entry::Occupied(entry) => {
+                let (existing, existing_is_directory) = entry.get();
+                (!(is_directory && *existing_is_directory)).then(|| existing.clone())
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((path.to_string(), is_directory));
+                None
+            }
+        }
+    }
+}
+
+/// Marks the last entry for each path, going by `paths` in archive order.
+pub(crate) fn last_occurrences<'a>(
+    folding: PathFolding,
+    paths: impl DoubleEndedIterator<Item = &'a str> + ExactSizeIterator,
+) -> Vec<bool> {
+    let mut keep = vec![false; paths.len()];
+    let mut seen = HashSet::new();
+    for (i, path) in paths.enumerate().rev() {
+        keep[i] = seen.insert(folding.key(path));
+    }
+    keep
+}
@@ -0,0 +1,76 @@
+//! Decoding of entry names that are not UTF-8.
+//!
+//! Archive formats store names as bytes. Modern tools write UTF-8, but
+//! archives from older Windows and East Asian tools use the system code page,
+//! and most formats don't record which. Names are read as UTF-8 when they
+//! are valid UTF-8, and otherwise decoded with the encoding set with
+//! [`ArchiveExtractor::with_name_encoding`](crate::ArchiveExtractor::with_name_encoding).
+
+/// A legacy encoding for entry names that are not valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use archive::NameEncoding;
+///
+/// assert_eq!(NameEncoding::ShiftJis.decode(b"\x83\x65\x83\x58\x83\x67"), "テスト");
+/// assert_eq!(NameEncoding::Cp437.decode(b"Cura\x87ao"), "Curaçao");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameEncoding {
+    /// IBM code page 437, the original DOS code page, which ZIP specifies
+    /// for names not marked as UTF-8.
+    Cp437,
+
+    /// Shift-JIS (Windows code page 932), used by Japanese Windows.
+    ShiftJis,
+
+    /// GBK (Windows code page 936), used by Simplified Chinese Windows.
+    Gbk,
+}
+
+impl NameEncoding {
+    /// Decodes `bytes`, replacing anything that isn't valid in the encoding
+    /// with U+FFFD.
+    pub fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Cp437 => bytes
+                .iter()
+                .map(|&byte| match byte {
+                    0..0x80 => char::from(byte),
+                    _ => CP437_HIGH[usize::from(byte - 0x80)],
+                })
+                .collect(),
+            Self::ShiftJis => encoding_rs::SHIFT_JIS
+                .decode_without_bom_handling(bytes)
+                .0
+                .into_owned(),
+            Self::Gbk => encoding_rs::GBK
+                .decode_without_bom_handling(bytes)
+                .0
+                .into_owned(),
+        }
+    }
+}
+
+/// Decodes a stored name: as UTF-8 if it is valid UTF-8, and otherwise with
+/// `fallback`, or as UTF-8 with U+FFFD replacements without one.
+pub(crate) fn decode_name(bytes: &[u8], fallback: Option<NameEncoding>) -> String {
+    match (std::str::from_utf8(bytes), fallback) {
+        (Ok(name), _) => name.to_string(),
+        (Err(_), Some(encoding)) => encoding.decode(bytes),
+        (Err(_), None) => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// The characters for bytes 0x80 to 0xFF in code page 437. The lower half
+/// is ASCII.
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ',
+    'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ',
+    'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕',
+    '╣', '║', '╗', '╝', '╜', '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦',
+    '╠', '═', '╬', '╧', '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐',
+    '▀', 'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±',
+    '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
@@ -1,8 +1,9 @@
 //! Error types for archive operations.
 //!
 //! This module provides error types used throughout the crate for handling
-//! various failure scenarios during archive extraction.
+//! various failure scenarios during archive extraction and creation.
 
+use crate::validation::{self, ZipAnomaly};
 use std::io;
 use thiserror::Error;
 
@@ -22,7 +23,7 @@ use thiserror::Error;
 /// ```
 pub type Result<T> = std::result::Result<T, ArchiveError>;
 
-/// Errors that can occur during archive extraction.
+/// Errors that can occur during archive extraction and creation.
 ///
 /// This enum represents all possible errors that can occur when working with
 /// archives, including I/O errors, format-specific errors, and safety limit violations.
@@ -124,4 +125,94 @@ pub enum ArchiveError {
     /// The string contains details about what is unsupported.
     #[error("Unsupported format: {0}")]
     UnsupportedFormat(String),
+
+    /// The entries passed to [`ArchiveWriter`](crate::ArchiveWriter) cannot be
+    /// written in the requested format.
+    ///
+    /// This error occurs, for example, when a single-file compression format
+    /// is given anything other than exactly one regular file.
+    ///
+    /// The string describes what was wrong with the input.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The entry requested from
+    /// [`ArchiveExtractor::extract_entry`](crate::ArchiveExtractor::extract_entry)
+    /// is not in the archive.
+    ///
+    /// The string is the requested path.
+    #[error("Entry not found: {0}")]
+    EntryNotFound(String),
+
+    /// The extraction was stopped through its
+    /// [`CancellationToken`](crate::CancellationToken).
+    #[error("Extraction cancelled")]
+    Cancelled,
+
+    /// An entry's contents or header do not match the checksum stored for
+    /// them in the archive.
+    ///
+    /// This is reported for the CRC-32 of ZIP and 7z entries, the header
+    /// checksum of TAR entries, and the CRCs of RAR entries.
+    ///
+    /// # Fields
+    ///
+    /// - `path`: The path of the entry that failed the check
+    /// - `expected`: The checksum stored in the archive
+    /// - `actual`: The checksum of the data actually read
+    #[error("Checksum mismatch for {path}: expected {expected:#010x}, got {actual:#010x}")]
+    ChecksumMismatch {
+        /// The path of the entry that failed the check
+        path: String,
+        /// The checksum stored in the archive
+        expected: u32,
+        /// The checksum of the data actually read
+        actual: u32,
+    },
+
+    /// Two entries of a ZIP archive share bytes of the archive.
+    ///
+    /// Overlapping-file zip bombs point many central directory records at
+    /// the same compressed data, so that a small archive inflates to many
+    /// copies of it. Archives like that are rejected before any entry is
+    /// decompressed.
+    ///
+    /// # Fields
+    ///
+    /// - `path`: The entry whose header or data starts inside another entry
+    /// - `other`: The entry it overlaps
+    #[error("Overlapping entries: {path} overlaps {other}")]
+    OverlappingEntries {
+        /// The entry whose header or data starts inside another entry
+        path: String,
+        /// The entry it overlaps
+        other: String,
+    },
+
+    /// A ZIP archive reads differently depending on the tool, and
+    /// [`ArchiveExtractor::with_strict_zip`](crate::ArchiveExtractor::with_strict_zip)
+    /// is enabled.
+    ///
+    /// The vector holds everything that was found, as returned by
+    /// [`ArchiveExtractor::validate_zip`](crate::ArchiveExtractor::validate_zip).
+    #[error("Ambiguous ZIP archive: {}", validation::describe(.0))]
+    AmbiguousZip(Vec<ZipAnomaly>),
+
+    /// An entry has the same path as an earlier one, and the
+    /// [`DuplicatePolicy`](crate::DuplicatePolicy) does not allow it.
+    ///
+    /// With case folding or Unicode normalization enabled, the two paths may
+    /// differ in spelling.
+    ///
+    /// # Fields
+    ///
+    /// - `path`: The path of the later entry
+    /// - `existing`: The path of the earlier entry
+    #[error("Duplicate entry: {path} collides with {existing}")]
+    DuplicateEntry {
+        /// The path of the later entry
+        path: String,
+        /// The path of the earlier entry
+        existing: String,
+    },
 }
@@ -4,9 +4,27 @@
 //! archive formats. The main entry point is [`ArchiveExtractor`], which can
 //! extract files from any supported format into memory.
 
+use crate::duplicates::{self, DuplicatePolicy, PathFolding, SeenPaths};
+use crate::encoding::{self, NameEncoding};
 use crate::error::{ArchiveError, Result};
 use crate::format::ArchiveFormat;
-use std::io::{Cursor, Read};
+use crate::glob::Glob;
+use crate::hash::{Digest, HashAlgorithm, HashedEntry, Hashers};
+use crate::integrity::{EntryCheck, IntegrityReport};
+use crate::library;
+use crate::lzip;
+use crate::lzw::LzwDecoder;
+use crate::progress::{CancellationToken, Progress};
+use crate::timestamp;
+use crate::validation::{self, ZipAnomaly};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead, Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::Arc;
 
 /// Represents a single file extracted from an archive.
 ///
@@ -34,19 +52,33 @@ use std::io::{Cursor, Read};
 /// # Ok(())
 /// # }
 /// ```
+///
+/// New fields may be added in minor releases, so entries are built with
+/// [`file`](Self::file), [`directory`](Self::directory) and
+/// [`symlink`](Self::symlink) rather than struct literals.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtractedFile {
     /// The original path of the file within the archive.
     ///
-    /// For multi-file archives (ZIP, TAR, 7-Zip), this is the path as stored
-    /// in the archive. For single-file compression formats:
-    /// - **Gzip**: The original filename from the header, or "data" if not present
-    /// - **Bzip2, XZ, LZ4, Zstandard**: Always "data" as these formats don't store filenames
+    /// For multi-file archives (ZIP, TAR, 7-Zip, CAB, RAR), this is the path as
+    /// stored in the archive, with backslash separators from CAB and legacy RAR
+    /// archives converted to `/`.
+    /// For single-file compression formats:
+    /// - **Gzip**: The original filename from the header without its directories,
+    ///   or the source filename without `.gz`, in the order set with
+    ///   [`ArchiveExtractor::with_gzip_name_precedence`], or "data". Header names
+    ///   with `..` components, control characters or no file name are ignored.
+    /// - **Bzip2, XZ, LZ4, Zstandard, compress, LZMA, lzip, Brotli, Snappy**: Always "data"
+    ///   as these formats don't store filenames
     pub path: String,
 
     /// The decompressed contents of the file.
     ///
-    /// For directories, this will be an empty vector.
+    /// For directories, this will be an empty vector. Serialized as base64;
+    /// serialize [`header`](Self::header) instead to leave the contents out.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::base64_bytes"))]
     pub data: Vec<u8>,
 
     /// Whether this entry represents a directory.
@@ -54,6 +86,277 @@ pub struct ExtractedFile {
     /// If `true`, the `data` field will be empty and `path` represents a directory.
     /// If `false`, this is a regular file with content in `data`.
     pub is_directory: bool,
+
+    /// Timestamps, permissions, ownership and link information, as far as
+    /// the archive format records them.
+    pub metadata: EntryMetadata,
+
+    /// Digests of the contents, one per algorithm added with
+    /// [`ArchiveExtractor::with_hash`], in that order.
+    ///
+    /// Empty unless hashing was asked for. Ignored when writing archives.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub digests: Vec<Digest>,
+}
+
+impl ExtractedFile {
+    /// Creates a regular file entry with default metadata.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ExtractedFile;
+    ///
+    /// let file = ExtractedFile::file("docs/readme.txt", b"Hello".to_vec());
+    /// assert!(!file.is_directory);
+    /// ```
+    pub fn file(path: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            path: path.into(),
+            data: data.into(),
+            is_directory: false,
+            metadata: EntryMetadata::default(),
+            digests: Vec::new(),
+        }
+    }
+
+    /// Creates a directory entry with default metadata.
+    pub fn directory(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            data: Vec::new(),
+            is_directory: true,
+            metadata: EntryMetadata::default(),
+            digests: Vec::new(),
+        }
+    }
+
+    /// Creates a symbolic link entry pointing at `target`.
+    pub fn symlink(path: impl Into<String>, target: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            data: Vec::new(),
+            is_directory: false,
+            metadata: EntryMetadata {
+                link_target: Some(target.into()),
+                ..EntryMetadata::default()
+            },
+            digests: Vec::new(),
+        }
+    }
+
+    /// Replaces the entry's metadata.
+    ///
+    /// This method uses the builder pattern, allowing you to chain configuration calls.
+    pub fn with_metadata(mut self, metadata: EntryMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Returns `true` if this entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.metadata.link_target.is_some()
+    }
+
+    /// Returns the digest computed with `algorithm`, if it was asked for.
+    pub fn digest(&self, algorithm: HashAlgorithm) -> Option<&Digest> {
+        self.digests.iter().find(|d| d.algorithm == algorithm)
+    }
+
+    /// Returns the entry's header: its path, type, size and metadata,
+    /// without the contents.
+    ///
+    /// This is the listing form of an entry, e.g. for serializing with the
+    /// `serde` feature when the contents are not wanted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ExtractedFile;
+    ///
+    /// let header = ExtractedFile::file("hello.txt", b"Hello".to_vec()).header();
+    /// assert_eq!(header.size, Some(5));
+    /// ```
+    pub fn header(&self) -> EntryHeader {
+        EntryHeader {
+            path: self.path.clone(),
+            is_directory: self.is_directory,
+            size: Some(self.data.len() as u64),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+/// A file extracted from an archive whose contents may point straight into
+/// the archive data.
+///
+/// Returned by [`ArchiveExtractor::extract_borrowed`]. Entries stored without
+/// compression in TAR, AR and ZIP archives borrow their contents from the
+/// input; everything else owns them as in [`ExtractedFile`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use archive::{ArchiveExtractor, ArchiveFormat};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = std::fs::read("backup.tar")?;
+/// let files = ArchiveExtractor::new().extract_borrowed_with_format(&data, ArchiveFormat::Tar)?;
+/// for file in &files {
+///     println!("{}: {} bytes, copied: {}", file.path, file.data.len(), !file.is_borrowed());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BorrowedFile<'a> {
+    /// The path of the file within the archive, as in [`ExtractedFile::path`].
+    pub path: String,
+
+    /// The contents of the file, borrowed from the archive data where
+    /// possible. Serialized as base64.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_impls::base64_bytes::serialize")
+    )]
+    pub data: Cow<'a, [u8]>,
+
+    /// Whether this entry represents a directory.
+    pub is_directory: bool,
+
+    /// Timestamps, permissions, ownership and link information, as far as
+    /// the archive format records them.
+    pub metadata: EntryMetadata,
+
+    /// Digests of the contents, as in [`ExtractedFile::digests`].
+    pub digests: Vec<Digest>,
+}
+
+impl BorrowedFile<'_> {
+    /// Returns `true` if the contents point into the archive data rather than
+    /// being a copy.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.data, Cow::Borrowed(_))
+    }
+
+    /// Returns `true` if this entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.metadata.link_target.is_some()
+    }
+
+    /// Copies borrowed contents, if any, into an [`ExtractedFile`].
+    pub fn into_owned(self) -> ExtractedFile {
+        ExtractedFile {
+            path: self.path,
+            data: self.data.into_owned(),
+            is_directory: self.is_directory,
+            metadata: self.metadata,
+            digests: self.digests,
+        }
+    }
+}
+
+/// Metadata stored alongside an archive entry.
+///
+/// Every field is optional because formats differ widely in what they
+/// record: TAR keeps everything, ZIP keeps a timestamp and permissions, and
+/// single-file compression formats keep next to nothing. Fields a format
+/// does not store are left as `None` on extraction and ignored on creation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct EntryMetadata {
+    /// Modification time in seconds since the Unix epoch.
+    pub mtime: Option<u64>,
+
+    /// Unix permission bits, e.g. `0o644`, without the file type bits.
+    pub mode: Option<u32>,
+
+    /// Numeric user ID of the owner.
+    pub uid: Option<u64>,
+
+    /// Numeric group ID of the owner.
+    pub gid: Option<u64>,
+
+    /// User name of the owner.
+    pub owner: Option<String>,
+
+    /// Group name of the owner.
+    pub group: Option<String>,
+
+    /// Target of a symbolic link, or `None` for anything else.
+    pub link_target: Option<String>,
+
+    /// The entry's name exactly as stored, before decoding, for formats that
+    /// store names as bytes (ZIP, TAR, AR and gzip). Ignored on creation.
+    /// Serialized as base64.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_impls::base64_bytes::option")
+    )]
+    pub raw_name: Option<Vec<u8>>,
+
+    /// Comment stored with the entry, from gzip headers. Ignored on
+    /// creation.
+    pub comment: Option<String>,
+
+    /// The operating system the entry was written on, as the OS byte of
+    /// gzip headers: 0 for FAT, 3 for Unix, 11 for NTFS, 255 for unknown.
+    /// Ignored on creation.
+    pub os: Option<u8>,
+
+    /// The extra field of gzip headers, as stored. Ignored on creation.
+    /// Serialized as base64.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_impls::base64_bytes::option")
+    )]
+    pub extra: Option<Vec<u8>>,
+}
+
+/// What is known about an archive entry before its contents are read.
+///
+/// This is what [`ArchiveExtractor::with_filter`] callbacks are given to
+/// decide whether an entry is extracted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryHeader {
+    /// The path of the entry, as it will appear in [`ExtractedFile::path`].
+    pub path: String,
+
+    /// Whether the entry is a directory.
+    pub is_directory: bool,
+
+    /// The uncompressed size declared by the archive, or `None` for
+    /// single-file compression formats, which only learn the size by
+    /// decompressing.
+    pub size: Option<u64>,
+
+    /// The entry's metadata. Symlink targets that ZIP and 7z store as the
+    /// entry's contents are not known yet.
+    pub metadata: EntryMetadata,
+}
+
+/// Where the name of a gzip file's entry comes from.
+///
+/// A gzip header may name the compressed file, and
+/// [`ArchiveExtractor::with_source_filename`] gives the name of the `.gz`
+/// file itself, from which the entry is named by dropping the extension.
+/// Without either, the entry is named `data`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GzipNamePrecedence {
+    /// Uses the header name, and the source filename only when the header
+    /// has none.
+    #[default]
+    Header,
+
+    /// Uses the source filename, and the header name only when the source
+    /// filename does not end in `.gz`.
+    SourceFilename,
+
+    /// Never uses the header name.
+    SourceFilenameOnly,
 }
 
 /// Main extractor that handles all archive formats.
@@ -129,7 +432,41 @@ pub struct ArchiveExtractor {
     max_file_size: usize,
     max_total_size: usize,
     source_filename: Option<String>,
-    format: Option<ArchiveFormat>,
+    pub(crate) format: Option<ArchiveFormat>,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    filter: Option<EntryFilter>,
+    progress: Option<ProgressCallback>,
+    cancellation: Option<CancellationToken>,
+    hashes: Vec<HashAlgorithm>,
+    strict_zip: bool,
+    duplicates: DuplicatePolicy,
+    path_folding: PathFolding,
+    name_encoding: Option<NameEncoding>,
+    gzip_name: GzipNamePrecedence,
+    #[cfg(feature = "parallel")]
+    parallel: bool,
+}
+
+/// A caller-supplied entry filter, wrapped so that the extractor stays
+/// `Debug` and `Clone`.
+#[derive(Clone)]
+struct EntryFilter(Arc<dyn Fn(&EntryHeader) -> bool + Send + Sync>);
+
+impl fmt::Debug for EntryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EntryFilter(..)")
+    }
+}
+
+/// A caller-supplied progress callback, wrapped like [`EntryFilter`].
+#[derive(Clone)]
+struct ProgressCallback(Arc<dyn Fn(&Progress<'_>) + Send + Sync>);
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback(..)")
+    }
 }
 
 impl Default for ArchiveExtractor {
@@ -139,6 +476,19 @@ impl Default for ArchiveExtractor {
             max_total_size: 1024 * 1024 * 1024, // 1 GB total
             source_filename: None,
             format: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            filter: None,
+            progress: None,
+            cancellation: None,
+            hashes: Vec::new(),
+            strict_zip: false,
+            duplicates: DuplicatePolicy::default(),
+            path_folding: PathFolding::default(),
+            name_encoding: None,
+            gzip_name: GzipNamePrecedence::default(),
+            #[cfg(feature = "parallel")]
+            parallel: false,
         }
     }
 }
@@ -226,7 +576,7 @@ impl ArchiveExtractor {
     /// Sets the source filename for the archive.
     ///
     /// This is used to derive output filenames for single-file compression
-    /// formats (Gz, Bz2, Xz, Lz4, Zst) by stripping the compression extension.
+    /// formats (Gz, Bz2, Xz, Lz4, Zst, Z, Lzma, Lzip, Br, Sz) by stripping the compression extension.
     /// For example, `"hello.txt.bz2"` produces an output path of `"hello.txt"`.
     ///
     /// For gzip, the header filename still takes priority; the source filename
@@ -245,6 +595,322 @@ impl ArchiveExtractor {
         self
     }
 
+    /// Only extracts entries whose path matches a glob pattern.
+    ///
+    /// May be called repeatedly; an entry is extracted if it matches any of
+    /// the include patterns. Patterns match the whole path, ignoring a leading
+    /// `./` and the trailing slash of directories. `*` and `?` do not match
+    /// `/`, `**` matches any number of path components, and `[a-z]` matches a
+    /// character class.
+    ///
+    /// Filtering happens before an entry's contents are read, so skipped
+    /// entries use no memory and do not count toward the size limits. Entries
+    /// inside a solid 7z block are still decompressed to reach the ones after
+    /// them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ArchiveExtractor;
+    ///
+    /// let extractor = ArchiveExtractor::new()
+    ///     .with_include("**/*.json")
+    ///     .with_include("src/**");
+    /// ```
+    pub fn with_include(mut self, pattern: &str) -> Self {
+        self.include.push(Glob::new(pattern));
+        self
+    }
+
+    /// Skips entries whose path matches a glob pattern.
+    ///
+    /// May be called repeatedly; an entry is skipped if it matches any of the
+    /// exclude patterns, even if it matches an include pattern. See
+    /// [`with_include`](Self::with_include) for the pattern syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ArchiveExtractor;
+    ///
+    /// let extractor = ArchiveExtractor::new()
+    ///     .with_include("src/**")
+    ///     .with_exclude("**/*.bak");
+    /// ```
+    pub fn with_exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(Glob::new(pattern));
+        self
+    }
+
+    /// Only extracts entries for which `filter` returns `true`.
+    ///
+    /// The filter sees each entry's [`EntryHeader`] before its contents are
+    /// read, and is applied after the include and exclude patterns. Setting a
+    /// filter replaces any previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ArchiveExtractor;
+    ///
+    /// // Skip directories and anything over 1 MB.
+    /// let extractor = ArchiveExtractor::new().with_filter(|header| {
+    ///     !header.is_directory && header.size.is_some_and(|size| size <= 1024 * 1024)
+    /// });
+    /// ```
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&EntryHeader) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(EntryFilter(Arc::new(filter)));
+        self
+    }
+
+    /// Calls `callback` as the extraction progresses.
+    ///
+    /// The callback receives a [`Progress`] snapshot after every entry and
+    /// after every megabyte of contents within an entry. It runs on the
+    /// extracting thread, so it should return quickly. Setting a callback
+    /// replaces any previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ArchiveExtractor;
+    ///
+    /// let extractor = ArchiveExtractor::new().with_progress(|progress| {
+    ///     if let Some(path) = progress.current_path {
+    ///         eprintln!("{} ({} bytes so far)", path, progress.bytes_out);
+    ///     }
+    /// });
+    /// ```
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Progress<'_>) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Stops the extraction with [`ArchiveError::Cancelled`] once `token` is
+    /// cancelled.
+    ///
+    /// The token is checked between entries and while an entry's contents
+    /// are decompressed, so even a single huge entry can be interrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveExtractor, CancellationToken};
+    ///
+    /// let token = CancellationToken::new();
+    /// let extractor = ArchiveExtractor::new().with_cancellation(token.clone());
+    ///
+    /// // Later, from another thread:
+    /// token.cancel();
+    /// ```
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Computes a digest of every entry's contents with `algorithm` as they
+    /// are decompressed, stored in [`ExtractedFile::digests`].
+    ///
+    /// May be called repeatedly to compute several digests in the same pass.
+    /// See [`hash_with_format`](Self::hash_with_format) to get only the
+    /// digests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveExtractor, ArchiveFormat, ArchiveWriter, ExtractedFile, HashAlgorithm};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data = ArchiveWriter::new().write_with_format(
+    ///     &[ExtractedFile::file("hello.txt", b"hello".to_vec())],
+    ///     ArchiveFormat::Zip,
+    /// )?;
+    /// let files = ArchiveExtractor::new()
+    ///     .with_hash(HashAlgorithm::Sha256)
+    ///     .extract_with_format(&data, ArchiveFormat::Zip)?;
+    /// assert_eq!(
+    ///     files[0].digest(HashAlgorithm::Sha256).unwrap().to_hex(),
+    ///     "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_hash(mut self, algorithm: HashAlgorithm) -> Self {
+        if !self.hashes.contains(&algorithm) {
+            self.hashes.push(algorithm);
+        }
+        self
+    }
+
+    /// Refuses ZIP archives that read differently depending on the tool.
+    ///
+    /// When enabled, every ZIP archive is checked with
+    /// [`validate_zip`](Self::validate_zip) before anything is extracted, and
+    /// any anomaly fails the extraction with
+    /// [`ArchiveError::AmbiguousZip`]. Disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat, ArchiveWriter, ExtractedFile};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut data = ArchiveWriter::new().write_with_format(
+    ///     &[ExtractedFile::file("hello.txt", b"hello".to_vec())],
+    ///     ArchiveFormat::Zip,
+    /// )?;
+    /// data.extend_from_slice(b"appended");
+    ///
+    /// let result = ArchiveExtractor::new()
+    ///     .with_strict_zip(true)
+    ///     .extract_with_format(&data, ArchiveFormat::Zip);
+    /// assert!(matches!(result, Err(ArchiveError::AmbiguousZip(_))));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_strict_zip(mut self, strict: bool) -> Self {
+        self.strict_zip = strict;
+        self
+    }
+
+    /// Sets what happens to entries whose path an earlier entry already has.
+    ///
+    /// By default every entry is returned. See [`DuplicatePolicy`] for the
+    /// alternatives, and [`with_case_folding`](Self::with_case_folding) and
+    /// [`with_unicode_normalization`](Self::with_unicode_normalization) for
+    /// paths that are spelled differently but collide on disk.
+    ///
+    /// The policy applies to the entries that pass the include/exclude
+    /// patterns and filter. The ZIP reader keeps only the last of several
+    /// central directory records with exactly the same name, so such ZIP
+    /// archives fail with [`ArchiveError::DuplicateEntry`] under both
+    /// [`DuplicatePolicy::Error`] and [`DuplicatePolicy::KeepFirst`]. The CAB
+    /// reader finds files by name, so only the first of several with exactly
+    /// the same name can be read, and such cabinets fail under every policy
+    /// but [`DuplicatePolicy::KeepFirst`]. Entries handed out one at a time
+    /// by the async API and by [`ArchiveConverter`](crate::ArchiveConverter)
+    /// cannot be taken back, so there [`DuplicatePolicy::KeepLast`] fails
+    /// like [`DuplicatePolicy::Error`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveExtractor, DuplicatePolicy};
+    ///
+    /// let extractor = ArchiveExtractor::new().with_duplicate_policy(DuplicatePolicy::Error);
+    /// ```
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicates = policy;
+        self
+    }
+
+    /// Treats paths that differ only in case, like `README` and `readme`, as
+    /// the same path when applying the duplicate policy.
+    ///
+    /// Use this for archives that will be unpacked on case-insensitive file
+    /// systems, such as the Windows and macOS defaults.
+    pub fn with_case_folding(mut self, fold: bool) -> Self {
+        self.path_folding.case = fold;
+        self
+    }
+
+    /// Treats paths that are equal after Unicode normalization (NFC), like a
+    /// precomposed `é` and `e` followed by a combining accent, as the same
+    /// path when applying the duplicate policy.
+    ///
+    /// Use this for archives that will be unpacked on file systems that
+    /// normalize names, such as macOS's.
+    pub fn with_unicode_normalization(mut self, normalize: bool) -> Self {
+        self.path_folding.unicode = normalize;
+        self
+    }
+
+    /// Sets the encoding of entry names that are not valid UTF-8.
+    ///
+    /// Names are read as UTF-8 whenever they are valid UTF-8, so this only
+    /// affects names written in a legacy code page. Without it, such ZIP
+    /// names are decoded as CP437, as the ZIP specification says, and TAR and
+    /// AR names have their invalid bytes replaced with U+FFFD. ZIP names
+    /// marked as UTF-8, or with an Info-ZIP Unicode Path extra field, are
+    /// always taken as UTF-8. The stored bytes are kept in
+    /// [`EntryMetadata::raw_name`] either way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveExtractor, NameEncoding};
+    ///
+    /// let extractor = ArchiveExtractor::new().with_name_encoding(NameEncoding::ShiftJis);
+    /// ```
+    pub fn with_name_encoding(mut self, encoding: NameEncoding) -> Self {
+        self.name_encoding = Some(encoding);
+        self
+    }
+
+    /// Sets whether the entry of a gzip file is named after the gzip header
+    /// or after [`with_source_filename`](Self::with_source_filename).
+    ///
+    /// The header name comes first by default. Uploaders can put any name in
+    /// the header, so prefer the source filename when the name the file was
+    /// received under is the one to trust. Either way, only the file name
+    /// part of a header name is used, and header names with `..` components,
+    /// control characters or no file name are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveExtractor, ArchiveFormat, ArchiveWriter, ExtractedFile, GzipNamePrecedence};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data = ArchiveWriter::new().write_with_format(
+    ///     &[ExtractedFile::file("invoice.pdf.exe", b"MZ".to_vec())],
+    ///     ArchiveFormat::Gz,
+    /// )?;
+    ///
+    /// let files = ArchiveExtractor::new()
+    ///     .with_source_filename("invoice.pdf.gz")
+    ///     .with_gzip_name_precedence(GzipNamePrecedence::SourceFilename)
+    ///     .extract_with_format(&data, ArchiveFormat::Gz)?;
+    /// assert_eq!(files[0].path, "invoice.pdf");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_gzip_name_precedence(mut self, precedence: GzipNamePrecedence) -> Self {
+        self.gzip_name = precedence;
+        self
+    }
+
+    /// Decompresses ZIP entries concurrently on rayon's global thread pool.
+    ///
+    /// ZIP entries are compressed independently, so each worker can inflate
+    /// its own entries from the shared input. Entries are still returned in
+    /// archive order, and `max_total_size` is enforced across all workers.
+    /// The [progress callback](Self::with_progress) is called from the
+    /// workers, possibly at the same time, as entries finish in any order.
+    /// Other formats, integrity tests and hash-only extraction run
+    /// sequentially as usual.
+    ///
+    /// Requires the `parallel` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ArchiveExtractor;
+    ///
+    /// let extractor = ArchiveExtractor::new().with_parallel(true);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
     /// Sets the archive format explicitly.
     ///
     /// When set, the [`extract`](Self::extract) method will use this format
@@ -425,411 +1091,2385 @@ impl ArchiveExtractor {
         data: &[u8],
         format: ArchiveFormat,
     ) -> Result<Vec<ExtractedFile>> {
-        match format {
-            ArchiveFormat::Zip => self.extract_zip(data),
-            ArchiveFormat::Tar => self.extract_tar(data),
-            ArchiveFormat::Ar => self.extract_ar(data),
-            ArchiveFormat::Deb => self.extract_deb(data),
-            ArchiveFormat::TarGz => self.extract_tar_gz(data),
-            ArchiveFormat::TarBz2 => self.extract_tar_bz2(data),
-            ArchiveFormat::TarXz => self.extract_tar_xz(data),
-            ArchiveFormat::TarZst => self.extract_tar_zst(data),
-            ArchiveFormat::TarLz4 => self.extract_tar_lz4(data),
-            ArchiveFormat::SevenZ => self.extract_7z(data),
-            ArchiveFormat::Gz => self.extract_single_gz(data),
-            ArchiveFormat::Bz2 => self.extract_single_bz2(data),
-            ArchiveFormat::Xz => self.extract_single_xz(data),
-            ArchiveFormat::Lz4 => self.extract_single_lz4(data),
-            ArchiveFormat::Zst => self.extract_single_zst(data),
-        }
-    }
-
-    /// Derives an output filename for single-file compression by stripping
-    /// the compression extension from `source_filename`.
-    fn derive_single_file_path(&self, format: ArchiveFormat) -> String {
-        if let Some(ref filename) = self.source_filename {
-            let ext = match format {
-                ArchiveFormat::Gz => ".gz",
-                ArchiveFormat::Bz2 => ".bz2",
-                ArchiveFormat::Xz => ".xz",
-                ArchiveFormat::Lz4 => ".lz4",
-                ArchiveFormat::Zst => ".zst",
-                _ => return "data".to_string(),
-            };
-            let lower = filename.to_lowercase();
-            if lower.ends_with(ext) {
-                let stripped = &filename[..filename.len() - ext.len()];
-                if !stripped.is_empty() {
-                    return stripped.to_string();
-                }
-            }
-        }
-        "data".to_string()
+        self.extract_session(data, format, &mut Session::new(self, None))
     }
 
-    fn extract_zip(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let reader = Cursor::new(data);
-        let mut archive = zip::ZipArchive::new(reader)?;
-        let mut files = Vec::new();
-        let mut total_size = 0usize;
+    /// Extracts a single entry by path using the builder-configured format.
+    ///
+    /// See [`extract_entry_with_format`](Self::extract_entry_with_format) for
+    /// details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveError::UnknownFormat`] if no format has been configured,
+    /// and [`ArchiveError::EntryNotFound`] if the archive has no such entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use archive::{ArchiveExtractor, ArchiveFormat};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = vec![0u8; 100];
+    /// let manifest = ArchiveExtractor::new()
+    ///     .with_format(ArchiveFormat::Zip)
+    ///     .extract_entry(&data, "package/package.json")?;
+    /// println!("{}", String::from_utf8_lossy(&manifest.data));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract_entry(&self, data: &[u8], path: &str) -> Result<ExtractedFile> {
+        let format = self.format.ok_or(ArchiveError::UnknownFormat)?;
+        self.extract_entry_with_format(data, format, path)
+    }
+
+    /// Extracts a single entry by path with an explicitly specified format.
+    ///
+    /// Only as much of the archive is decoded as is needed to reach the entry:
+    /// ZIP and CAB entries are read directly, 7z decodes only the block holding
+    /// the entry, and streaming formats such as TAR stop as soon as the entry
+    /// has been read. Only the entry itself counts toward the size limits.
+    ///
+    /// Paths are compared as stored in the archive, except that a trailing
+    /// slash on directories and a leading `./` are ignored. If the archive
+    /// holds several entries with the same path, the first one is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveError::EntryNotFound`] if the archive has no entry at
+    /// `path`. See [`extract_with_format`](Self::extract_with_format) for other
+    /// possible errors.
+    pub fn extract_entry_with_format(
+        &self,
+        data: &[u8],
+        format: ArchiveFormat,
+        path: &str,
+    ) -> Result<ExtractedFile> {
+        let mut session = Session::new(self, Some(path));
+        self.extract_session(data, format, &mut session)?
+            .into_iter()
+            .find(|file| same_entry(path, &file.path))
+            .ok_or_else(|| ArchiveError::EntryNotFound(path.to_string()))
+    }
+
+    /// Extracts all files without copying stored contents, using the
+    /// builder-configured format.
+    ///
+    /// See [`extract_borrowed_with_format`](Self::extract_borrowed_with_format)
+    /// for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveError::UnknownFormat`] if no format has been configured.
+    pub fn extract_borrowed<'a>(&self, data: &'a [u8]) -> Result<Vec<BorrowedFile<'a>>> {
+        let format = self.format.ok_or(ArchiveError::UnknownFormat)?;
+        self.extract_borrowed_with_format(data, format)
+    }
+
+    /// Extracts all files with an explicitly specified format, borrowing the
+    /// contents of uncompressed entries from `data` instead of copying them.
+    ///
+    /// Regular files in plain TAR, AR and DEB archives, and entries stored
+    /// without compression in ZIP archives, point straight into `data`.
+    /// Stored ZIP entries are still read once to check their CRC. Every
+    /// other entry, and every entry of the other formats, is decompressed
+    /// into an owned buffer as usual.
+    ///
+    /// Borrowed entries count toward the size limits just like copied ones,
+    /// so the same limits give the same results as
+    /// [`extract_with_format`](Self::extract_with_format).
+    ///
+    /// # Errors
+    ///
+    /// See [`extract_with_format`](Self::extract_with_format).
+    pub fn extract_borrowed_with_format<'a>(
+        &self,
+        data: &'a [u8],
+        format: ArchiveFormat,
+    ) -> Result<Vec<BorrowedFile<'a>>> {
+        let mut session = Session::new(self, None);
+        // Only where entry offsets are offsets into `data`.
+        if matches!(
+            format,
+            ArchiveFormat::Tar | ArchiveFormat::Ar | ArchiveFormat::Deb | ArchiveFormat::Zip
+        ) {
+            session.zero_copy_len = Some(data.len());
+        }
+        let files = self.extract_session(data, format, &mut session)?;
+
+        let mut borrowed = session.borrowed.into_iter().peekable();
+        Ok(files
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut file)| {
+                let data = match borrowed.next_if(|(index, _)| *index == i) {
+                    Some((_, range)) => {
+                        if !self.hashes.is_empty() {
+                            file.digests = Hashers::digest(&self.hashes, &data[range.clone()]);
+                        }
+                        Cow::Borrowed(&data[range])
+                    }
+                    None => Cow::Owned(file.data),
+                };
+                BorrowedFile {
+                    path: file.path,
+                    data,
+                    is_directory: file.is_directory,
+                    metadata: file.metadata,
+                    digests: file.digests,
+                }
+            })
+            .collect())
+    }
+
+    /// Computes digests of every file in an archive without keeping the
+    /// contents, using the builder-configured format.
+    ///
+    /// See [`hash_with_format`](Self::hash_with_format) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveError::UnknownFormat`] if no format has been configured.
+    pub fn hash(&self, data: &[u8]) -> Result<Vec<HashedEntry>> {
+        let format = self.format.ok_or(ArchiveError::UnknownFormat)?;
+        self.hash_with_format(data, format)
+    }
+
+    /// Computes digests of every file in an archive with an explicitly
+    /// specified format, returning each file's path, size and digests and
+    /// throwing its contents away.
+    ///
+    /// The digests are those added with [`with_hash`](Self::with_hash), or
+    /// SHA-256 if none were. Directories and symbolic links are left out.
+    /// The size limits, include/exclude patterns and filter apply as they do
+    /// to extraction.
+    ///
+    /// # Errors
+    ///
+    /// See [`extract_with_format`](Self::extract_with_format).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use archive::{ArchiveExtractor, ArchiveFormat, HashAlgorithm};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = vec![0u8; 100];
+    /// let entries = ArchiveExtractor::new()
+    ///     .with_hash(HashAlgorithm::Sha256)
+    ///     .with_hash(HashAlgorithm::Blake3)
+    ///     .hash_with_format(&data, ArchiveFormat::TarGz)?;
+    /// for entry in entries {
+    ///     println!("{}  {} ({} bytes)", entry.digests[0], entry.path, entry.size);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hash_with_format(&self, data: &[u8], format: ArchiveFormat) -> Result<Vec<HashedEntry>> {
+        let default;
+        let extractor = if self.hashes.is_empty() {
+            default = self.clone().with_hash(HashAlgorithm::Sha256);
+            &default
+        } else {
+            self
+        };
+        let mut session = Session::new(extractor, None);
+        session.hashed = Some(Vec::new());
+        extractor.extract_session(data, format, &mut session)?;
+        Ok(session.hashed.unwrap_or_default())
+    }
+
+    /// Tests an archive using the builder-configured format.
+    ///
+    /// See [`test_with_format`](Self::test_with_format) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveError::UnknownFormat`] if no format has been configured.
+    pub fn test(&self, data: &[u8]) -> Result<IntegrityReport> {
+        let format = self.format.ok_or(ArchiveError::UnknownFormat)?;
+        self.test_with_format(data, format)
+    }
+
+    /// Tests an archive with an explicitly specified format, like `unzip -t`
+    /// or `7z t`: every entry is decoded and checked against the checksums
+    /// the archive stores, and its contents are thrown away.
+    ///
+    /// A mismatch against a stored checksum is reported as
+    /// [`ArchiveError::ChecksumMismatch`]: the CRC-32 of ZIP and 7z entries,
+    /// the header checksum of TAR entries, and the CRCs of RAR entries.
+    /// Formats whose decoder checks a checksum of its own, such as gzip or
+    /// xz, report whatever error their decoder gives.
+    ///
+    /// Failures are recorded against the entry they belong to. ZIP, 7z and
+    /// CAB entries can be checked independently, so the test goes on past a
+    /// bad entry; for the other formats the first failure ends it. Failures
+    /// that belong to no single entry end up in [`IntegrityReport::error`].
+    /// The size limits, include/exclude patterns and filter apply as they do
+    /// to extraction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveError::Cancelled`] if the test was cancelled. Every
+    /// other failure is part of the report.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use archive::{ArchiveExtractor, ArchiveFormat};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = vec![0u8; 100];
+    /// let report = ArchiveExtractor::new().test_with_format(&data, ArchiveFormat::Zip)?;
+    /// for check in &report.entries {
+    ///     match &check.error {
+    ///         None => println!("{}: OK", check.path),
+    ///         Some(err) => println!("{}: {}", check.path, err),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn test_with_format(&self, data: &[u8], format: ArchiveFormat) -> Result<IntegrityReport> {
+        let mut session = Session::new(self, None);
+        session.checks = Some(Vec::new());
+        let error = match self.extract_session(data, format, &mut session) {
+            Ok(_) => None,
+            Err(ArchiveError::Cancelled) => return Err(ArchiveError::Cancelled),
+            Err(err) => session.fail_entry(err).err(),
+        };
+        Ok(IntegrityReport {
+            entries: session.checks.unwrap_or_default(),
+            error,
+        })
+    }
+
+    /// Checks a ZIP archive for anything that makes it read differently
+    /// depending on the tool, without decompressing it.
+    ///
+    /// Every local header is compared with its central directory record by
+    /// name, compression method, CRC-32 and sizes, names repeated in the
+    /// central directory are reported, and the archive is searched for extra
+    /// end-of-central-directory records and for data before the first entry
+    /// or after the end. The anomalies are returned in that order; an empty
+    /// list means the archive is unambiguous. Only an archive that cannot be
+    /// read at all is an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveExtractor, ArchiveFormat, ArchiveWriter, ExtractedFile, ZipAnomaly};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let zip = ArchiveWriter::new().write_with_format(
+    ///     &[ExtractedFile::file("hello.txt", b"hello".to_vec())],
+    ///     ArchiveFormat::Zip,
+    /// )?;
+    /// let mut data = b"#!/bin/sh\n".to_vec();
+    /// data.extend_from_slice(&zip);
+    ///
+    /// let anomalies = ArchiveExtractor::new().validate_zip(&data)?;
+    /// assert_eq!(anomalies, [ZipAnomaly::PrefixData { size: 10 }]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_zip(&self, data: &[u8]) -> Result<Vec<ZipAnomaly>> {
+        validation::zip_anomalies(data)
+    }
+
+    /// Reads the symbol table of an ar archive, such as a static library
+    /// (`.a`), mapping each symbol to the member that defines it.
+    ///
+    /// GNU and BSD symbol tables are both understood, and member names are
+    /// resolved and decoded as they are on extraction, so they match
+    /// [`ExtractedFile::path`]. A symbol defined by several members maps to
+    /// the first. An archive without a symbol table gives an empty map.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use archive::ArchiveExtractor;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data = std::fs::read("libfoo.a")?;
+    /// let symbols = ArchiveExtractor::new().ar_symbols(&data)?;
+    /// if let Some(member) = symbols.get("foo_init") {
+    ///     println!("foo_init is defined in {}", member);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ar_symbols(&self, data: &[u8]) -> Result<BTreeMap<String, String>> {
+        library::symbols(data, self.name_encoding)
+    }
+
+    /// Extracts `data`, handing each entry to `sink` as soon as it has been
+    /// read instead of collecting them.
+    pub(crate) fn extract_into(
+        &self,
+        data: &[u8],
+        format: ArchiveFormat,
+        sink: EntrySink<'_>,
+    ) -> Result<()> {
+        let mut session = Session::new(self, None).with_sink(sink);
+        self.extract_session(data, format, &mut session).map(drop)
+    }
+
+    #[cfg(feature = "async")]
+    /// Like [`extract_into`](Self::extract_into), but reads a streamable
+    /// format from `reader` as it goes.
+    pub(crate) fn extract_reader_into<R: BufRead>(
+        &self,
+        reader: R,
+        format: ArchiveFormat,
+        sink: EntrySink<'_>,
+    ) -> Result<()> {
+        let mut session = Session::new(self, None).with_sink(sink);
+        session
+            .run(|session| self.extract_stream(session.input(reader), format, session))
+            .map(drop)
+    }
+
+    fn extract_session(
+        &self,
+        data: &[u8],
+        format: ArchiveFormat,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let files = session.run(|session| self.extract_format(data, format, session))?;
+        Ok(session.keep_last(files))
+    }
+
+    fn extract_format(
+        &self,
+        data: &[u8],
+        format: ArchiveFormat,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        match format {
+            ArchiveFormat::Zip => self.extract_zip(data, session),
+            ArchiveFormat::SevenZ => self.extract_7z(data, session),
+            ArchiveFormat::Cab => self.extract_cab(data, session),
+            ArchiveFormat::Rar => self.extract_rar(data, session),
+            _ => self.extract_stream(session.input(Cursor::new(data)), format, session),
+        }
+    }
+
+    /// Extracts a format that is read front to back, see
+    /// [`ArchiveFormat::is_streamable`].
+    fn extract_stream<R: BufRead>(
+        &self,
+        input: R,
+        format: ArchiveFormat,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        match format {
+            ArchiveFormat::Tar => self.extract_tar(input, session),
+            ArchiveFormat::Ar | ArchiveFormat::Deb => self.extract_ar(input, session),
+            ArchiveFormat::TarGz => self.extract_tar_gz(input, session),
+            ArchiveFormat::TarBz2 => self.extract_tar_bz2(input, session),
+            ArchiveFormat::TarXz => self.extract_tar_xz(input, session),
+            ArchiveFormat::TarZst => self.extract_tar_zst(input, session),
+            ArchiveFormat::TarLz4 => self.extract_tar_lz4(input, session),
+            ArchiveFormat::TarZ => self.extract_tar_z(input, session),
+            ArchiveFormat::TarLzma => self.extract_tar_lzma(input, session),
+            ArchiveFormat::TarLzip => self.extract_tar_lzip(input, session),
+            ArchiveFormat::TarBr => self.extract_tar_br(input, session),
+            ArchiveFormat::TarSz => self.extract_tar_sz(input, session),
+            ArchiveFormat::Gz => self.extract_single_gz(input, session),
+            // The remaining single-file formats store no name, so their one
+            // entry can be checked before anything is decompressed.
+            ArchiveFormat::Bz2
+            | ArchiveFormat::Xz
+            | ArchiveFormat::Lz4
+            | ArchiveFormat::Zst
+            | ArchiveFormat::Z
+            | ArchiveFormat::Lzma
+            | ArchiveFormat::Lzip
+            | ArchiveFormat::Br
+            | ArchiveFormat::Sz
+                if !session.wants(&single_file_header(self.derive_single_file_path(format))) =>
+            {
+                Ok(Vec::new())
+            }
+            ArchiveFormat::Bz2 => self.extract_single_bz2(input, session),
+            ArchiveFormat::Xz => self.extract_single_xz(input, session),
+            ArchiveFormat::Lz4 => self.extract_single_lz4(input, session),
+            ArchiveFormat::Zst => self.extract_single_zst(input, session),
+            ArchiveFormat::Z => self.extract_single_z(input, session),
+            ArchiveFormat::Lzma => self.extract_single_lzma(input, session),
+            ArchiveFormat::Lzip => self.extract_single_lzip(input, session),
+            ArchiveFormat::Br => self.extract_single_br(input, session),
+            ArchiveFormat::Sz => self.extract_single_sz(input, session),
+            ArchiveFormat::Zip
+            | ArchiveFormat::SevenZ
+            | ArchiveFormat::Cab
+            | ArchiveFormat::Rar => Err(ArchiveError::UnsupportedFormat(format!(
+                "{} cannot be extracted from a stream",
+                format.name()
+            ))),
+        }
+    }
+
+    /// Derives an output filename for single-file compression by stripping
+    /// the compression extension from `source_filename`.
+    fn derive_single_file_path(&self, format: ArchiveFormat) -> String {
+        self.source_file_path(format)
+            .unwrap_or_else(|| "data".to_string())
+    }
+
+    /// Strips the compression extension from `source_filename`, if it has
+    /// the one for `format`.
+    fn source_file_path(&self, format: ArchiveFormat) -> Option<String> {
+        if let Some(ref filename) = self.source_filename {
+            let ext = match format {
+                ArchiveFormat::Gz => ".gz",
+                ArchiveFormat::Bz2 => ".bz2",
+                ArchiveFormat::Xz => ".xz",
+                ArchiveFormat::Lz4 => ".lz4",
+                ArchiveFormat::Zst => ".zst",
+                ArchiveFormat::Z => ".z",
+                ArchiveFormat::Lzma => ".lzma",
+                ArchiveFormat::Lzip => ".lz",
+                ArchiveFormat::Br => ".br",
+                ArchiveFormat::Sz => ".sz",
+                _ => return None,
+            };
+            let lower = filename.to_lowercase();
+            if lower.ends_with(ext) {
+                let stripped = &filename[..filename.len() - ext.len()];
+                if !stripped.is_empty() {
+                    return Some(stripped.to_string());
+                }
+            }
+        }
+        None
+    }
+
+    fn extract_zip(&self, data: &[u8], session: &mut Session<'_>) -> Result<Vec<ExtractedFile>> {
+        if self.strict_zip {
+            let anomalies = validation::zip_anomalies(data)?;
+            if !anomalies.is_empty() {
+                return Err(ArchiveError::AmbiguousZip(anomalies));
+            }
+        }
+
+        #[cfg(feature = "parallel")]
+        if self.parallel
+            && session.target.is_none()
+            && session.zero_copy_len.is_none()
+            && session.checks.is_none()
+            && session.hashed.is_none()
+        {
+            return self.extract_zip_parallel(data, session);
+        }
+
+        let mut archive = zip::ZipArchive::new(session.input(Cursor::new(data)))?;
+        self.check_zip_duplicates(data, &archive)?;
+        let mut files = Vec::new();
+
+        // A single entry is looked up in the central directory rather than
+        // found by walking every entry. Inflating just one entry cannot
+        // multiply shared data, so the overlap check is skipped for it.
+        let indices: Vec<usize> = match session.target {
+            Some(target) => zip_index_for_name(&archive, target)
+                .or_else(|| self.zip_index_for_decoded_name(data, &mut archive, target))
+                .into_iter()
+                .collect(),
+            None => {
+                check_zip_overlaps(&mut archive)?;
+                (0..archive.len()).collect()
+            }
+        };
+
+        for i in indices {
+            session.check_cancelled()?;
+            let mut file = archive.by_index(i)?;
+            let header = self.zip_header(data, &file);
+            if !session.admit(&header)? {
+                continue;
+            }
+            let EntryHeader {
+                path,
+                is_directory,
+                mut metadata,
+                ..
+            } = header;
+
+            if !is_directory {
+                session.reserve(file.size() as usize)?;
+
+                let stored = file.compression() == zip::CompressionMethod::Stored;
+                if let Some(range) = session
+                    .borrow_range(file.data_start(), file.size())
+                    .filter(|_| stored && !file.is_symlink())
+                {
+                    // Still read the contents through once, which checks the CRC.
+                    if let Err(err) = io::copy(&mut file, &mut io::sink()) {
+                        session.verify_crc(&path, &data[range], file.crc32())?;
+                        return Err(err.into());
+                    }
+                    session.push_borrowed(
+                        &mut files,
+                        ExtractedFile {
+                            path,
+                            data: Vec::new(),
+                            is_directory,
+                            metadata,
+                            digests: Vec::new(),
+                        },
+                        range,
+                    );
+                    continue;
+                }
+
+                let (size, crc) = (file.size(), file.crc32());
+                let contents = match session.read_checked(&mut file, &path, size, crc) {
+                    Ok(contents) => contents,
+                    Err(err) => {
+                        session.fail_entry(err)?;
+                        continue;
+                    }
+                };
+
+                // ZIP stores a symlink's target as the entry's contents.
+                if file.is_symlink() {
+                    metadata.link_target = Some(String::from_utf8_lossy(&contents).into_owned());
+                }
+
+                session.push(
+                    &mut files,
+                    ExtractedFile {
+                        path,
+                        data: contents,
+                        is_directory,
+                        metadata,
+                        digests: Vec::new(),
+                    },
+                );
+            } else {
+                session.push(
+                    &mut files,
+                    ExtractedFile {
+                        path,
+                        data: Vec::new(),
+                        is_directory,
+                        metadata,
+                        digests: Vec::new(),
+                    },
+                );
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Describes a ZIP entry, decoding its name from the central directory.
+    ///
+    /// Names marked as UTF-8, or replaced by an Info-ZIP Unicode Path extra
+    /// field, are taken as the ZIP reader decoded them. Other names are
+    /// decoded like TAR names, falling back to CP437.
+    fn zip_header<R: Read>(&self, data: &[u8], file: &zip::read::ZipFile<'_, R>) -> EntryHeader {
+        let (path, raw_name) = match validation::central_name(data, file.central_header_start()) {
+            Some((raw, is_utf8)) if is_utf8 || raw != file.name_raw() => {
+                (file.name().to_string(), raw.to_vec())
+            }
+            Some((raw, _)) => (
+                encoding::decode_name(raw, Some(self.name_encoding.unwrap_or(NameEncoding::Cp437))),
+                raw.to_vec(),
+            ),
+            None => (file.name().to_string(), file.name_raw().to_vec()),
+        };
+        EntryHeader {
+            path,
+            is_directory: file.is_dir(),
+            size: Some(file.size()),
+            metadata: EntryMetadata {
+                mtime: file.last_modified().and_then(timestamp::zip_to_unix),
+                mode: file.unix_mode().map(|mode| mode & 0o7777),
+                raw_name: Some(raw_name),
+                ..EntryMetadata::default()
+            },
+        }
+    }
+
+    /// Looks an entry up by its decoded name, for names the ZIP reader
+    /// decodes differently.
+    fn zip_index_for_decoded_name<R: Read + Seek>(
+        &self,
+        data: &[u8],
+        archive: &mut zip::ZipArchive<R>,
+        path: &str,
+    ) -> Option<usize> {
+        (0..archive.len()).find(|&i| {
+            archive
+                .by_index_raw(i)
+                .is_ok_and(|file| same_entry(path, &self.zip_header(data, &file).path))
+        })
+    }
+
+    /// Fails on central directory records with the same name if the
+    /// duplicate policy needs to see them. The ZIP reader indexes entries by
+    /// name and keeps only the last of them, so they never reach
+    /// [`Session::admit`]. Repeated directories are allowed as usual.
+    fn check_zip_duplicates<R: Read + Seek>(
+        &self,
+        data: &[u8],
+        archive: &zip::ZipArchive<R>,
+    ) -> Result<()> {
+        if !matches!(
+            self.duplicates,
+            DuplicatePolicy::Error | DuplicatePolicy::KeepFirst
+        ) {
+            return Ok(());
+        }
+        let start = archive.central_directory_start();
+        match validation::repeated_names(data, start)
+            .into_iter()
+            .find(|(name, _)| !name.ends_with('/'))
+        {
+            Some((name, _)) => Err(ArchiveError::DuplicateEntry {
+                path: name.clone(),
+                existing: name,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Inflates ZIP entries on rayon's thread pool. Headers are read and
+    /// filtered up front, every worker decompresses through its own handle on
+    /// the shared input, and the results are handed out in archive order.
+    #[cfg(feature = "parallel")]
+    fn extract_zip_parallel(
+        &self,
+        data: &[u8],
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        use rayon::prelude::*;
+        use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+        let archive = zip::ZipArchive::new(Cursor::new(data))?;
+
+        let mut jobs = Vec::new();
+        self.check_zip_duplicates(data, &archive)?;
+        let mut headers = archive.clone();
+        check_zip_overlaps(&mut headers)?;
+        for i in 0..headers.len() {
+            session.check_cancelled()?;
+            let file = headers.by_index_raw(i)?;
+            let header = self.zip_header(data, &file);
+            if session.admit(&header)? {
+                jobs.push((
+                    i,
+                    header,
+                    file.is_symlink(),
+                    file.compressed_size(),
+                    file.crc32(),
+                ));
+            }
+        }
+
+        // Shared by the workers, so that together they stop at the limit.
+        let total_size = AtomicUsize::new(session.total_size);
+        // Progress is counted across the workers, and each reports it as it
+        // goes, in whatever order the entries finish.
+        let entries_done = AtomicUsize::new(session.entries_done);
+        let bytes_in = AtomicU64::new(session.input_position());
+        let bytes_out = AtomicU64::new(session.bytes_out);
+        let report = |path: &str| {
+            if let Some(callback) = &self.progress {
+                (callback.0)(&Progress {
+                    entries_done: entries_done.load(Ordering::Relaxed),
+                    bytes_in: bytes_in.load(Ordering::Relaxed),
+                    bytes_out: bytes_out.load(Ordering::Relaxed),
+                    current_path: Some(path),
+                });
+            }
+        };
+        let finish = |path: &str, compressed_size: u64| {
+            bytes_in.fetch_add(compressed_size, Ordering::Relaxed);
+            entries_done.fetch_add(1, Ordering::Relaxed);
+            report(path);
+        };
+
+        // The position of the earliest entry that failed so far. Entries
+        // after it give up, since its error is the one reported; entries
+        // before it still run, in case they fail too.
+        let first_failure = AtomicUsize::new(usize::MAX);
+        let superseded = |position: usize| first_failure.load(Ordering::Relaxed) < position;
+
+        let contents: Vec<Result<(Vec<u8>, Vec<Digest>)>> = jobs
+            .par_iter()
+            .enumerate()
+            .map_init(
+                || archive.clone(),
+                |archive, (position, (i, header, _, compressed_size, crc))| {
+                    // Never reported: an earlier entry's error comes first.
+                    if superseded(position) {
+                        return Err(ArchiveError::Cancelled);
+                    }
+                    let result = (|| {
+                        if header.is_directory {
+                            finish(&header.path, *compressed_size);
+                            return Ok((Vec::new(), Vec::new()));
+                        }
+                        let size = header.size.unwrap_or_default() as usize;
+                        if size > self.max_file_size {
+                            return Err(ArchiveError::FileTooLarge {
+                                size,
+                                limit: self.max_file_size,
+                            });
+                        }
+                        let total = total_size.fetch_add(size, Ordering::Relaxed) + size;
+                        if total > self.max_total_size {
+                            return Err(ArchiveError::TotalSizeTooLarge {
+                                size: total,
+                                limit: self.max_total_size,
+                            });
+                        }
+
+                        let mut file = archive.by_index(*i)?;
+                        let mut contents = Vec::new();
+                        loop {
+                            if superseded(position)
+                                || self
+                                    .cancellation
+                                    .as_ref()
+                                    .is_some_and(CancellationToken::is_cancelled)
+                            {
+                                return Err(ArchiveError::Cancelled);
+                            }
+                            match (&mut file).take(PROGRESS_CHUNK).read_to_end(&mut contents) {
+                                Ok(0) => {
+                                    let digests = Hashers::digest(&self.hashes, &contents);
+                                    finish(&header.path, *compressed_size);
+                                    return Ok((contents, digests));
+                                }
+                                Ok(read) => {
+                                    bytes_out.fetch_add(read as u64, Ordering::Relaxed);
+                                    report(&header.path);
+                                }
+                                // See `Session::read_checked`.
+                                Err(err) => {
+                                    return Err(if contents.len() as u64 == size as u64 {
+                                        crc_mismatch(&header.path, &contents, *crc)
+                                            .unwrap_or(err.into())
+                                    } else {
+                                        err.into()
+                                    });
+                                }
+                            }
+                        }
+                    })();
+                    if result.is_err() {
+                        first_failure.fetch_min(position, Ordering::Relaxed);
+                    }
+                    result
+                },
+            )
+            .collect();
+        session.total_size = total_size.into_inner();
+        session.entries_done = entries_done.into_inner();
+        session.bytes_out = bytes_out.into_inner();
+        session.bytes_in.set(bytes_in.into_inner());
+
+        let mut files = Vec::new();
+        for ((_, header, is_symlink, _, _), contents) in jobs.into_iter().zip(contents) {
+            let (contents, digests) = contents?;
+            let EntryHeader {
+                path,
+                is_directory,
+                mut metadata,
+                ..
+            } = header;
+            // ZIP stores a symlink's target as the entry's contents.
+            if is_symlink {
+                metadata.link_target = Some(String::from_utf8_lossy(&contents).into_owned());
+            }
+            session.store(
+                &mut files,
+                ExtractedFile {
+                    path,
+                    data: contents,
+                    is_directory,
+                    metadata,
+                    digests,
+                },
+            );
+        }
+
+        Ok(files)
+    }
+
+    fn extract_tar<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        self.process_tar_entries(input, session)
+    }
+
+    fn extract_ar<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut archive = ar::Archive::new(input);
+        self.process_ar_entries(&mut archive, session)
+    }
+
+    fn extract_tar_gz<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let decoder = flate2::bufread::GzDecoder::new(input);
+        self.process_tar_entries(decoder, session)
+    }
+
+    fn extract_tar_bz2<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let decoder = bzip2::bufread::BzDecoder::new(input);
+        self.process_tar_entries(decoder, session)
+    }
+
+    fn extract_tar_xz<R: BufRead>(
+        &self,
+        mut input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut output = Vec::new();
+        lzma_rs::xz_decompress(&mut input, &mut output)
+            .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+        let cursor = Cursor::new(output);
+        self.process_tar_entries(cursor, session)
+    }
+
+    fn extract_tar_lzma<R: BufRead>(
+        &self,
+        mut input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut output = Vec::new();
+        lzma_rs::lzma_decompress(&mut input, &mut output)
+            .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+        let cursor = Cursor::new(output);
+        self.process_tar_entries(cursor, session)
+    }
+
+    fn extract_tar_lzip<R: BufRead>(
+        &self,
+        mut input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let output = lzip::decompress(&data)?;
+        let cursor = Cursor::new(output);
+        self.process_tar_entries(cursor, session)
+    }
+
+    fn extract_tar_br<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let decoder = brotli::Decompressor::new(input, 4096);
+        self.process_tar_entries(decoder, session)
+    }
+
+    fn extract_tar_sz<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let decoder = snap::read::FrameDecoder::new(input);
+        self.process_tar_entries(decoder, session)
+    }
+
+    fn extract_tar_zst<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let decoder = zstd::stream::read::Decoder::with_buffer(input)?;
+        self.process_tar_entries(decoder, session)
+    }
+
+    fn extract_tar_lz4<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let decoder = lz4::Decoder::new(input)?;
+        self.process_tar_entries(decoder, session)
+    }
+
+    fn extract_tar_z<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let decoder = LzwDecoder::new(input)?;
+        self.process_tar_entries(decoder, session)
+    }
+
+    fn extract_7z(&self, data: &[u8], session: &mut Session<'_>) -> Result<Vec<ExtractedFile>> {
+        let mut cursor = session.input(Cursor::new(data));
+        let len = data.len() as u64;
+
+        let mut archive = sevenz_rust::Archive::read(&mut cursor, len, &[])
+            .map_err(|e| ArchiveError::InvalidArchive(format!("7z error: {}", e)))?;
+
+        // The decoder's own CRC checks fail the read that completes an entry,
+        // losing the contents needed to report the mismatch, so the CRCs are
+        // checked below instead.
+        let mut stored_crcs = Vec::with_capacity(archive.files.len());
+        for file in &mut archive.files {
+            stored_crcs.push(file.has_crc.then_some(file.crc as u32));
+            file.has_crc = false;
+        }
+        for folder in &mut archive.folders {
+            folder.has_crc = false;
+        }
+
+        // The blocks to decode, and the files without contents, which belong
+        // to no block. A single entry is read from its block alone.
+        let stream_map = &archive.stream_map;
+        let (blocks, unblocked): (Vec<usize>, Vec<usize>) = match session.target {
+            None => (
+                (0..archive.folders.len()).collect(),
+                (0..archive.files.len())
+                    .filter(|&index| stream_map.file_folder_index[index].is_none())
+                    .collect(),
+            ),
+            Some(target) => match archive
+                .files
+                .iter()
+                .position(|file| same_entry(target, file.name()))
+            {
+                None => (Vec::new(), Vec::new()),
+                Some(index) => match stream_map.file_folder_index[index] {
+                    Some(block) => (vec![block], Vec::new()),
+                    None => (Vec::new(), vec![index]),
+                },
+            },
+        };
+        // Entries are handed over without their index, so their CRCs are
+        // taken in the order they are decoded: block by block, then the rest.
+        let mut expected_crcs = blocks
+            .iter()
+            .flat_map(|&block| {
+                let start = stream_map.folder_first_file_index[block];
+                start..start + archive.folders[block].num_unpack_sub_streams
+            })
+            .chain(unblocked.iter().copied())
+            .map(|index| stored_crcs[index])
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        let mut files = Vec::new();
+        let mut stop_error: Option<ArchiveError> = None;
+
+        // Single-pass extraction: validate sizes and extract contents in one iteration
+        let mut each = |entry: &sevenz_rust::SevenZArchiveEntry,
+                        reader: &mut dyn Read|
+         -> std::result::Result<bool, sevenz_rust::Error> {
+            let expected_crc = expected_crcs.next().flatten();
+            if let Err(err) = session.check_cancelled() {
+                stop_error = Some(err);
+                return Ok(false);
+            }
+            let header = EntryHeader {
+                path: entry.name().to_string(),
+                is_directory: entry.is_directory(),
+                size: Some(entry.size()),
+                metadata: sevenz_metadata(entry, &[]),
+            };
+            match session.admit(&header) {
+                Ok(true) => {}
+                Ok(false) => {
+                    // Entries in a block are decoded in sequence, so skipped
+                    // contents still have to be read past.
+                    std::io::copy(reader, &mut std::io::sink())?;
+                    return Ok(true);
+                }
+                Err(err) => {
+                    stop_error = Some(err);
+                    return Ok(false);
+                }
+            }
+
+            if header.is_directory {
+                session.push(
+                    &mut files,
+                    ExtractedFile {
+                        path: header.path,
+                        data: Vec::new(),
+                        is_directory: true,
+                        metadata: header.metadata,
+                        digests: Vec::new(),
+                    },
+                );
+            } else {
+                if let Err(err) = session.reserve(entry.size() as usize) {
+                    stop_error = Some(err);
+                    return Ok(false); // Stop iteration
+                }
+
+                let contents = match session.read_contents(reader, &header.path) {
+                    Ok(contents) => contents,
+                    Err(ArchiveError::Io(e)) => return Err(e.into()),
+                    Err(err) => {
+                        stop_error = Some(err);
+                        return Ok(false);
+                    }
+                };
+                if let Some(crc) = expected_crc
+                    && let Err(err) = session.verify_crc(&header.path, &contents, crc)
+                {
+                    // The entry has been read in full, so a test can go on.
+                    if let Err(err) = session.fail_entry(err) {
+                        stop_error = Some(err);
+                        return Ok(false);
+                    }
+                    return Ok(true);
+                }
+
+                session.push(
+                    &mut files,
+                    ExtractedFile {
+                        path: header.path,
+                        metadata: sevenz_metadata(entry, &contents),
+                        data: contents,
+                        is_directory: false,
+                        digests: Vec::new(),
+                    },
+                );
+            }
+            Ok(!session.stops_after_match())
+        };
+
+        let mut result = Ok(true);
+        for &block in &blocks {
+            result = sevenz_rust::BlockDecoder::new(block, &archive, &[], &mut cursor)
+                .for_each_entries(&mut each);
+            if !matches!(result, Ok(true)) {
+                break;
+            }
+        }
+        for &index in &unblocked {
+            if !matches!(result, Ok(true)) {
+                break;
+            }
+            result = each(&archive.files[index], &mut [0u8; 0].as_slice());
+        }
+
+        // Check if we stopped due to size limits or cancellation
+        if let Some(err) = stop_error {
+            return Err(err);
+        }
+
+        // Check for other extraction errors
+        result.map_err(|e| ArchiveError::InvalidArchive(format!("7z extraction error: {}", e)))?;
+
+        Ok(files)
+    }
+
+    fn extract_cab(&self, data: &[u8], session: &mut Session<'_>) -> Result<Vec<ExtractedFile>> {
+        let mut cabinet = cab::Cabinet::new(session.input(Cursor::new(data)))
+            .map_err(|e| ArchiveError::InvalidArchive(format!("CAB error: {}", e)))?;
+
+        // Collect the file list up front, since reading a file borrows the
+        // cabinet mutably. Files are listed folder by folder, which keeps
+        // reads sequential within each compressed folder stream. Every read
+        // still decompresses its folder from the start up to the file, so
+        // the cost grows with the square of the files in a folder.
+        let mut entries = Vec::new();
+        // Files are read by name, which finds the first of several with the
+        // same name, so only that one can be extracted.
+        let mut names = HashSet::new();
+        for folder in cabinet.folder_entries() {
+            if let cab::CompressionType::Quantum(_, _) = folder.compression_type() {
+                return Err(ArchiveError::UnsupportedFormat(
+                    "CAB Quantum compression".to_string(),
+                ));
+            }
+            for file in folder.file_entries() {
+                let header = EntryHeader {
+                    // Cabinet paths use Windows separators; normalize them so
+                    // paths look the same as those from every other format.
+                    path: file.name().replace('\\', "/"),
+                    is_directory: false,
+                    size: Some(u64::from(file.uncompressed_size())),
+                    metadata: EntryMetadata {
+                        // CAB timestamps carry no time zone; they are read as UTC.
+                        mtime: file.datetime().and_then(timestamp::primitive_to_unix),
+                        ..EntryMetadata::default()
+                    },
+                };
+                if session.wants(&header)
+                    && !names.insert(file.name())
+                    && self.duplicates != DuplicatePolicy::KeepFirst
+                {
+                    return Err(ArchiveError::DuplicateEntry {
+                        path: header.path.clone(),
+                        existing: header.path,
+                    });
+                }
+                if session.admit(&header)? {
+                    entries.push((file.name().to_string(), header));
+                }
+            }
+        }
+
+        let mut files = Vec::new();
+
+        for (name, header) in entries {
+            session.check_cancelled()?;
+            session.reserve(header.size.unwrap_or_default() as usize)?;
+
+            let mut reader = cabinet
+                .read_file(&name)
+                .map_err(|e| ArchiveError::InvalidArchive(format!("CAB error: {}", e)))?;
+            let contents = match session.read_contents(&mut reader, &header.path) {
+                Ok(contents) => contents,
+                Err(err) => {
+                    session.fail_entry(err)?;
+                    continue;
+                }
+            };
+
+            session.push(
+                &mut files,
+                ExtractedFile {
+                    path: header.path,
+                    data: contents,
+                    is_directory: false,
+                    metadata: header.metadata,
+                    digests: Vec::new(),
+                },
+            );
+            if session.stops_after_match() {
+                break;
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn extract_rar(&self, data: &[u8], session: &mut Session<'_>) -> Result<Vec<ExtractedFile>> {
+        let archive = rars::ArchiveReader::read(data).map_err(rar_error)?;
+        let legacy_names = archive.family() != rars::ArchiveFamily::Rar50Plus;
+
+        // Declared sizes are checked below before anything is decoded; the
+        // decoder-side limits also catch entries that lie about their size.
+        let options = rars::ArchiveReadOptions::new()
+            .with_max_member_output_bytes(self.max_file_size as u64)
+            .with_max_total_output_bytes(self.max_total_size as u64);
+
+        let mut files = Vec::new();
+        let mut stop_error: Option<ArchiveError> = None;
+        // The callback only hands out a writer; the decoded contents of an
+        // entry are complete once the next entry (or the end) is reached.
+        let mut pending: Option<(String, EntryMetadata, SharedBuffer)> = None;
+
+        let result = archive.extract_with_control(options, |member| {
+            session.add_input(member.meta.packed_size);
+            if let Some((path, metadata, buffer)) = pending.take() {
+                session.push(
+                    &mut files,
+                    ExtractedFile {
+                        path,
+                        data: buffer.take(),
+                        is_directory: false,
+                        metadata,
+                        digests: Vec::new(),
+                    },
+                );
+            }
+            if session.stops_after_match() && !files.is_empty() {
+                return Ok(rars::ExtractionDecision::Stop);
+            }
+            if let Err(err) = session.check_cancelled() {
+                stop_error = Some(err);
+                return Ok(rars::ExtractionDecision::Stop);
+            }
+
+            let mut path = member.meta.name_lossy();
+            if legacy_names {
+                // RAR 1.5-4.x writers on Windows store backslash separators.
+                path = path.replace('\\', "/");
+            }
+            if member.meta.is_redirection {
+                // Links carry no contents of their own.
+                return Ok(rars::ExtractionDecision::Skip);
+            }
+
+            let header = EntryHeader {
+                path,
+                is_directory: member.meta.is_directory,
+                size: Some(member.meta.unpacked_size),
+                metadata: rar_metadata(&member.meta),
+            };
+            match session.admit(&header) {
+                Ok(true) => {}
+                Ok(false) => return Ok(rars::ExtractionDecision::Skip),
+                Err(err) => {
+                    stop_error = Some(err);
+                    return Ok(rars::ExtractionDecision::Stop);
+                }
+            }
+
+            if header.is_directory {
+                session.push(
+                    &mut files,
+                    ExtractedFile {
+                        path: header.path,
+                        data: Vec::new(),
+                        is_directory: true,
+                        metadata: header.metadata,
+                        digests: Vec::new(),
+                    },
+                );
+                return Ok(rars::ExtractionDecision::Skip);
+            }
+
+            if let Err(err) = session.reserve(member.meta.unpacked_size as usize) {
+                stop_error = Some(err);
+                return Ok(rars::ExtractionDecision::Stop);
+            }
+
+            let buffer = SharedBuffer::new(self.cancellation.clone());
+            pending = Some((header.path, header.metadata, buffer.clone()));
+            Ok(rars::ExtractionDecision::Extract(Box::new(buffer)))
+        });
+
+        // Check if we stopped due to size limits or cancellation
+        if let Some(err) = stop_error {
+            return Err(err);
+        }
+
+        result.map_err(rar_error)?;
+
+        if let Some((path, metadata, buffer)) = pending.take() {
+            session.push(
+                &mut files,
+                ExtractedFile {
+                    path,
+                    data: buffer.take(),
+                    is_directory: false,
+                    metadata,
+                    digests: Vec::new(),
+                },
+            );
+        }
+
+        Ok(files)
+    }
+
+    // Single-file decompression methods
+
+    /// Checks a decompressed single file against the size limit and wraps it
+    /// up as the archive's only entry.
+    fn single_file(
+        &self,
+        path: String,
+        decompressed: Vec<u8>,
+        metadata: EntryMetadata,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        if decompressed.len() > self.max_file_size {
+            return Err(ArchiveError::FileTooLarge {
+                size: decompressed.len(),
+                limit: self.max_file_size,
+            });
+        }
+
+        let mut files = Vec::new();
+        session.push(
+            &mut files,
+            ExtractedFile {
+                path,
+                data: decompressed,
+                is_directory: false,
+                metadata,
+                digests: Vec::new(),
+            },
+        );
+        Ok(files)
+    }
+
+    fn extract_single_gz<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decoder = flate2::bufread::GzDecoder::new(input);
+
+        // The header is parsed up front, so the entry can be checked before
+        // decompressing. Header names that aren't safe as a path are ignored.
+        let header_name = decoder
+            .header()
+            .and_then(|h| h.filename())
+            .and_then(|f| std::str::from_utf8(f).ok())
+            .and_then(safe_gzip_name)
+            .map(|s| s.to_string());
+        let source_name = || self.source_file_path(ArchiveFormat::Gz);
+        let path = match self.gzip_name {
+            GzipNamePrecedence::Header => header_name.or_else(source_name),
+            GzipNamePrecedence::SourceFilename => source_name().or(header_name),
+            GzipNamePrecedence::SourceFilenameOnly => source_name(),
+        }
+        .unwrap_or_else(|| "data".to_string());
+        let header = EntryHeader {
+            metadata: decoder.header().map(gzip_metadata).unwrap_or_default(),
+            ..single_file_header(path)
+        };
+        if !session.wants(&header) {
+            return Ok(Vec::new());
+        }
+
+        let decompressed = session.read_contents(&mut decoder, &header.path)?;
+        self.single_file(header.path, decompressed, header.metadata, session)
+    }
+
+    fn extract_single_bz2<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decoder = bzip2::bufread::BzDecoder::new(input);
+        let path = self.derive_single_file_path(ArchiveFormat::Bz2);
+        let decompressed = session.read_contents(&mut decoder, &path)?;
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
+
+    fn extract_single_xz<R: BufRead>(
+        &self,
+        mut input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decompressed = Vec::new();
+        lzma_rs::xz_decompress(&mut input, &mut decompressed)
+            .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+
+        let path = self.derive_single_file_path(ArchiveFormat::Xz);
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
+
+    fn extract_single_lzma<R: BufRead>(
+        &self,
+        mut input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decompressed = Vec::new();
+        lzma_rs::lzma_decompress(&mut input, &mut decompressed)
+            .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+
+        let path = self.derive_single_file_path(ArchiveFormat::Lzma);
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
+
+    fn extract_single_lzip<R: BufRead>(
+        &self,
+        mut input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let decompressed = lzip::decompress(&data)?;
+
+        let path = self.derive_single_file_path(ArchiveFormat::Lzip);
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
+
+    fn extract_single_br<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decoder = brotli::Decompressor::new(input, 4096);
+        let path = self.derive_single_file_path(ArchiveFormat::Br);
+        let decompressed = session.read_contents(&mut decoder, &path)?;
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
+
+    fn extract_single_sz<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decoder = snap::read::FrameDecoder::new(input);
+        let path = self.derive_single_file_path(ArchiveFormat::Sz);
+        let decompressed = session.read_contents(&mut decoder, &path)?;
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
+
+    fn extract_single_lz4<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decoder = lz4::Decoder::new(input)?;
+        let path = self.derive_single_file_path(ArchiveFormat::Lz4);
+        let decompressed = session.read_contents(&mut decoder, &path)?;
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
+
+    fn extract_single_zst<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decoder = zstd::stream::read::Decoder::with_buffer(input)?;
+        let path = self.derive_single_file_path(ArchiveFormat::Zst);
+        let decompressed = session.read_contents(&mut decoder, &path)?;
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
 
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let is_directory = file.is_dir();
+    fn extract_single_z<R: BufRead>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut decoder = LzwDecoder::new(input)?;
+        let path = self.derive_single_file_path(ArchiveFormat::Z);
+        let decompressed = session.read_contents(&mut decoder, &path)?;
+        self.single_file(path, decompressed, EntryMetadata::default(), session)
+    }
+
+    fn process_tar_entries<R: Read>(
+        &self,
+        input: R,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let input = LastBlock::new(input);
+        let last_block = Rc::clone(&input.block);
+        let mut archive = tar::Archive::new(input);
+        let mut files = Vec::new();
+
+        for entry_result in archive.entries()? {
+            session.check_cancelled()?;
+            // The tar crate reports a bad header checksum without saying
+            // where; the header is the last block it read.
+            let mut entry = entry_result
+                .map_err(|err| tar_checksum_mismatch(&last_block.borrow()).unwrap_or(err.into()))?;
+            let metadata = tar_metadata(&entry)?;
+            let header = EntryHeader {
+                path: encoding::decode_name(
+                    metadata.raw_name.as_deref().unwrap_or_default(),
+                    self.name_encoding,
+                ),
+                is_directory: entry.header().entry_type().is_dir(),
+                size: Some(entry.size()),
+                metadata,
+            };
+            // Unread contents are skipped when the next entry is requested.
+            if !session.admit(&header)? {
+                continue;
+            }
+            let EntryHeader {
+                path,
+                is_directory,
+                metadata,
+                ..
+            } = header;
 
             if !is_directory {
-                let size = file.size() as usize;
-                if size > self.max_file_size {
-                    return Err(ArchiveError::FileTooLarge {
-                        size,
-                        limit: self.max_file_size,
-                    });
+                session.reserve(entry.size() as usize)?;
+
+                // Sparse files are stored in pieces, so only plain regular
+                // files are contiguous in the archive.
+                let entry_type = entry.header().entry_type();
+                if let Some(range) = session
+                    .borrow_range(entry.raw_file_position(), entry.size())
+                    .filter(|_| entry_type.is_file())
+                {
+                    // Unread contents are skipped when the next entry is requested.
+                    session.push_borrowed(
+                        &mut files,
+                        ExtractedFile {
+                            path,
+                            data: Vec::new(),
+                            is_directory,
+                            metadata,
+                            digests: Vec::new(),
+                        },
+                        range,
+                    );
+                    if session.stops_after_match() {
+                        break;
+                    }
+                    continue;
                 }
 
-                total_size += size;
-                if total_size > self.max_total_size {
-                    return Err(ArchiveError::TotalSizeTooLarge {
-                        size: total_size,
-                        limit: self.max_total_size,
-                    });
+                let contents = session.read_contents(&mut entry, &path)?;
+
+                session.push(
+                    &mut files,
+                    ExtractedFile {
+                        path,
+                        data: contents,
+                        is_directory,
+                        metadata,
+                        digests: Vec::new(),
+                    },
+                );
+            } else {
+                session.push(
+                    &mut files,
+                    ExtractedFile {
+                        path,
+                        data: Vec::new(),
+                        is_directory,
+                        metadata,
+                        digests: Vec::new(),
+                    },
+                );
+            }
+            if session.stops_after_match() {
+                break;
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn process_ar_entries<R: Read>(
+        &self,
+        archive: &mut ar::Archive<R>,
+        session: &mut Session<'_>,
+    ) -> Result<Vec<ExtractedFile>> {
+        let mut files = Vec::new();
+
+        while let Some(entry_result) = archive.next_entry() {
+            session.check_cancelled()?;
+            let mut entry = entry_result?;
+            let ar_header = entry.header();
+            // The ar reader hides symbol tables only once it has recognized
+            // the GNU or BSD variant.
+            if library::is_special_member(ar_header.identifier()) {
+                continue;
+            }
+            let header = EntryHeader {
+                path: encoding::decode_name(ar_header.identifier(), self.name_encoding),
+                is_directory: false,
+                size: Some(ar_header.size()),
+                metadata: EntryMetadata {
+                    mtime: Some(ar_header.mtime()),
+                    mode: Some(ar_header.mode() & 0o7777),
+                    uid: Some(u64::from(ar_header.uid())),
+                    gid: Some(u64::from(ar_header.gid())),
+                    raw_name: Some(ar_header.identifier().to_vec()),
+                    ..EntryMetadata::default()
+                },
+            };
+            if !session.admit(&header)? {
+                continue;
+            }
+
+            session.reserve(ar_header.size() as usize)?;
+
+            // The reader has just read the header, so it sits at the start of
+            // the contents.
+            if let Some(range) = session.borrow_range(session.input_position(), ar_header.size()) {
+                session.push_borrowed(
+                    &mut files,
+                    ExtractedFile {
+                        path: header.path,
+                        data: Vec::new(),
+                        is_directory: false,
+                        metadata: header.metadata,
+                        digests: Vec::new(),
+                    },
+                    range,
+                );
+                if session.stops_after_match() {
+                    break;
                 }
+                continue;
+            }
 
-                let mut contents = Vec::new();
-                file.read_to_end(&mut contents)?;
+            let contents = session.read_contents(&mut entry, &header.path)?;
 
-                files.push(ExtractedFile {
-                    path: file.name().to_string(),
+            session.push(
+                &mut files,
+                ExtractedFile {
+                    path: header.path,
                     data: contents,
-                    is_directory,
-                });
-            } else {
-                files.push(ExtractedFile {
-                    path: file.name().to_string(),
-                    data: Vec::new(),
-                    is_directory,
-                });
+                    is_directory: false,
+                    metadata: header.metadata,
+                    digests: Vec::new(),
+                },
+            );
+            if session.stops_after_match() {
+                break;
             }
         }
 
         Ok(files)
     }
+}
 
-    fn extract_tar(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let mut archive = tar::Archive::new(cursor);
-        self.process_tar_entries(&mut archive)
-    }
+/// Per-call state shared by the per-format extraction loops: which entries to
+/// read, and how much has been read so far.
+struct Session<'a> {
+    extractor: &'a ArchiveExtractor,
+    total_size: usize,
+    /// When set, only the entry at this path is read, and the loops stop as
+    /// soon as it has been.
+    target: Option<&'a str>,
+    entries_done: usize,
+    bytes_out: u64,
+    /// Shared with every [`Input`] handed out, which record how far into the
+    /// archive data they have read.
+    bytes_in: Rc<Cell<u64>>,
+    /// When set, extracted entries are handed here instead of collected.
+    sink: Option<EntrySink<'a>>,
+    /// Set once the sink has stopped accepting entries.
+    detached: bool,
+    /// The length of the archive data, when entries stored without
+    /// compression should borrow from it instead of being copied.
+    zero_copy_len: Option<usize>,
+    /// Indices into the returned entries, and the ranges of the archive data
+    /// holding their contents, for entries pushed without their contents.
+    borrowed: Vec<(usize, Range<usize>)>,
+    /// When set, the call is an integrity test: entries are recorded here
+    /// and their contents dropped.
+    checks: Option<Vec<EntryCheck>>,
+    /// The path of the entry whose contents last failed to read, and how
+    /// much of them had been read, for attributing the failure in a test.
+    failed_read: Option<(String, u64)>,
+    /// When set, only digests are wanted: entries are recorded here and
+    /// their contents dropped.
+    hashed: Option<Vec<HashedEntry>>,
+    /// Digests of the contents last read in full, computed as they were
+    /// read, and their length.
+    streamed_digests: Option<(usize, Vec<Digest>)>,
+    /// The paths of the entries admitted so far, for the duplicate policy.
+    seen: SeenPaths,
+}
 
-    fn extract_ar(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let mut archive = ar::Archive::new(cursor);
-        self.process_ar_entries(&mut archive)
-    }
+/// Receives extracted entries one at a time, returning `false` once it no
+/// longer wants any.
+pub(crate) type EntrySink<'a> = Box<dyn FnMut(ExtractedFile) -> bool + 'a>;
 
-    fn extract_deb(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let mut archive = ar::Archive::new(cursor);
-        self.process_ar_entries(&mut archive)
-    }
+/// Progress is reported within an entry every time this much more of its
+/// contents has been read.
+const PROGRESS_CHUNK: u64 = 1024 * 1024;
 
-    fn extract_tar_gz(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let decoder = flate2::read::GzDecoder::new(cursor);
-        let mut archive = tar::Archive::new(decoder);
-        self.process_tar_entries(&mut archive)
+impl<'a> Session<'a> {
+    fn new(extractor: &'a ArchiveExtractor, target: Option<&'a str>) -> Self {
+        Self {
+            extractor,
+            total_size: 0,
+            target,
+            entries_done: 0,
+            bytes_out: 0,
+            bytes_in: Rc::default(),
+            sink: None,
+            detached: false,
+            zero_copy_len: None,
+            borrowed: Vec::new(),
+            checks: None,
+            failed_read: None,
+            hashed: None,
+            streamed_digests: None,
+            seen: SeenPaths::new(extractor.path_folding),
+        }
     }
 
-    fn extract_tar_bz2(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let decoder = bzip2::read::BzDecoder::new(cursor);
-        let mut archive = tar::Archive::new(decoder);
-        self.process_tar_entries(&mut archive)
+    fn with_sink(mut self, sink: EntrySink<'a>) -> Self {
+        self.sink = Some(sink);
+        self
     }
 
-    fn extract_tar_xz(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let mut output = Vec::new();
-        lzma_rs::xz_decompress(&mut cursor.clone(), &mut output)
-            .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
-        let cursor = Cursor::new(output);
-        let mut archive = tar::Archive::new(cursor);
-        self.process_tar_entries(&mut archive)
+    /// Wraps the archive data in a reader that counts the bytes consumed and
+    /// fails once the extraction is cancelled.
+    fn input<R>(&self, inner: R) -> Input<R> {
+        Input {
+            inner,
+            position: 0,
+            bytes_in: Rc::clone(&self.bytes_in),
+            cancellation: self.extractor.cancellation.clone(),
+        }
     }
 
-    fn extract_tar_zst(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let decoder = zstd::stream::read::Decoder::new(cursor)?;
-        let mut archive = tar::Archive::new(decoder);
-        self.process_tar_entries(&mut archive)
+    /// Counts input consumed by a decoder that does not read through
+    /// [`Input`].
+    fn add_input(&self, size: u64) {
+        self.bytes_in.set(self.bytes_in.get() + size);
     }
 
-    fn extract_tar_lz4(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let decoder = lz4::Decoder::new(cursor)?;
-        let mut archive = tar::Archive::new(decoder);
-        self.process_tar_entries(&mut archive)
+    fn is_cancelled(&self) -> bool {
+        self.detached
+            || self
+                .extractor
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
     }
 
-    fn extract_7z(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let mut cursor = Cursor::new(data);
-        let len = cursor.get_ref().len() as u64;
-
-        let mut archive = sevenz_rust::SevenZReader::new(&mut cursor, len, "".into())
-            .map_err(|e| ArchiveError::InvalidArchive(format!("7z error: {}", e)))?;
+    fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(ArchiveError::Cancelled);
+        }
+        Ok(())
+    }
 
-        let mut files = Vec::new();
-        let mut total_size = 0usize;
-        let mut size_error: Option<ArchiveError> = None;
+    /// Runs an extraction, reporting cancellation as such.
+    fn run<T>(&mut self, extract: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.check_cancelled()?;
+        let result = extract(self);
+        // Cancellation inside a decoder surfaces as whatever error the decoder
+        // makes of the failed read; report it as what it is.
+        if result.is_err() && self.is_cancelled() {
+            return Err(ArchiveError::Cancelled);
+        }
+        result
+    }
 
-        // Single-pass extraction: validate sizes and extract contents in one iteration
-        let result = archive.for_each_entries(|entry, reader| {
-            if entry.is_directory() {
-                files.push(ExtractedFile {
-                    path: entry.name().to_string(),
-                    data: Vec::new(),
-                    is_directory: true,
-                });
-            } else {
-                let size = entry.size() as usize;
-                if size > self.max_file_size {
-                    size_error = Some(ArchiveError::FileTooLarge {
-                        size,
-                        limit: self.max_file_size,
-                    });
-                    return Ok(false); // Stop iteration
-                }
+    /// Reads an entry's contents in chunks, reporting progress and checking
+    /// for cancellation after each.
+    fn read_contents<R: Read + ?Sized>(&mut self, reader: &mut R, path: &str) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.read_into(reader, path, &mut contents)?;
+        Ok(contents)
+    }
 
-                total_size += size;
-                if total_size > self.max_total_size {
-                    size_error = Some(ArchiveError::TotalSizeTooLarge {
-                        size: total_size,
-                        limit: self.max_total_size,
-                    });
-                    return Ok(false); // Stop iteration
+    /// Like [`read_contents`](Self::read_contents), but keeps what was read
+    /// when reading fails.
+    fn read_into<R: Read + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        path: &str,
+        contents: &mut Vec<u8>,
+    ) -> Result<()> {
+        // Digests are fed each chunk while it is fresh in the cache.
+        let mut hashers = Hashers::new(&self.extractor.hashes);
+        self.streamed_digests = None;
+        let result = loop {
+            if let Err(err) = self.check_cancelled() {
+                break Err(err);
+            }
+            let start = contents.len();
+            match (&mut *reader).take(PROGRESS_CHUNK).read_to_end(contents) {
+                Ok(0) => break Ok(()),
+                Ok(_) => {
+                    hashers.update(&contents[start..]);
+                    self.report(Some(path), self.bytes_out + contents.len() as u64);
                 }
+                Err(err) => break Err(err.into()),
+            }
+        };
+        match result {
+            Ok(()) if !hashers.is_empty() => {
+                self.streamed_digests = Some((contents.len(), hashers.finish()));
+            }
+            Ok(()) => {}
+            Err(_) => self.failed_read = Some((path.to_string(), contents.len() as u64)),
+        }
+        result
+    }
 
-                let mut contents = Vec::new();
-                reader.read_to_end(&mut contents)?;
+    /// Like [`read_contents`](Self::read_contents), for a decoder that checks
+    /// the contents against their CRC-32 itself. Such decoders fail the read
+    /// that finds a mismatch with a plain I/O error once all of the contents
+    /// have been handed over, which is turned into
+    /// [`ArchiveError::ChecksumMismatch`].
+    fn read_checked<R: Read + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        path: &str,
+        size: u64,
+        crc: u32,
+    ) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        match self.read_into(reader, path, &mut contents) {
+            Ok(()) => Ok(contents),
+            Err(ArchiveError::Io(err)) if contents.len() as u64 == size => {
+                self.verify_crc(path, &contents, crc)?;
+                Err(err.into())
+            }
+            Err(err) => Err(err),
+        }
+    }
 
-                files.push(ExtractedFile {
-                    path: entry.name().to_string(),
-                    data: contents,
-                    is_directory: false,
-                });
+    /// Checks contents read in full against the CRC-32 stored for them.
+    fn verify_crc(&mut self, path: &str, contents: &[u8], expected: u32) -> Result<()> {
+        match crc_mismatch(path, contents, expected) {
+            Some(err) => {
+                self.failed_read = Some((path.to_string(), contents.len() as u64));
+                Err(err)
             }
-            Ok(true)
-        });
+            None => Ok(()),
+        }
+    }
 
-        // Check if we stopped due to size limits
-        if let Some(err) = size_error {
+    /// In a test, records a failure against the entry it belongs to, so that
+    /// the caller can go on to the next entry. Cancellation, failures that
+    /// belong to no entry, and every failure outside a test are returned.
+    fn fail_entry(&mut self, err: ArchiveError) -> Result<()> {
+        self.streamed_digests = None;
+        let Some(checks) = &mut self.checks else {
             return Err(err);
+        };
+        let (path, size) = match (self.failed_read.take(), &err) {
+            (_, ArchiveError::Cancelled) => return Err(err),
+            (Some(read), _) => read,
+            (None, ArchiveError::ChecksumMismatch { path, .. }) => (path.clone(), 0),
+            (None, _) => return Err(err),
+        };
+        checks.push(EntryCheck {
+            path,
+            size,
+            error: Some(err),
+        });
+        Ok(())
+    }
+
+    /// Adds an extracted entry to the results, or hands it to the sink, and
+    /// reports it.
+    fn push(&mut self, files: &mut Vec<ExtractedFile>, mut file: ExtractedFile) {
+        let hashes = &self.extractor.hashes;
+        if !hashes.is_empty() && !file.is_directory && file.digests.is_empty() {
+            file.digests = match self.streamed_digests.take() {
+                Some((len, digests)) if len == file.data.len() => digests,
+                // Contents that were not read through `read_into`.
+                _ => Hashers::digest(hashes, &file.data),
+            };
         }
+        self.entries_done += 1;
+        self.bytes_out += file.data.len() as u64;
+        self.report(Some(&file.path), self.bytes_out);
+        self.store(files, file);
+    }
 
-        // Check for other extraction errors
-        result.map_err(|e| ArchiveError::InvalidArchive(format!("7z extraction error: {}", e)))?;
+    /// Like [`push`](Self::push), for an entry that has already been counted
+    /// and reported.
+    fn store(&mut self, files: &mut Vec<ExtractedFile>, file: ExtractedFile) {
+        if let Some(checks) = &mut self.checks {
+            checks.push(EntryCheck {
+                path: file.path,
+                size: file.data.len() as u64,
+                error: None,
+            });
+            return;
+        }
+        if let Some(hashed) = &mut self.hashed {
+            if !file.is_directory && !file.is_symlink() {
+                hashed.push(HashedEntry {
+                    path: file.path,
+                    size: file.data.len() as u64,
+                    digests: file.digests,
+                });
+            }
+            return;
+        }
+        match &mut self.sink {
+            Some(sink) => self.detached |= !sink(file),
+            None => files.push(file),
+        }
+    }
 
-        Ok(files)
+    /// Returns the range of the archive data an entry's contents can be
+    /// borrowed from, if borrowing was asked for and the range is in bounds.
+    fn borrow_range(&self, start: u64, size: u64) -> Option<Range<usize>> {
+        let start = usize::try_from(start).ok()?;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+        (end <= self.zero_copy_len?).then_some(start..end)
     }
 
-    // Single-file decompression methods
+    /// Like [`push`](Self::push), for an entry whose contents are `range` of
+    /// the archive data.
+    fn push_borrowed(
+        &mut self,
+        files: &mut Vec<ExtractedFile>,
+        file: ExtractedFile,
+        range: Range<usize>,
+    ) {
+        debug_assert!(self.sink.is_none(), "borrowed entries are never streamed");
+        self.entries_done += 1;
+        self.bytes_out += range.len() as u64;
+        self.report(Some(&file.path), self.bytes_out);
+        self.borrowed.push((files.len(), range));
+        files.push(file);
+    }
 
-    fn extract_single_gz(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let mut decoder = flate2::read::GzDecoder::new(cursor);
-        let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed)?;
+    /// How far the archive data has been read. For an input read front to
+    /// back, this is where the reader is.
+    fn input_position(&self) -> u64 {
+        self.bytes_in.get()
+    }
 
-        if decompressed.len() > self.max_file_size {
-            return Err(ArchiveError::FileTooLarge {
-                size: decompressed.len(),
-                limit: self.max_file_size,
+    fn report(&self, current_path: Option<&str>, bytes_out: u64) {
+        if let Some(callback) = &self.extractor.progress {
+            (callback.0)(&Progress {
+                entries_done: self.entries_done,
+                bytes_in: self.bytes_in.get(),
+                bytes_out,
+                current_path,
             });
         }
+    }
 
-        // Try to extract original filename from gzip header, fall back to
-        // source_filename-derived path, then "data"
-        let fallback = self.derive_single_file_path(ArchiveFormat::Gz);
-        let path = decoder
-            .header()
-            .and_then(|h| h.filename())
-            .and_then(|f| std::str::from_utf8(f).ok())
-            .map(|s| s.to_string())
-            .unwrap_or(fallback);
-
-        Ok(vec![ExtractedFile {
-            path,
-            data: decompressed,
-            is_directory: false,
-        }])
+    /// Returns whether an entry should be read, going by the requested path
+    /// and the extractor's include/exclude patterns and filter.
+    fn wants(&self, header: &EntryHeader) -> bool {
+        let extractor = self.extractor;
+        let key = entry_key(&header.path);
+        self.target
+            .is_none_or(|target| same_entry(target, &header.path))
+            && (extractor.include.is_empty() || extractor.include.iter().any(|g| g.matches(key)))
+            && !extractor.exclude.iter().any(|g| g.matches(key))
+            && extractor
+                .filter
+                .as_ref()
+                .is_none_or(|filter| (filter.0)(header))
     }
 
-    fn extract_single_bz2(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let mut decoder = bzip2::read::BzDecoder::new(cursor);
-        let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed)?;
+    /// Like [`wants`](Self::wants), and applies the duplicate policy to the
+    /// entries that are wanted.
+    fn admit(&mut self, header: &EntryHeader) -> Result<bool> {
+        if !self.wants(header) {
+            return Ok(false);
+        }
+        let policy = self.extractor.duplicates;
+        if policy == DuplicatePolicy::KeepAll {
+            return Ok(true);
+        }
+        let Some(existing) = self.seen.insert(&header.path, header.is_directory) else {
+            return Ok(true);
+        };
+        match policy {
+            DuplicatePolicy::KeepFirst => Ok(false),
+            // Earlier copies are dropped once the extraction is done, which
+            // is too late for entries already handed to a sink.
+            DuplicatePolicy::KeepLast if self.sink.is_none() => Ok(true),
+            _ => Err(ArchiveError::DuplicateEntry {
+                path: header.path.clone(),
+                existing,
+            }),
+        }
+    }
 
-        if decompressed.len() > self.max_file_size {
-            return Err(ArchiveError::FileTooLarge {
-                size: decompressed.len(),
-                limit: self.max_file_size,
-            });
+    /// Under [`DuplicatePolicy::KeepLast`], drops every entry that a later
+    /// one with the same path replaces, from the results and from what a test
+    /// or hash-only extraction recorded.
+    fn keep_last(&mut self, files: Vec<ExtractedFile>) -> Vec<ExtractedFile> {
+        if self.extractor.duplicates != DuplicatePolicy::KeepLast {
+            return files;
+        }
+        let folding = self.extractor.path_folding;
+        if let Some(checks) = &mut self.checks {
+            let keep = duplicates::last_occurrences(folding, checks.iter().map(|c| &*c.path));
+            let mut keep = keep.into_iter();
+            checks.retain(|_| keep.next().unwrap_or(true));
+        }
+        if let Some(hashed) = &mut self.hashed {
+            let keep = duplicates::last_occurrences(folding, hashed.iter().map(|h| &*h.path));
+            let mut keep = keep.into_iter();
+            hashed.retain(|_| keep.next().unwrap_or(true));
         }
 
-        Ok(vec![ExtractedFile {
-            path: self.derive_single_file_path(ArchiveFormat::Bz2),
-            data: decompressed,
-            is_directory: false,
-        }])
+        let keep = duplicates::last_occurrences(folding, files.iter().map(|f| &*f.path));
+        // Borrowed contents are recorded by index into the results.
+        let mut new_index = Vec::with_capacity(keep.len());
+        let mut kept = 0;
+        for &keep in &keep {
+            new_index.push(kept);
+            kept += usize::from(keep);
+        }
+        self.borrowed = std::mem::take(&mut self.borrowed)
+            .into_iter()
+            .filter(|(i, _)| keep[*i])
+            .map(|(i, range)| (new_index[i], range))
+            .collect();
+        files
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(file, keep)| keep.then_some(file))
+            .collect()
     }
 
-    fn extract_single_xz(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let mut cursor = Cursor::new(data);
-        let mut decompressed = Vec::new();
-        lzma_rs::xz_decompress(&mut cursor, &mut decompressed)
-            .map_err(|e| ArchiveError::InvalidArchive(e.to_string()))?;
+    /// Returns whether a loop is done once it has read a wanted entry. Not
+    /// when the duplicate policy has to see the entries at the same path
+    /// further on.
+    fn stops_after_match(&self) -> bool {
+        self.target.is_some()
+            && matches!(
+                self.extractor.duplicates,
+                DuplicatePolicy::KeepAll | DuplicatePolicy::KeepFirst
+            )
+    }
 
-        if decompressed.len() > self.max_file_size {
+    /// Checks the size of an entry about to be read against the limits and
+    /// counts it toward the total.
+    fn reserve(&mut self, size: usize) -> Result<()> {
+        let extractor = self.extractor;
+        if size > extractor.max_file_size {
             return Err(ArchiveError::FileTooLarge {
-                size: decompressed.len(),
-                limit: self.max_file_size,
+                size,
+                limit: extractor.max_file_size,
             });
         }
 
-        Ok(vec![ExtractedFile {
-            path: self.derive_single_file_path(ArchiveFormat::Xz),
-            data: decompressed,
-            is_directory: false,
-        }])
+        self.total_size += size;
+        if self.total_size > extractor.max_total_size {
+            return Err(ArchiveError::TotalSizeTooLarge {
+                size: self.total_size,
+                limit: extractor.max_total_size,
+            });
+        }
+        Ok(())
     }
+}
 
-    fn extract_single_lz4(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let mut decoder = lz4::Decoder::new(cursor)?;
-        let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed)?;
+/// The archive data as handed to the format decoders: counts the bytes
+/// consumed and fails once the extraction is cancelled.
+struct Input<R> {
+    inner: R,
+    position: u64,
+    bytes_in: Rc<Cell<u64>>,
+    cancellation: Option<CancellationToken>,
+}
 
-        if decompressed.len() > self.max_file_size {
-            return Err(ArchiveError::FileTooLarge {
-                size: decompressed.len(),
-                limit: self.max_file_size,
-            });
+impl<R> Input<R> {
+    fn check_cancelled(&self) -> io::Result<()> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(io::Error::other("extraction cancelled"));
         }
+        Ok(())
+    }
 
-        Ok(vec![ExtractedFile {
-            path: self.derive_single_file_path(ArchiveFormat::Lz4),
-            data: decompressed,
-            is_directory: false,
-        }])
+    /// Records the furthest position read, which for seeking decoders is not
+    /// the number of bytes read.
+    fn advance_to(&mut self, position: u64) {
+        self.position = position;
+        if position > self.bytes_in.get() {
+            self.bytes_in.set(position);
+        }
     }
+}
 
-    fn extract_single_zst(&self, data: &[u8]) -> Result<Vec<ExtractedFile>> {
-        let cursor = Cursor::new(data);
-        let mut decoder = zstd::stream::read::Decoder::new(cursor)?;
-        let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed)?;
+impl<R: Read> Read for Input<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check_cancelled()?;
+        let read = self.inner.read(buf)?;
+        self.advance_to(self.position + read as u64);
+        Ok(read)
+    }
+}
 
-        if decompressed.len() > self.max_file_size {
-            return Err(ArchiveError::FileTooLarge {
-                size: decompressed.len(),
-                limit: self.max_file_size,
-            });
-        }
+impl<R: BufRead> BufRead for Input<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.check_cancelled()?;
+        self.inner.fill_buf()
+    }
 
-        Ok(vec![ExtractedFile {
-            path: self.derive_single_file_path(ArchiveFormat::Zst),
-            data: decompressed,
-            is_directory: false,
-        }])
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+        self.advance_to(self.position + amount as u64);
     }
+}
 
-    fn process_tar_entries<R: Read>(
-        &self,
-        archive: &mut tar::Archive<R>,
-    ) -> Result<Vec<ExtractedFile>> {
-        let mut files = Vec::new();
-        let mut total_size = 0usize;
+impl<R: Seek> Seek for Input<R> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let position = self.inner.seek(position)?;
+        self.position = position;
+        Ok(position)
+    }
+}
 
-        for entry_result in archive.entries()? {
-            let mut entry = entry_result?;
-            let path = entry.path()?.to_string_lossy().to_string();
-            let is_directory = entry.header().entry_type().is_dir();
+/// Builds the header of the one entry in a single-file compression format,
+/// whose size is unknown until it has been decompressed.
+fn single_file_header(path: String) -> EntryHeader {
+    EntryHeader {
+        path,
+        is_directory: false,
+        size: None,
+        metadata: EntryMetadata::default(),
+    }
+}
 
-            if !is_directory {
-                let size = entry.size() as usize;
-                if size > self.max_file_size {
-                    return Err(ArchiveError::FileTooLarge {
-                        size,
-                        limit: self.max_file_size,
-                    });
-                }
+/// Reads the metadata recorded in a gzip header. A zero timestamp means none
+/// was recorded.
+fn gzip_metadata(header: &flate2::GzHeader) -> EntryMetadata {
+    EntryMetadata {
+        mtime: Some(u64::from(header.mtime())).filter(|&mtime| mtime != 0),
+        raw_name: header.filename().map(<[u8]>::to_vec),
+        comment: header.comment().map(gzip_text),
+        os: Some(header.operating_system()),
+        extra: header.extra().map(<[u8]>::to_vec),
+        ..EntryMetadata::default()
+    }
+}
 
-                total_size += size;
-                if total_size > self.max_total_size {
-                    return Err(ArchiveError::TotalSizeTooLarge {
-                        size: total_size,
-                        limit: self.max_total_size,
-                    });
-                }
+/// Reduces a gzip header name to a bare file name. Directories are stripped,
+/// and names that step out of the directory with `..`, contain control
+/// characters or leave nothing are rejected.
+fn safe_gzip_name(name: &str) -> Option<&str> {
+    let mut components = name.split(['/', '\\']);
+    if name.chars().any(char::is_control) || components.clone().any(|c| c == "..") {
+        return None;
+    }
+    components
+        .next_back()
+        .filter(|base| !base.is_empty() && *base != ".")
+}
 
-                let mut contents = Vec::new();
-                entry.read_to_end(&mut contents)?;
+/// Decodes text from a gzip header, which is Latin-1 by the specification
+/// but often UTF-8 in practice.
+fn gzip_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&byte| char::from(byte)).collect(),
+    }
+}
 
-                files.push(ExtractedFile {
-                    path,
-                    data: contents,
-                    is_directory,
-                });
-            } else {
-                files.push(ExtractedFile {
-                    path,
-                    data: Vec::new(),
-                    is_directory,
-                });
-            }
+/// Returns the error for contents that do not match their stored CRC-32.
+fn crc_mismatch(path: &str, contents: &[u8], expected: u32) -> Option<ArchiveError> {
+    let actual = crc32fast::hash(contents);
+    (actual != expected).then(|| ArchiveError::ChecksumMismatch {
+        path: path.to_string(),
+        expected,
+        actual,
+    })
+}
+
+/// Returns the error for a TAR header block whose stored checksum does not
+/// match its contents.
+fn tar_checksum_mismatch(block: &[u8]) -> Option<ArchiveError> {
+    let block: &[u8; 512] = block.try_into().ok()?;
+    let header = tar::Header::from_byte_slice(block);
+    let expected = header.cksum().ok()?;
+    // The checksum is summed with its own field taken as spaces.
+    let actual = block
+        .iter()
+        .enumerate()
+        .map(|(i, &byte)| u32::from(if (148..156).contains(&i) { b' ' } else { byte }))
+        .sum();
+    (actual != expected).then(|| ArchiveError::ChecksumMismatch {
+        path: String::from_utf8_lossy(&header.path_bytes()).into_owned(),
+        expected,
+        actual,
+    })
+}
+
+/// Compares two entry paths, ignoring a leading `./` and the trailing slash
+/// of directories.
+fn same_entry(a: &str, b: &str) -> bool {
+    entry_key(a) == entry_key(b)
+}
+
+pub(crate) fn entry_key(path: &str) -> &str {
+    path.strip_prefix("./")
+        .unwrap_or(path)
+        .trim_end_matches('/')
+}
+
+/// Looks an entry up in a ZIP central directory, with or without the trailing
+/// slash that marks directories.
+fn zip_index_for_name<R: Read + std::io::Seek>(
+    archive: &zip::ZipArchive<R>,
+    path: &str,
+) -> Option<usize> {
+    let key = entry_key(path);
+    archive
+        .index_for_name(path)
+        .or_else(|| archive.index_for_name(key))
+        .or_else(|| archive.index_for_name(&format!("{}/", key)))
+}
+
+/// Rejects ZIP archives in which two entries share bytes, from the start of
+/// their local header to the end of their compressed data. This catches
+/// overlapping-file zip bombs, whose central directory records point again
+/// and again at the same data, without decompressing anything.
+fn check_zip_overlaps<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> Result<()> {
+    let mut spans = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        let end = file.data_start().saturating_add(file.compressed_size());
+        spans.push((file.header_start()..end, file.name().to_string()));
+    }
+    spans.sort_by_key(|(span, _)| span.start);
+
+    // Sorted by start, the entries are disjoint exactly if each one starts
+    // after the previous one ends.
+    for pair in spans.windows(2) {
+        let [(previous, other), (span, path)] = pair else {
+            unreachable!()
+        };
+        if span.start < previous.end {
+            return Err(ArchiveError::OverlappingEntries {
+                path: path.clone(),
+                other: other.clone(),
+            });
         }
+    }
+    Ok(())
+}
 
-        Ok(files)
+/// Reads the metadata recorded in a TAR entry's header, including PAX and
+/// GNU long names.
+fn tar_metadata<R: Read>(entry: &tar::Entry<'_, R>) -> Result<EntryMetadata> {
+    let header = entry.header();
+    let link_target = if header.entry_type().is_symlink() {
+        entry
+            .link_name()?
+            .map(|target| target.to_string_lossy().into_owned())
+    } else {
+        None
+    };
+    Ok(EntryMetadata {
+        mtime: header.mtime().ok(),
+        mode: header.mode().ok().map(|mode| mode & 0o7777),
+        uid: header.uid().ok(),
+        gid: header.gid().ok(),
+        owner: non_empty(header.username().ok().flatten()),
+        group: non_empty(header.groupname().ok().flatten()),
+        link_target,
+        raw_name: Some(entry.path_bytes().into_owned()),
+        ..EntryMetadata::default()
+    })
+}
+
+fn non_empty(name: Option<&str>) -> Option<String> {
+    name.filter(|name| !name.is_empty()).map(str::to_string)
+}
+
+/// Windows attribute flag signalling Unix mode bits in the upper 16 bits.
+pub(crate) const SEVENZ_UNIX_EXTENSION: u32 = 0x8000;
+
+/// Reads the modification time and, for archives written on Unix, the mode
+/// bits that p7zip keeps in the upper half of the Windows attributes.
+fn sevenz_metadata(entry: &sevenz_rust::SevenZArchiveEntry, contents: &[u8]) -> EntryMetadata {
+    let mut metadata = EntryMetadata::default();
+    if entry.has_last_modified_date {
+        metadata.mtime = u64::try_from(entry.last_modified_date().to_unix_time()).ok();
+    }
+    if entry.has_windows_attributes && entry.windows_attributes() & SEVENZ_UNIX_EXTENSION != 0 {
+        let mode = entry.windows_attributes() >> 16;
+        metadata.mode = Some(mode & 0o7777);
+        // Like ZIP, 7-Zip stores a symlink's target as the entry's contents.
+        if mode & 0o170000 == 0o120000 {
+            metadata.link_target = Some(String::from_utf8_lossy(contents).into_owned());
+        }
     }
+    metadata
+}
 
-    fn process_ar_entries<R: Read>(
-        &self,
-        archive: &mut ar::Archive<R>,
-    ) -> Result<Vec<ExtractedFile>> {
-        let mut files = Vec::new();
-        let mut total_size = 0usize;
+/// Reads the metadata of a RAR member. Only RAR 5 stores Unix timestamps, and
+/// attributes are only Unix mode bits when the archive was made on Unix.
+fn rar_metadata(meta: &rars::ArchiveMemberMeta) -> EntryMetadata {
+    let rar5 = meta.family == rars::ArchiveFamily::Rar50Plus;
+    let unix_host = if rar5 { Some(1) } else { Some(3) };
+    EntryMetadata {
+        mtime: meta.file_time.filter(|_| rar5).map(u64::from),
+        mode: (meta.host_os == unix_host).then_some(meta.file_attr as u32 & 0o7777),
+        ..EntryMetadata::default()
+    }
+}
 
-        while let Some(entry_result) = archive.next_entry() {
-            let mut entry = entry_result?;
-            let path = String::from_utf8_lossy(entry.header().identifier()).to_string();
+/// Converts a RAR library error into the matching [`ArchiveError`].
+fn rar_error(err: rars::Error) -> ArchiveError {
+    let checksums = match *err.root_cause() {
+        rars::Error::CrcMismatch { expected, actual } => {
+            Some((u32::from(expected), u32::from(actual)))
+        }
+        rars::Error::Crc32Mismatch { expected, actual } => Some((expected, actual)),
+        _ => None,
+    };
+    if let (Some((expected, actual)), Some((name, _))) = (checksums, err.entry_context()) {
+        return ArchiveError::ChecksumMismatch {
+            path: String::from_utf8_lossy(name).into_owned(),
+            expected,
+            actual,
+        };
+    }
+    if matches!(
+        err.root_cause(),
+        rars::Error::TooShort | rars::Error::UnsupportedSignature
+    ) {
+        return ArchiveError::InvalidArchive(format!("RAR error: {}", err));
+    }
+    match err.kind() {
+        rars::ErrorKind::PasswordRequired | rars::ErrorKind::BadPassword => {
+            ArchiveError::UnsupportedFormat("encrypted RAR archive".to_string())
+        }
+        rars::ErrorKind::UnsupportedFormat | rars::ErrorKind::UnsupportedFeature => {
+            ArchiveError::UnsupportedFormat(format!("RAR: {}", err))
+        }
+        _ => ArchiveError::InvalidArchive(format!("RAR error: {}", err)),
+    }
+}
 
-            let size = entry.header().size() as usize;
-            if size > self.max_file_size {
-                return Err(ArchiveError::FileTooLarge {
-                    size,
-                    limit: self.max_file_size,
-                });
-            }
+/// Passes reads through, keeping the last 512-byte block read.
+struct LastBlock<R> {
+    inner: R,
+    block: Rc<RefCell<Vec<u8>>>,
+}
 
-            total_size += size;
-            if total_size > self.max_total_size {
-                return Err(ArchiveError::TotalSizeTooLarge {
-                    size: total_size,
-                    limit: self.max_total_size,
-                });
-            }
+impl<R> LastBlock<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            block: Rc::default(),
+        }
+    }
+}
 
-            let mut contents = Vec::new();
-            entry.read_to_end(&mut contents)?;
+impl<R: Read> Read for LastBlock<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        let mut block = self.block.borrow_mut();
+        if read >= 512 {
+            block.clear();
+            block.extend_from_slice(&buf[read - 512..read]);
+        } else {
+            block.extend_from_slice(&buf[..read]);
+            let excess = block.len().saturating_sub(512);
+            block.drain(..excess);
+        }
+        Ok(read)
+    }
+}
 
-            files.push(ExtractedFile {
-                path,
-                data: contents,
-                is_directory: false,
-            });
+/// A clonable in-memory writer, for decoders that take ownership of their
+/// output but whose contents must be collected afterwards. Writes fail once
+/// the extraction is cancelled, which stops the decoder mid-entry.
+#[derive(Clone)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>, Option<CancellationToken>);
+
+impl SharedBuffer {
+    fn new(cancellation: Option<CancellationToken>) -> Self {
+        Self(Rc::default(), cancellation)
+    }
+
+    fn take(&self) -> Vec<u8> {
+        self.0.take()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.1.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            return Err(io::Error::other("extraction cancelled"));
         }
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
 
-        Ok(files)
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
@@ -3,6 +3,8 @@
 //! This module defines the supported archive and compression formats.
 
 use mime_type::MimeType;
+use std::fmt;
+use std::str::FromStr;
 
 use crate::ArchiveError;
 
@@ -51,12 +53,15 @@ pub enum ArchiveFormat {
     /// a single archive file. This variant is uncompressed.
     Tar,
 
-    /// Unix ar archive (`.ar`).
+    /// Unix ar archive (`.ar`, `.a`).
     ///
     /// ar (archive) is a file format for collecting multiple files into
     /// a single archive file. The file format is used commonly on unix-like
     /// systems, but the file format itself has never been standardized and
-    /// there are multiple variants of the format.
+    /// there are multiple variants of the format. Static libraries (`.a`)
+    /// are ar archives of object files; their symbol and long-name tables
+    /// are not extracted as files, see
+    /// [`ArchiveExtractor::ar_symbols`](crate::ArchiveExtractor::ar_symbols).
     Ar,
 
     /// Debian package (`.deb`).
@@ -96,6 +101,33 @@ pub enum ArchiveFormat {
     /// over compression ratio. Useful for fast decompression.
     TarLz4,
 
+    /// TAR archive compressed with Unix `compress` (`.tar.Z`, `.taz`).
+    ///
+    /// Combines TAR archiving with the LZW compression of the classic Unix
+    /// `compress` utility. Mostly found in old software distributions.
+    TarZ,
+
+    /// TAR archive with legacy LZMA compression (`.tar.lzma`).
+    ///
+    /// Combines TAR archiving with the LZMA-alone format that predates XZ.
+    TarLzma,
+
+    /// TAR archive with lzip compression (`.tar.lz`, `.tlz`).
+    ///
+    /// Combines TAR archiving with lzip, an LZMA-based format with CRC-32
+    /// and size integrity checks.
+    TarLzip,
+
+    /// TAR archive with Brotli compression (`.tar.br`).
+    ///
+    /// Combines TAR archiving with Brotli compression.
+    TarBr,
+
+    /// TAR archive with framed Snappy compression (`.tar.sz`).
+    ///
+    /// Combines TAR archiving with the Snappy framing format.
+    TarSz,
+
     /// Single file compressed with gzip (`.gz`).
     ///
     /// A single file compressed using the gzip algorithm. If the gzip header
@@ -127,11 +159,62 @@ pub enum ArchiveFormat {
     /// will be named "data" as Zstandard doesn't store original filenames by default.
     Zst,
 
+    /// Single file compressed with Unix `compress` (`.Z`).
+    ///
+    /// A single file compressed using LZW with 9 to 16 bit codes. The
+    /// extracted file will be named "data" as `compress` doesn't store
+    /// original filenames.
+    Z,
+
+    /// Single file compressed with legacy LZMA (`.lzma`).
+    ///
+    /// A single file in the LZMA-alone format written by LZMA Utils and
+    /// `xz --format=lzma`. The extracted file will be named "data" as the
+    /// format doesn't store original filenames.
+    Lzma,
+
+    /// Single file compressed with lzip (`.lz`).
+    ///
+    /// A single file compressed with lzip. Multi-member files are supported,
+    /// and each member's CRC-32 and size trailer is verified. The extracted
+    /// file will be named "data" as lzip doesn't store original filenames.
+    Lzip,
+
+    /// Single file compressed with Brotli (`.br`).
+    ///
+    /// A single file compressed using Brotli, as commonly served for web
+    /// assets. The extracted file will be named "data" as Brotli doesn't
+    /// store original filenames.
+    Br,
+
+    /// Single file compressed with framed Snappy (`.sz`).
+    ///
+    /// A single file in the Snappy framing format. Raw, unframed Snappy
+    /// blocks are not supported. The extracted file will be named "data" as
+    /// Snappy doesn't store original filenames.
+    Sz,
+
     /// 7-Zip archive format (`.7z`).
     ///
     /// 7-Zip is a high-compression archive format that supports multiple
     /// compression algorithms and can achieve excellent compression ratios.
     SevenZ,
+
+    /// Microsoft Cabinet archive (`.cab`).
+    ///
+    /// Cabinet files group their entries into folders, each compressed as a
+    /// single stream. Uncompressed, MSZIP and LZX folders are supported;
+    /// Quantum-compressed folders are rejected as unsupported. Each file is
+    /// read by decompressing its folder from the start, so extracting every
+    /// file of a large compressed folder takes time quadratic in its size.
+    Cab,
+
+    /// RAR archive (`.rar`).
+    ///
+    /// Both the RAR 1.5–4.x and the RAR 5.0+ on-disk formats are supported,
+    /// including stored, compressed and solid archives. Encrypted entries and
+    /// multi-volume sets are not supported.
+    Rar,
 }
 
 impl ArchiveFormat {
@@ -172,23 +255,47 @@ impl ArchiveFormat {
         if lower.ends_with(".tar.lz4") {
             return Ok(Self::TarLz4);
         }
+        if lower.ends_with(".tar.z") {
+            return Ok(Self::TarZ);
+        }
+        if lower.ends_with(".tar.lzma") {
+            return Ok(Self::TarLzma);
+        }
+        if lower.ends_with(".tar.lz") {
+            return Ok(Self::TarLzip);
+        }
+        if lower.ends_with(".tar.br") {
+            return Ok(Self::TarBr);
+        }
+        if lower.ends_with(".tar.sz") {
+            return Ok(Self::TarSz);
+        }
 
         // Check single extensions
         let ext = lower.rsplit('.').next().unwrap_or("");
         match ext {
             "zip" => Ok(Self::Zip),
             "tar" => Ok(Self::Tar),
-            "ar" => Ok(Self::Ar),
+            "ar" | "a" => Ok(Self::Ar),
             "deb" => Ok(Self::Deb),
             "tgz" => Ok(Self::TarGz),
             "tbz2" => Ok(Self::TarBz2),
             "txz" => Ok(Self::TarXz),
+            "taz" => Ok(Self::TarZ),
+            "tlz" => Ok(Self::TarLzip),
             "gz" => Ok(Self::Gz),
             "bz2" => Ok(Self::Bz2),
             "xz" => Ok(Self::Xz),
             "lz4" => Ok(Self::Lz4),
             "zst" => Ok(Self::Zst),
+            "z" => Ok(Self::Z),
+            "lzma" => Ok(Self::Lzma),
+            "lz" => Ok(Self::Lzip),
+            "br" => Ok(Self::Br),
+            "sz" => Ok(Self::Sz),
             "7z" => Ok(Self::SevenZ),
+            "cab" => Ok(Self::Cab),
+            "rar" => Ok(Self::Rar),
             _ => Err(ArchiveError::UnknownFormat),
         }
     }
@@ -218,15 +325,96 @@ impl ArchiveFormat {
             Self::TarXz => "TAR.XZ",
             Self::TarZst => "TAR.ZST",
             Self::TarLz4 => "TAR.LZ4",
+            Self::TarZ => "TAR.Z",
+            Self::TarLzma => "TAR.LZMA",
+            Self::TarLzip => "TAR.LZ",
+            Self::TarBr => "TAR.BR",
+            Self::TarSz => "TAR.SZ",
             Self::Gz => "GZIP",
             Self::Bz2 => "BZIP2",
             Self::Xz => "XZ",
             Self::Lz4 => "LZ4",
             Self::Zst => "ZSTD",
+            Self::Z => "COMPRESS",
+            Self::Lzma => "LZMA",
+            Self::Lzip => "LZIP",
+            Self::Br => "BROTLI",
+            Self::Sz => "SNAPPY",
             Self::SevenZ => "7Z",
+            Self::Cab => "CAB",
+            Self::Rar => "RAR",
+        }
+    }
+
+    /// Returns the format's stable identifier.
+    ///
+    /// The identifier is the format's canonical file extension in lowercase,
+    /// such as `"tar.gz"` or `"7z"`. It is what [`Display`](fmt::Display)
+    /// prints and what [`FromStr`] parses back, and how the format is
+    /// serialized with the `serde` feature. Unlike [`name`](Self::name), it
+    /// will not change between releases.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ArchiveFormat;
+    ///
+    /// assert_eq!(ArchiveFormat::TarGz.as_str(), "tar.gz");
+    /// assert_eq!("tar.gz".parse::<ArchiveFormat>().unwrap(), ArchiveFormat::TarGz);
+    /// assert_eq!(ArchiveFormat::SevenZ.to_string(), "7z");
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Tar => "tar",
+            Self::Ar => "ar",
+            Self::Deb => "deb",
+            Self::TarGz => "tar.gz",
+            Self::TarBz2 => "tar.bz2",
+            Self::TarXz => "tar.xz",
+            Self::TarZst => "tar.zst",
+            Self::TarLz4 => "tar.lz4",
+            Self::TarZ => "tar.z",
+            Self::TarLzma => "tar.lzma",
+            Self::TarLzip => "tar.lz",
+            Self::TarBr => "tar.br",
+            Self::TarSz => "tar.sz",
+            Self::Gz => "gz",
+            Self::Bz2 => "bz2",
+            Self::Xz => "xz",
+            Self::Lz4 => "lz4",
+            Self::Zst => "zst",
+            Self::Z => "z",
+            Self::Lzma => "lzma",
+            Self::Lzip => "lz",
+            Self::Br => "br",
+            Self::Sz => "sz",
+            Self::SevenZ => "7z",
+            Self::Cab => "cab",
+            Self::Rar => "rar",
         }
     }
 
+    /// Returns whether the format can be extracted front to back without
+    /// seeking.
+    ///
+    /// ZIP, 7z, CAB and RAR keep their index apart from the entries (or, for
+    /// RAR, are decoded from the whole buffer), so they need the complete
+    /// archive in memory. Everything else can be read as a stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ArchiveFormat;
+    ///
+    /// assert!(ArchiveFormat::TarGz.is_streamable());
+    /// assert!(ArchiveFormat::Zst.is_streamable());
+    /// assert!(!ArchiveFormat::Zip.is_streamable());
+    /// ```
+    pub fn is_streamable(&self) -> bool {
+        !matches!(self, Self::Zip | Self::SevenZ | Self::Cab | Self::Rar)
+    }
+
     /// Determines the archive format from a MIME type string.
     ///
     /// Performs case-insensitive matching against known archive MIME types.
@@ -261,7 +449,14 @@ impl ArchiveFormat {
             "application/x-xz" => Ok(Self::Xz),
             "application/x-lz4" => Ok(Self::Lz4),
             "application/zstd" | "application/x-zstd" => Ok(Self::Zst),
+            "application/x-compress" => Ok(Self::Z),
+            "application/x-lzma" => Ok(Self::Lzma),
+            "application/x-lzip" => Ok(Self::Lzip),
+            "application/x-brotli" => Ok(Self::Br),
+            "application/x-snappy-framed" => Ok(Self::Sz),
             "application/x-7z-compressed" => Ok(Self::SevenZ),
+            "application/vnd.ms-cab-compressed" => Ok(Self::Cab),
+            "application/vnd.rar" | "application/x-rar-compressed" => Ok(Self::Rar),
             other => Err(ArchiveError::UnsupportedFormat(other.to_string())),
         }
     }
@@ -349,6 +544,95 @@ impl ArchiveFormat {
     pub fn is_supported_mime(mime: &MimeType) -> bool {
         ArchiveFormat::try_from(mime).is_ok()
     }
+
+    /// Returns the MIME type of this format, if [`MimeType`] has one for it.
+    ///
+    /// Compressed TAR archives give the type of their outer compression, as
+    /// they are served. Legacy LZMA, Brotli and framed Snappy have no MIME
+    /// type of their own and give `None` rather than a related type whose
+    /// decoder could not read them.
+    ///
+    /// # Examples
+    /// ```
+    /// use archive::ArchiveFormat;
+    /// use mime_type::MimeType;
+    ///
+    /// assert_eq!(
+    ///     ArchiveFormat::TarGz.mime_type(),
+    ///     Some(MimeType::Archive(mime_type::Archive::Gz))
+    /// );
+    /// assert_eq!(ArchiveFormat::Br.mime_type(), None);
+    /// ```
+    pub fn mime_type(&self) -> Option<MimeType> {
+        let mime = match self {
+            ArchiveFormat::Zip => mime_type::Archive::Zip,
+            ArchiveFormat::Tar => mime_type::Archive::Tar,
+            ArchiveFormat::Ar => mime_type::Archive::Ar,
+            ArchiveFormat::Deb => mime_type::Archive::Deb,
+            ArchiveFormat::Gz | ArchiveFormat::TarGz => mime_type::Archive::Gz,
+            ArchiveFormat::Bz2 | ArchiveFormat::TarBz2 => mime_type::Archive::Bz2,
+            ArchiveFormat::Xz | ArchiveFormat::TarXz => mime_type::Archive::Xz,
+            ArchiveFormat::Lz4 | ArchiveFormat::TarLz4 => mime_type::Archive::Lz4,
+            ArchiveFormat::Zst | ArchiveFormat::TarZst => mime_type::Archive::Zst,
+            ArchiveFormat::SevenZ => mime_type::Archive::SevenZ,
+            ArchiveFormat::Cab => mime_type::Archive::Cab,
+            ArchiveFormat::Rar => mime_type::Archive::Rar,
+            ArchiveFormat::Z | ArchiveFormat::TarZ => mime_type::Archive::Z,
+            ArchiveFormat::Lzip | ArchiveFormat::TarLzip => mime_type::Archive::Lz,
+            ArchiveFormat::Lzma
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::Br
+            | ArchiveFormat::TarBr
+            | ArchiveFormat::Sz
+            | ArchiveFormat::TarSz => return None,
+        };
+        Some(MimeType::Archive(mime))
+    }
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = ArchiveError;
+
+    /// Parses an identifier returned by [`ArchiveFormat::as_str`], ignoring
+    /// case.
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        match id.to_ascii_lowercase().as_str() {
+            "zip" => Ok(Self::Zip),
+            "tar" => Ok(Self::Tar),
+            "ar" => Ok(Self::Ar),
+            "deb" => Ok(Self::Deb),
+            "tar.gz" => Ok(Self::TarGz),
+            "tar.bz2" => Ok(Self::TarBz2),
+            "tar.xz" => Ok(Self::TarXz),
+            "tar.zst" => Ok(Self::TarZst),
+            "tar.lz4" => Ok(Self::TarLz4),
+            "tar.z" => Ok(Self::TarZ),
+            "tar.lzma" => Ok(Self::TarLzma),
+            "tar.lz" => Ok(Self::TarLzip),
+            "tar.br" => Ok(Self::TarBr),
+            "tar.sz" => Ok(Self::TarSz),
+            "gz" => Ok(Self::Gz),
+            "bz2" => Ok(Self::Bz2),
+            "xz" => Ok(Self::Xz),
+            "lz4" => Ok(Self::Lz4),
+            "zst" => Ok(Self::Zst),
+            "z" => Ok(Self::Z),
+            "lzma" => Ok(Self::Lzma),
+            "lz" => Ok(Self::Lzip),
+            "br" => Ok(Self::Br),
+            "sz" => Ok(Self::Sz),
+            "7z" => Ok(Self::SevenZ),
+            "cab" => Ok(Self::Cab),
+            "rar" => Ok(Self::Rar),
+            _ => Err(ArchiveError::UnknownFormat),
+        }
+    }
 }
 
 impl TryFrom<&MimeType> for ArchiveFormat {
@@ -366,6 +650,10 @@ impl TryFrom<&MimeType> for ArchiveFormat {
             MimeType::Archive(mime_type::Archive::Lz4) => Ok(Self::Lz4),
             MimeType::Archive(mime_type::Archive::Zst) => Ok(Self::Zst),
             MimeType::Archive(mime_type::Archive::SevenZ) => Ok(Self::SevenZ),
+            MimeType::Archive(mime_type::Archive::Cab) => Ok(Self::Cab),
+            MimeType::Archive(mime_type::Archive::Rar) => Ok(Self::Rar),
+            MimeType::Archive(mime_type::Archive::Z) => Ok(Self::Z),
+            MimeType::Archive(mime_type::Archive::Lz) => Ok(Self::Lzip),
             _ => Err(ArchiveError::UnsupportedFormat(mime.to_string())),
         }
     }
@@ -379,31 +667,23 @@ impl TryFrom<MimeType> for ArchiveFormat {
     }
 }
 
-impl From<&ArchiveFormat> for MimeType {
-    fn from(format: &ArchiveFormat) -> Self {
-        match format {
-            ArchiveFormat::Zip => MimeType::Archive(mime_type::Archive::Zip),
-            ArchiveFormat::Tar => MimeType::Archive(mime_type::Archive::Tar),
-            ArchiveFormat::Ar => MimeType::Archive(mime_type::Archive::Ar),
-            ArchiveFormat::Deb => MimeType::Archive(mime_type::Archive::Deb),
-            ArchiveFormat::Gz => MimeType::Archive(mime_type::Archive::Gz),
-            ArchiveFormat::Bz2 => MimeType::Archive(mime_type::Archive::Bz2),
-            ArchiveFormat::Xz => MimeType::Archive(mime_type::Archive::Xz),
-            ArchiveFormat::Lz4 => MimeType::Archive(mime_type::Archive::Lz4),
-            ArchiveFormat::Zst => MimeType::Archive(mime_type::Archive::Zst),
-            ArchiveFormat::SevenZ => MimeType::Archive(mime_type::Archive::SevenZ),
-            ArchiveFormat::TarGz => MimeType::Archive(mime_type::Archive::Gz),
-            ArchiveFormat::TarBz2 => MimeType::Archive(mime_type::Archive::Bz2),
-            ArchiveFormat::TarXz => MimeType::Archive(mime_type::Archive::Xz),
-            ArchiveFormat::TarZst => MimeType::Archive(mime_type::Archive::Zst),
-            ArchiveFormat::TarLz4 => MimeType::Archive(mime_type::Archive::Lz4),
-        }
+impl TryFrom<&ArchiveFormat> for MimeType {
+    type Error = ArchiveError;
+
+    /// Fails for formats without a MIME type of their own; see
+    /// [`ArchiveFormat::mime_type`].
+    fn try_from(format: &ArchiveFormat) -> Result<Self, Self::Error> {
+        format
+            .mime_type()
+            .ok_or_else(|| ArchiveError::UnsupportedFormat(format!("no MIME type for {}", format)))
     }
 }
 
-impl From<ArchiveFormat> for MimeType {
-    fn from(format: ArchiveFormat) -> Self {
-        MimeType::from(&format)
+impl TryFrom<ArchiveFormat> for MimeType {
+    type Error = ArchiveError;
+
+    fn try_from(format: ArchiveFormat) -> Result<Self, Self::Error> {
+        MimeType::try_from(&format)
     }
 }
 
@@ -425,6 +705,10 @@ mod tests {
             ArchiveFormat::from_filename("a.ar").unwrap(),
             ArchiveFormat::Ar
         );
+        assert_eq!(
+            ArchiveFormat::from_filename("libfoo.a").unwrap(),
+            ArchiveFormat::Ar
+        );
         assert_eq!(
             ArchiveFormat::from_filename("a.deb").unwrap(),
             ArchiveFormat::Deb
@@ -485,6 +769,62 @@ mod tests {
             ArchiveFormat::from_filename("a.7z").unwrap(),
             ArchiveFormat::SevenZ
         );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.cab").unwrap(),
+            ArchiveFormat::Cab
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.rar").unwrap(),
+            ArchiveFormat::Rar
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.Z").unwrap(),
+            ArchiveFormat::Z
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.tar.Z").unwrap(),
+            ArchiveFormat::TarZ
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.taz").unwrap(),
+            ArchiveFormat::TarZ
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.lzma").unwrap(),
+            ArchiveFormat::Lzma
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.lz").unwrap(),
+            ArchiveFormat::Lzip
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.tar.lzma").unwrap(),
+            ArchiveFormat::TarLzma
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.tar.lz").unwrap(),
+            ArchiveFormat::TarLzip
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.tlz").unwrap(),
+            ArchiveFormat::TarLzip
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.br").unwrap(),
+            ArchiveFormat::Br
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.sz").unwrap(),
+            ArchiveFormat::Sz
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.tar.br").unwrap(),
+            ArchiveFormat::TarBr
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("a.tar.sz").unwrap(),
+            ArchiveFormat::TarSz
+        );
     }
 
     #[test]
@@ -530,6 +870,26 @@ mod tests {
             ArchiveFormat::from_filename("foo.tar.lz4").unwrap(),
             ArchiveFormat::TarLz4
         );
+        assert_eq!(
+            ArchiveFormat::from_filename("foo.tar.Z").unwrap(),
+            ArchiveFormat::TarZ
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("foo.tar.lzma").unwrap(),
+            ArchiveFormat::TarLzma
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("foo.tar.lz").unwrap(),
+            ArchiveFormat::TarLzip
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("foo.tar.br").unwrap(),
+            ArchiveFormat::TarBr
+        );
+        assert_eq!(
+            ArchiveFormat::from_filename("foo.tar.sz").unwrap(),
+            ArchiveFormat::TarSz
+        );
     }
 
     #[test]
@@ -539,6 +899,67 @@ mod tests {
         assert!(ArchiveFormat::from_filename("noextension").is_err());
     }
 
+    const ALL_FORMATS: [ArchiveFormat; 27] = [
+        ArchiveFormat::Zip,
+        ArchiveFormat::Tar,
+        ArchiveFormat::Ar,
+        ArchiveFormat::Deb,
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarBz2,
+        ArchiveFormat::TarXz,
+        ArchiveFormat::TarZst,
+        ArchiveFormat::TarLz4,
+        ArchiveFormat::TarZ,
+        ArchiveFormat::TarLzma,
+        ArchiveFormat::TarLzip,
+        ArchiveFormat::TarBr,
+        ArchiveFormat::TarSz,
+        ArchiveFormat::Gz,
+        ArchiveFormat::Bz2,
+        ArchiveFormat::Xz,
+        ArchiveFormat::Lz4,
+        ArchiveFormat::Zst,
+        ArchiveFormat::Z,
+        ArchiveFormat::Lzma,
+        ArchiveFormat::Lzip,
+        ArchiveFormat::Br,
+        ArchiveFormat::Sz,
+        ArchiveFormat::SevenZ,
+        ArchiveFormat::Cab,
+        ArchiveFormat::Rar,
+    ];
+
+    #[test]
+    fn test_as_str_round_trips() {
+        for format in ALL_FORMATS {
+            assert_eq!(format.as_str().parse::<ArchiveFormat>().unwrap(), format);
+            assert_eq!(format.to_string(), format.as_str());
+            assert_eq!(
+                format
+                    .as_str()
+                    .to_uppercase()
+                    .parse::<ArchiveFormat>()
+                    .unwrap(),
+                format
+            );
+            // The identifier doubles as the file extension.
+            assert_eq!(
+                ArchiveFormat::from_filename(&format!("a.{}", format)).unwrap(),
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_str_unknown() {
+        assert!(matches!(
+            "tgz".parse::<ArchiveFormat>(),
+            Err(ArchiveError::UnknownFormat)
+        ));
+        assert!("".parse::<ArchiveFormat>().is_err());
+        assert!("archive.zip".parse::<ArchiveFormat>().is_err());
+    }
+
     #[test]
     fn test_from_mime_str_all_supported() {
         assert_eq!(
@@ -581,6 +1002,34 @@ mod tests {
             ArchiveFormat::from_mime_str("application/x-7z-compressed").unwrap(),
             ArchiveFormat::SevenZ
         );
+        assert_eq!(
+            ArchiveFormat::from_mime_str("application/vnd.ms-cab-compressed").unwrap(),
+            ArchiveFormat::Cab
+        );
+        assert_eq!(
+            ArchiveFormat::from_mime_str("application/vnd.rar").unwrap(),
+            ArchiveFormat::Rar
+        );
+        assert_eq!(
+            ArchiveFormat::from_mime_str("application/x-compress").unwrap(),
+            ArchiveFormat::Z
+        );
+        assert_eq!(
+            ArchiveFormat::from_mime_str("application/x-lzma").unwrap(),
+            ArchiveFormat::Lzma
+        );
+        assert_eq!(
+            ArchiveFormat::from_mime_str("application/x-lzip").unwrap(),
+            ArchiveFormat::Lzip
+        );
+        assert_eq!(
+            ArchiveFormat::from_mime_str("application/x-brotli").unwrap(),
+            ArchiveFormat::Br
+        );
+        assert_eq!(
+            ArchiveFormat::from_mime_str("application/x-snappy-framed").unwrap(),
+            ArchiveFormat::Sz
+        );
     }
 
     #[test]
@@ -613,6 +1062,10 @@ mod tests {
             ArchiveFormat::from_mime_str("application/x-zstd").unwrap(),
             ArchiveFormat::Zst
         );
+        assert_eq!(
+            ArchiveFormat::from_mime_str("application/x-rar-compressed").unwrap(),
+            ArchiveFormat::Rar
+        );
     }
 
     #[test]
@@ -622,6 +1075,33 @@ mod tests {
         assert!(ArchiveFormat::from_mime_str("image/png").is_err());
     }
 
+    #[test]
+    fn test_mime_type() {
+        for format in ALL_FORMATS {
+            let Some(mime) = format.mime_type() else {
+                assert!(MimeType::try_from(format).is_err());
+                continue;
+            };
+            // The MIME type reads back as the format or its outer compression.
+            let back = ArchiveFormat::try_from(&mime).unwrap();
+            assert!(
+                back == format || format.as_str().starts_with("tar."),
+                "{:?}",
+                format
+            );
+        }
+        for format in [
+            ArchiveFormat::Lzma,
+            ArchiveFormat::TarLzma,
+            ArchiveFormat::Br,
+            ArchiveFormat::TarBr,
+            ArchiveFormat::Sz,
+            ArchiveFormat::TarSz,
+        ] {
+            assert_eq!(format.mime_type(), None);
+        }
+    }
+
     #[cfg(feature = "detect-infer")]
     #[test]
     fn test_from_bytes_zip() {
@@ -689,6 +1169,15 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "detect-infer")]
+    #[test]
+    fn test_from_bytes_z() {
+        let data = crate::lzw::compress(b"hello");
+        assert_eq!(data[..2], [0x1f, 0x9d]);
+
+        assert_eq!(ArchiveFormat::from_bytes(&data).unwrap(), ArchiveFormat::Z);
+    }
+
     #[cfg(feature = "detect-infer")]
     #[test]
     fn test_from_bytes_unknown() {
@@ -0,0 +1,208 @@
+//! Glob patterns for selecting archive entries by path.
+//!
+//! The syntax follows the usual shell and `.gitignore` conventions:
+//!
+//! - `?` matches any single character except `/`
+//! - `*` matches any run of characters except `/`
+//! - `**` as a whole path component matches any number of components, so
+//!   `**/*.json` matches `a.json` and `a/b/c.json`, and `src/**` matches
+//!   everything below `src`
+//! - `[abc]`, `[a-z]` and `[!a-z]` (or `[^a-z]`) match character classes
+//! - `\` matches the next character literally
+//!
+//! Patterns always match the whole path. An unclosed `[` is matched literally.
+
+/// A compiled glob pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Glob {
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    Star,
+    /// `**/`: nothing, or any sequence of whole components.
+    AnyDirs,
+    /// `**` at the end of the pattern or on its own: anything at all.
+    AnyPath,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    /// Compiles a pattern.
+    pub(crate) fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    let starts_component = i == 0 || chars[i - 1] == '/';
+                    let next = chars.get(i + 2);
+                    if starts_component && next == Some(&'/') {
+                        tokens.push(Token::AnyDirs);
+                        i += 3;
+                    } else if starts_component && next.is_none() {
+                        tokens.push(Token::AnyPath);
+                        i += 2;
+                    } else {
+                        // `**` inside a component is just `*`.
+                        tokens.push(Token::Star);
+                        i += 2;
+                    }
+                }
+                '*' => {
+                    tokens.push(Token::Star);
+                    i += 1;
+                }
+                '?' => {
+                    tokens.push(Token::AnyChar);
+                    i += 1;
+                }
+                '[' => match parse_class(&chars[i + 1..]) {
+                    Some((token, len)) => {
+                        tokens.push(token);
+                        i += len + 1;
+                    }
+                    None => {
+                        tokens.push(Token::Literal('['));
+                        i += 1;
+                    }
+                },
+                '\\' if i + 1 < chars.len() => {
+                    tokens.push(Token::Literal(chars[i + 1]));
+                    i += 2;
+                }
+                c => {
+                    tokens.push(Token::Literal(c));
+                    i += 1;
+                }
+            }
+        }
+
+        Self { tokens }
+    }
+
+    /// Returns whether the pattern matches the whole of `path`.
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        let text: Vec<char> = path.chars().collect();
+        // matched[i] is whether the tokens from the current one onwards
+        // match text[i..]. Tokens are processed from last to first.
+        let mut matched = vec![false; text.len() + 1];
+        matched[text.len()] = true;
+
+        for token in self.tokens.iter().rev() {
+            let mut next = vec![false; text.len() + 1];
+            for i in (0..=text.len()).rev() {
+                next[i] = match token {
+                    Token::Literal(c) => text.get(i) == Some(c) && matched[i + 1],
+                    Token::AnyChar => text.get(i).is_some_and(|&c| c != '/') && matched[i + 1],
+                    Token::Class { negated, ranges } => {
+                        text.get(i).is_some_and(|&c| {
+                            c != '/'
+                                && ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+                        }) && matched[i + 1]
+                    }
+                    // Either match nothing, or one more character and retry.
+                    Token::Star => {
+                        matched[i] || (text.get(i).is_some_and(|&c| c != '/') && next[i + 1])
+                    }
+                    Token::AnyPath => matched[i] || (i < text.len() && next[i + 1]),
+                    Token::AnyDirs => {
+                        matched[i] || (i..text.len()).any(|j| text[j] == '/' && matched[j + 1])
+                    }
+                };
+            }
+            matched = next;
+        }
+
+        matched[0]
+    }
+}
+
+/// Parses a character class after its opening `[`, returning the token and
+/// the number of characters consumed including the closing `]`.
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let mut i = 0;
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    // A `]` right after the opening bracket is a literal member.
+    let mut first = true;
+    loop {
+        let c = *chars.get(i)?;
+        if c == ']' && !first {
+            return Some((Token::Class { negated, ranges }, i + 1));
+        }
+        first = false;
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&end| end != ']') {
+            ranges.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).matches(path)
+    }
+
+    #[test]
+    fn test_literals_and_wildcards() {
+        assert!(matches("a.txt", "a.txt"));
+        assert!(!matches("a.txt", "b/a.txt"));
+        assert!(matches("*.txt", "a.txt"));
+        assert!(!matches("*.txt", "dir/a.txt"));
+        assert!(matches("dir/?.txt", "dir/a.txt"));
+        assert!(!matches("dir/?.txt", "dir/ab.txt"));
+        assert!(matches("a\\*", "a*"));
+        assert!(!matches("a\\*", "ab"));
+    }
+
+    #[test]
+    fn test_globstar() {
+        assert!(matches("**/*.json", "package.json"));
+        assert!(matches("**/*.json", "a/b/package.json"));
+        assert!(!matches("**/*.json", "a/b/package.jsonl"));
+        assert!(matches("src/**", "src/main.rs"));
+        assert!(matches("src/**", "src/a/b/c.rs"));
+        assert!(!matches("src/**", "src"));
+        assert!(!matches("src/**", "srcx/a.rs"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(matches("**", "any/path"));
+        // Not a whole component, so the same as `*`.
+        assert!(matches("a**", "abc"));
+        assert!(!matches("a**", "a/c"));
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(matches("[abc].txt", "b.txt"));
+        assert!(!matches("[abc].txt", "d.txt"));
+        assert!(matches("[a-c].txt", "c.txt"));
+        assert!(matches("[!a-c].txt", "d.txt"));
+        assert!(!matches("[^a-c].txt", "a.txt"));
+        assert!(matches("[]].txt", "].txt"));
+        assert!(matches("[a-].txt", "-.txt"));
+        // An unclosed class is a literal bracket.
+        assert!(matches("[a", "[a"));
+    }
+}
@@ -0,0 +1,199 @@
+//! Content hashing of extracted entries.
+//!
+//! [`ArchiveExtractor::with_hash`](crate::ArchiveExtractor::with_hash)
+//! computes digests of each entry's contents as they are decompressed, and
+//! [`ArchiveExtractor::hash_with_format`](crate::ArchiveExtractor::hash_with_format)
+//! returns only the digests, throwing the contents away.
+
+use crate::error::ArchiveError;
+use sha2::Digest as _;
+use std::fmt;
+use std::str::FromStr;
+
+/// A hash algorithm for digests of entry contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    /// SHA-256
+    Sha256,
+    /// SHA-1
+    Sha1,
+    /// BLAKE3, with the default 32-byte output
+    Blake3,
+    /// MD5
+    Md5,
+}
+
+impl HashAlgorithm {
+    /// Returns the algorithm's name in lowercase, as accepted by
+    /// [`FromStr`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::HashAlgorithm;
+    ///
+    /// assert_eq!(HashAlgorithm::Sha256.name(), "sha256");
+    /// assert_eq!("sha256".parse::<HashAlgorithm>().unwrap(), HashAlgorithm::Sha256);
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha1 => "sha1",
+            Self::Blake3 => "blake3",
+            Self::Md5 => "md5",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = ArchiveError;
+
+    /// Parses an algorithm name, ignoring case and a `-` as in `SHA-256`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Ok(Self::Sha256),
+            "sha1" => Ok(Self::Sha1),
+            "blake3" => Ok(Self::Blake3),
+            "md5" => Ok(Self::Md5),
+            _ => Err(ArchiveError::InvalidInput(format!(
+                "unknown hash algorithm: {}",
+                name
+            ))),
+        }
+    }
+}
+
+/// The digest of an entry's contents.
+///
+/// Displays as lowercase hex.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Digest {
+    /// The algorithm that produced the digest.
+    pub algorithm: HashAlgorithm,
+
+    /// The raw digest bytes. Serialized as lowercase hex under the name
+    /// `hex`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "hex", with = "crate::serde_impls::hex_bytes")
+    )]
+    pub bytes: Vec<u8>,
+}
+
+impl Digest {
+    /// Returns the digest as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.bytes
+            .iter()
+            .try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// An entry as returned by hash-only extraction: its path, size and
+/// digests, without its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashedEntry {
+    /// The path of the entry, as in [`ExtractedFile::path`](crate::ExtractedFile::path).
+    pub path: String,
+
+    /// The size of the contents in bytes.
+    pub size: u64,
+
+    /// One digest per configured algorithm, in the order they were added.
+    pub digests: Vec<Digest>,
+}
+
+impl HashedEntry {
+    /// Returns the digest computed with `algorithm`, if it was configured.
+    pub fn digest(&self, algorithm: HashAlgorithm) -> Option<&Digest> {
+        self.digests.iter().find(|d| d.algorithm == algorithm)
+    }
+}
+
+/// Digests in progress, one per algorithm, fed as contents are read.
+pub(crate) struct Hashers(Vec<Hasher>);
+
+enum Hasher {
+    Sha256(sha2::Sha256),
+    Sha1(sha1::Sha1),
+    Blake3(Box<blake3::Hasher>),
+    Md5(md5::Md5),
+}
+
+impl Hashers {
+    pub(crate) fn new(algorithms: &[HashAlgorithm]) -> Self {
+        Self(
+            algorithms
+                .iter()
+                .map(|algorithm| match algorithm {
+                    HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+                    HashAlgorithm::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+                    HashAlgorithm::Blake3 => Hasher::Blake3(Box::default()),
+                    HashAlgorithm::Md5 => Hasher::Md5(md5::Md5::new()),
+                })
+                .collect(),
+        )
+    }
+
+    /// Digests `data` in one go.
+    pub(crate) fn digest(algorithms: &[HashAlgorithm], data: &[u8]) -> Vec<Digest> {
+        let mut hashers = Self::new(algorithms);
+        hashers.update(data);
+        hashers.finish()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for hasher in &mut self.0 {
+            match hasher {
+                Hasher::Sha256(hasher) => hasher.update(data),
+                Hasher::Sha1(hasher) => hasher.update(data),
+                Hasher::Blake3(hasher) => {
+                    hasher.update(data);
+                }
+                Hasher::Md5(hasher) => hasher.update(data),
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<Digest> {
+        self.0
+            .into_iter()
+            .map(|hasher| match hasher {
+                Hasher::Sha256(hasher) => Digest {
+                    algorithm: HashAlgorithm::Sha256,
+                    bytes: hasher.finalize().to_vec(),
+                },
+                Hasher::Sha1(hasher) => Digest {
+                    algorithm: HashAlgorithm::Sha1,
+                    bytes: hasher.finalize().to_vec(),
+                },
+                Hasher::Blake3(hasher) => Digest {
+                    algorithm: HashAlgorithm::Blake3,
+                    bytes: hasher.finalize().as_bytes().to_vec(),
+                },
+                Hasher::Md5(hasher) => Digest {
+                    algorithm: HashAlgorithm::Md5,
+                    bytes: hasher.finalize().to_vec(),
+                },
+            })
+            .collect()
+    }
+}
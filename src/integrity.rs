@@ -0,0 +1,81 @@
+//! Integrity reports from
+//! [`ArchiveExtractor::test`](crate::ArchiveExtractor::test).
+//!
+//! Testing an archive decodes every entry and checks it against the
+//! checksums the archive stores, without keeping the contents, and reports
+//! the outcome entry by entry instead of stopping at the first failure.
+
+use crate::error::ArchiveError;
+
+/// The outcome of testing an archive.
+///
+/// # Examples
+///
+/// ```no_run
+/// use archive::{ArchiveExtractor, ArchiveFormat};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let data = vec![0u8; 100];
+/// let report = ArchiveExtractor::new().test_with_format(&data, ArchiveFormat::Zip)?;
+/// for check in report.failures() {
+///     eprintln!("{}: {}", check.path, check.error.as_ref().unwrap());
+/// }
+/// if let Some(err) = &report.error {
+///     eprintln!("archive: {}", err);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IntegrityReport {
+    /// Every entry that was checked, in archive order.
+    pub entries: Vec<EntryCheck>,
+
+    /// An error that stopped the test without belonging to any one entry,
+    /// such as a corrupt index or a size limit. Entries after it were not
+    /// checked. Serialized as the error message.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_impls::error_message")
+    )]
+    pub error: Option<ArchiveError>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if every entry passed and the test ran to the end.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none() && self.entries.iter().all(EntryCheck::is_ok)
+    }
+
+    /// Returns the entries that failed their check.
+    pub fn failures(&self) -> impl Iterator<Item = &EntryCheck> {
+        self.entries.iter().filter(|check| !check.is_ok())
+    }
+}
+
+/// The outcome of testing a single entry.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EntryCheck {
+    /// The entry's path within the archive.
+    pub path: String,
+
+    /// Bytes of contents decoded, up to the failure if there was one.
+    pub size: u64,
+
+    /// Why the entry failed, or `None` if it passed. Serialized as the error
+    /// message.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_impls::error_message")
+    )]
+    pub error: Option<ArchiveError>,
+}
+
+impl EntryCheck {
+    /// Returns `true` if the entry passed.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
@@ -1,13 +1,39 @@
 //! A unified interface for extracting common archive formats in-memory.
 //!
 //! This crate provides a simple, safe API for extracting various archive formats
-//! including ZIP, TAR (with multiple compression options), 7-Zip, and single-file
-//! compression formats. All extraction happens in-memory without touching the disk.
+//! including ZIP, TAR (with multiple compression options), 7-Zip, CAB, RAR, and
+//! single-file compression formats. All extraction happens in-memory without
+//! touching the disk.
 //!
 //! # Features
 //!
 //! - **Unified API**: Single interface for all archive formats
 //! - **In-memory extraction**: No disk I/O required
+//! - **Archive creation**: Write every supported format with [`ArchiveWriter`],
+//!   RAR with the `rar-write` feature
+//! - **Format conversion**: Repack archives with [`ArchiveConverter`], reporting
+//!   anything the target format cannot store
+//! - **Progress and cancellation**: Report progress and stop long extractions
+//!   with a [`CancellationToken`]
+//! - **Async extraction**: Stream entries from a tokio `AsyncRead` with the
+//!   `async` feature
+//! - **Parallel ZIP extraction**: Inflate entries concurrently with the
+//!   `parallel` feature
+//! - **Content hashing**: SHA-256, SHA-1, BLAKE3 and MD5 digests computed
+//!   while entries are decompressed, or on their own
+//! - **Integrity testing**: Check every entry against its stored checksums
+//!   without keeping the contents, like `unzip -t`
+//! - **Strict ZIP validation**: Detect local headers that disagree with the
+//!   central directory, extra end records and data around the archive
+//! - **Duplicate paths**: Choose which of several entries with the same path
+//!   is kept, optionally ignoring case and Unicode normalization
+//! - **Static libraries**: Map the symbols of `.a` files to the object files
+//!   defining them
+//! - **Legacy file names**: Decode names from older tools as CP437, Shift-JIS
+//!   or GBK, with the stored bytes kept on every entry
+//! - **Command-line tool**: An `archive` binary with the `cli` feature
+//! - **Serialization**: Serde support for formats, listings and reports with
+//!   the `serde` feature
 //! - **Safety limits**: Protection against zip bombs and resource exhaustion
 //! - **Pure Rust**: Minimal C dependencies (only bzip2)
 //! - **Cross-platform**: Works on Linux, macOS, Windows (x86_64, ARM64)
@@ -15,9 +41,13 @@
 //! # Supported Formats
 //!
 //! - **ZIP** (`.zip`)
-//! - **TAR** (`.tar`, `.tar.gz`, `.tar.bz2`, `.tar.xz`, `.tar.zst`, `.tar.lz4`)
+//! - **TAR** (`.tar`, `.tar.gz`, `.tar.bz2`, `.tar.xz`, `.tar.zst`, `.tar.lz4`, `.tar.Z`,
+//!   `.tar.lzma`, `.tar.lz`, `.tar.br`, `.tar.sz`)
 //! - **7-Zip** (`.7z`)
-//! - **Single-file compression** (`.gz`, `.bz2`, `.xz`, `.lz4`, `.zst`)
+//! - **Cabinet** (`.cab`)
+//! - **RAR** (`.rar`, RAR 1.5–4.x and RAR 5.0+)
+//! - **Single-file compression** (`.gz`, `.bz2`, `.xz`, `.lz4`, `.zst`, `.Z`, `.lzma`, `.lz`,
+//!   `.br`, `.sz`)
 //!
 //! # Examples
 //!
@@ -92,6 +122,49 @@
 //! # }
 //! ```
 //!
+//! ## Creating Archives
+//!
+//! [`ArchiveWriter`] writes the same [`ExtractedFile`] entries back out, into
+//! any supported format:
+//!
+//! ```
+//! use archive::{ArchiveFormat, ArchiveWriter, CompressionLevel, ExtractedFile};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let entries = vec![
+//!     ExtractedFile::directory("docs/"),
+//!     ExtractedFile::file("docs/readme.txt", b"Hello".to_vec()),
+//! ];
+//!
+//! let data = ArchiveWriter::new()
+//!     .with_format(ArchiveFormat::TarZst)
+//!     .with_compression_level(CompressionLevel::Best)
+//!     .write(&entries)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Converting Archives
+//!
+//! [`convert()`] repacks an archive into another format. Metadata is kept where
+//! both formats support it, and anything dropped is listed in the result:
+//!
+//! ```no_run
+//! use archive::ArchiveFormat;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let zip_data = vec![0u8; 100];
+//! let conversion = archive::convert(&zip_data, ArchiveFormat::Zip, ArchiveFormat::TarZst)?;
+//! for loss in &conversion.losses {
+//!     eprintln!("lost: {loss:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Use [`ArchiveConverter`] to set the extractor's size limits or the
+//! writer's compression level.
+//!
 //! # Safety
 //!
 //! This crate includes built-in protections against:
@@ -123,10 +196,40 @@
 //! # }
 //! ```
 
+#[cfg(feature = "async")]
+pub mod async_extractor;
+pub mod convert;
+pub mod duplicates;
+pub mod encoding;
 pub mod error;
 pub mod extractor;
 pub mod format;
+mod glob;
+pub mod hash;
+pub mod integrity;
+mod library;
+mod lzip;
+mod lzw;
+pub mod progress;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod timestamp;
+pub mod validation;
+pub mod writer;
+mod xz;
 
+#[cfg(feature = "async")]
+pub use async_extractor::AsyncEntries;
+pub use convert::{ArchiveConverter, Conversion, ConversionLoss, MetadataField, convert};
+pub use duplicates::DuplicatePolicy;
+pub use encoding::NameEncoding;
 pub use error::{ArchiveError, Result};
-pub use extractor::{ArchiveExtractor, ExtractedFile};
+pub use extractor::{
+    ArchiveExtractor, BorrowedFile, EntryHeader, EntryMetadata, ExtractedFile, GzipNamePrecedence,
+};
 pub use format::ArchiveFormat;
+pub use hash::{Digest, HashAlgorithm, HashedEntry};
+pub use integrity::{EntryCheck, IntegrityReport};
+pub use progress::{CancellationToken, Progress};
+pub use validation::ZipAnomaly;
+pub use writer::{ArchiveWriter, CompressionLevel};
@@ -0,0 +1,178 @@
+//! Symbol tables of static libraries.
+//!
+//! A static library (`.a`) is an ar archive of object files whose first
+//! member indexes the symbols they define. GNU ar names that member `/` and
+//! keeps long member names in a `//` member; BSD ar names it `__.SYMDEF` or
+//! `__.SYMDEF SORTED` and stores long names in front of each member's
+//! contents, as `#1/<length>`. Both tables point at member headers by offset,
+//! which [`symbols`] maps back to member names.
+
+use crate::encoding::{self, NameEncoding};
+use crate::error::{ArchiveError, Result};
+use std::collections::{BTreeMap, HashMap};
+
+const MAGIC: &[u8] = b"!<arch>\n";
+const HEADER_LEN: usize = 60;
+
+const GNU_SYMBOL_TABLE: &[u8] = b"/";
+const GNU_NAME_TABLE: &[u8] = b"//";
+const BSD_SYMBOL_TABLE: &[u8] = b"__.SYMDEF";
+const BSD_SORTED_SYMBOL_TABLE: &[u8] = b"__.SYMDEF SORTED";
+
+/// Returns `true` for the members that index an archive rather than hold a
+/// file: the GNU symbol and long-name tables and the BSD symbol table.
+pub(crate) fn is_special_member(name: &[u8]) -> bool {
+    [
+        GNU_SYMBOL_TABLE,
+        GNU_NAME_TABLE,
+        BSD_SYMBOL_TABLE,
+        BSD_SORTED_SYMBOL_TABLE,
+    ]
+    .contains(&name)
+}
+
+/// Maps every symbol in an ar archive's symbol table to the name of the
+/// member defining it. A symbol defined by several members maps to the
+/// first, as a linker would pick. Archives without a symbol table give an
+/// empty map.
+pub(crate) fn symbols(
+    data: &[u8],
+    fallback: Option<NameEncoding>,
+) -> Result<BTreeMap<String, String>> {
+    if !data.starts_with(MAGIC) {
+        return Err(invalid("missing ar magic"));
+    }
+    let mut pos = MAGIC.len();
+
+    let mut long_names: &[u8] = &[];
+    let mut table = None;
+    let mut members = HashMap::new();
+    while pos < data.len() {
+        let header = data
+            .get(pos..pos + HEADER_LEN)
+            .ok_or_else(|| invalid("truncated member header"))?;
+        let size = number(&header[48..58]).ok_or_else(|| invalid("invalid member size"))?;
+        let mut contents = data
+            .get(pos + HEADER_LEN..)
+            .and_then(|rest| rest.get(..size))
+            .ok_or_else(|| invalid("truncated member"))?;
+
+        let field = trim_end(&header[..16], b' ');
+        let name = if field == GNU_SYMBOL_TABLE || field == GNU_NAME_TABLE {
+            field
+        } else if let Some(len) = field.strip_prefix(b"#1/") {
+            // BSD: the name is the start of the contents, padded with NULs.
+            let len = number(len)
+                .filter(|&len| len <= contents.len())
+                .ok_or_else(|| invalid("invalid BSD member name length"))?;
+            let (name, rest) = contents.split_at(len);
+            contents = rest;
+            trim_end(name, 0)
+        } else if let Some(start) = field.strip_prefix(b"/") {
+            // GNU: an offset into the long-name table, where names end in "/".
+            let name = number(start)
+                .and_then(|start| long_names.get(start..))
+                .ok_or_else(|| invalid("invalid GNU long name offset"))?;
+            let end = name
+                .iter()
+                .position(|&byte| byte == b'/' || byte == 0)
+                .unwrap_or(name.len());
+            &name[..end]
+        } else {
+            field.strip_suffix(b"/").unwrap_or(field)
+        };
+
+        if name == GNU_NAME_TABLE {
+            long_names = contents;
+        } else if is_special_member(name) {
+            table.get_or_insert((name == GNU_SYMBOL_TABLE, contents));
+        } else {
+            members.insert(pos, name);
+        }
+        // Members start on even offsets.
+        pos += HEADER_LEN + size + size % 2;
+    }
+
+    let Some((is_gnu, contents)) = table else {
+        return Ok(BTreeMap::new());
+    };
+    let entries = if is_gnu {
+        gnu_symbols(contents)
+    } else {
+        bsd_symbols(contents)
+    }
+    .ok_or_else(|| invalid("malformed symbol table"))?;
+
+    let mut symbols = BTreeMap::new();
+    for (symbol, offset) in entries {
+        // Stale tables can point at members that are gone; skip those.
+        if let Some(member) = members.get(&offset) {
+            symbols
+                .entry(encoding::decode_name(symbol, None))
+                .or_insert_with(|| encoding::decode_name(member, fallback));
+        }
+    }
+    Ok(symbols)
+}
+
+/// Reads a GNU symbol table: a big-endian count, as many big-endian member
+/// offsets, then the symbol names, each ending in NUL.
+fn gnu_symbols(table: &[u8]) -> Option<Vec<(&[u8], usize)>> {
+    let count = be_u32(table.get(..4)?)?;
+    let offsets = table.get(4..)?.get(..count.checked_mul(4)?)?;
+    let mut names = table[4 + offsets.len()..].split(|&byte| byte == 0);
+    offsets
+        .chunks_exact(4)
+        .map(|offset| Some((names.next()?, be_u32(offset)?)))
+        .collect()
+}
+
+/// Reads a BSD symbol table: the little-endian byte length of an array of
+/// (name offset, member offset) pairs, the array, then the byte length of
+/// the NUL-terminated names and the names.
+fn bsd_symbols(table: &[u8]) -> Option<Vec<(&[u8], usize)>> {
+    let pairs_len = le_u32(table.get(..4)?)?;
+    let pairs = table.get(4..)?.get(..pairs_len)?;
+    let rest = &table[4 + pairs_len..];
+    let names_len = le_u32(rest.get(..4)?)?;
+    let names = rest.get(4..)?.get(..names_len)?;
+    pairs
+        .chunks_exact(8)
+        .map(|pair| {
+            let name = names.get(le_u32(&pair[..4])?..)?;
+            let end = name
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(name.len());
+            Some((&name[..end], le_u32(&pair[4..])?))
+        })
+        .collect()
+}
+
+fn be_u32(bytes: &[u8]) -> Option<usize> {
+    usize::try_from(u32::from_be_bytes(bytes.try_into().ok()?)).ok()
+}
+
+fn le_u32(bytes: &[u8]) -> Option<usize> {
+    usize::try_from(u32::from_le_bytes(bytes.try_into().ok()?)).ok()
+}
+
+/// Parses a decimal header field, padded with spaces.
+fn number(field: &[u8]) -> Option<usize> {
+    std::str::from_utf8(trim_end(field, b' '))
+        .ok()?
+        .parse()
+        .ok()
+}
+
+fn trim_end(bytes: &[u8], padding: u8) -> &[u8] {
+    let end = bytes
+        .iter()
+        .rposition(|&byte| byte != padding)
+        .map_or(0, |pos| pos + 1);
+    &bytes[..end]
+}
+
+fn invalid(reason: &str) -> ArchiveError {
+    ArchiveError::InvalidArchive(format!("AR error: {}", reason))
+}
@@ -0,0 +1,241 @@
+//! Decoder and encoder for the lzip format (`.lz`).
+//!
+//! An lzip file is a sequence of one or more members. Each member consists of
+//! a 6-byte header (`LZIP`, a version byte and a coded dictionary size), a raw
+//! LZMA stream using the fixed properties `lc=3, lp=0, pb=2` and terminated by
+//! an end-of-stream marker, and a 20-byte trailer holding the CRC-32 of the
+//! uncompressed data, the uncompressed size and the size of the whole member.
+//!
+//! Members are located by walking the trailers backwards from the end of the
+//! file, the same way `lziprecover` does, so the compressed stream of each
+//! member is known exactly before it is decoded.
+
+use crate::error::{ArchiveError, Result};
+use sevenz_rust::lzma::{CountingWriter, LZMA2Options, LZMAWriter};
+use std::io::{Read, Write};
+
+/// Magic bytes at the start of every lzip member.
+const MAGIC: &[u8; 4] = b"LZIP";
+const HEADER_SIZE: usize = 6;
+const TRAILER_SIZE: usize = 20;
+/// LZMA properties byte for `lc=3, lp=0, pb=2`, the only ones lzip uses.
+const LZMA_PROPERTIES: u8 = 0x5D;
+/// Smallest dictionary size lzip allows, as a power of two.
+const MIN_DICT_EXPONENT: u32 = 12;
+
+/// Decompresses all members of an lzip file, verifying each trailer.
+pub(crate) fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    if !data.starts_with(MAGIC) {
+        return Err(invalid("not lzip data"));
+    }
+
+    let mut output = Vec::new();
+    for member in split_members(data)? {
+        decompress_member(member, &mut output)?;
+    }
+    Ok(output)
+}
+
+/// Compresses `data` into a single lzip member using an LZMA preset from 0
+/// to 9.
+pub(crate) fn compress(data: &[u8], preset: u32) -> Result<Vec<u8>> {
+    let mut options = LZMA2Options::with_preset(preset);
+    // Round the dictionary up to a power of two, which codes exactly.
+    let exponent = options
+        .dict_size
+        .next_power_of_two()
+        .trailing_zeros()
+        .max(MIN_DICT_EXPONENT);
+    options.dict_size = 1 << exponent;
+
+    let mut output = MAGIC.to_vec();
+    output.extend_from_slice(&[1, exponent as u8]);
+    let mut writer = LZMAWriter::new_no_header(CountingWriter::new(&mut output), &options, true)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    drop(writer);
+
+    let mut crc = flate2::Crc::new();
+    crc.update(data);
+    output.extend_from_slice(&crc.sum().to_le_bytes());
+    output.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    let member_size = (output.len() + 8) as u64;
+    output.extend_from_slice(&member_size.to_le_bytes());
+    Ok(output)
+}
+
+/// Splits the file into members using the member size stored in each trailer.
+fn split_members(data: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut members = Vec::new();
+    let mut end = data.len();
+    while end > 0 {
+        if end < HEADER_SIZE + TRAILER_SIZE {
+            return Err(invalid("truncated member"));
+        }
+        let member_size = read_u64(&data[end - 8..end]);
+        if member_size < (HEADER_SIZE + TRAILER_SIZE) as u64 || member_size > end as u64 {
+            return Err(invalid("corrupt member size"));
+        }
+        let start = end - member_size as usize;
+        if !data[start..].starts_with(MAGIC) {
+            return Err(invalid("corrupt member header"));
+        }
+        members.push(&data[start..end]);
+        end = start;
+    }
+    members.reverse();
+    Ok(members)
+}
+
+fn decompress_member(member: &[u8], output: &mut Vec<u8>) -> Result<()> {
+    let version = member[4];
+    if version != 1 {
+        return Err(ArchiveError::UnsupportedFormat(format!(
+            "lzip version {version}"
+        )));
+    }
+    let dict_size = dictionary_size(member[5])?;
+    let (stream, trailer) =
+        member[HEADER_SIZE..].split_at(member.len() - HEADER_SIZE - TRAILER_SIZE);
+
+    // Present the raw stream to the decoder as a legacy `.lzma` file with an
+    // unknown uncompressed size, so it decodes up to the end-of-stream marker.
+    let mut header = [0xFF; 13];
+    header[0] = LZMA_PROPERTIES;
+    header[1..5].copy_from_slice(&dict_size.to_le_bytes());
+
+    let start = output.len();
+    lzma_rs::lzma_decompress(&mut (&header[..]).chain(stream), output)
+        .map_err(|e| ArchiveError::InvalidArchive(format!("lzip error: {e}")))?;
+    let decoded = &output[start..];
+
+    let expected_crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let expected_size = read_u64(&trailer[4..12]);
+    if decoded.len() as u64 != expected_size {
+        return Err(invalid("data size mismatch"));
+    }
+    let mut crc = flate2::Crc::new();
+    crc.update(decoded);
+    if crc.sum() != expected_crc {
+        return Err(invalid("CRC mismatch"));
+    }
+    Ok(())
+}
+
+/// Decodes the header's dictionary size: a power of two between 4 KiB and
+/// 512 MiB, minus up to seven sixteenths of itself.
+fn dictionary_size(coded: u8) -> Result<u32> {
+    let exponent = u32::from(coded & 0x1F);
+    if !(12..=29).contains(&exponent) {
+        return Err(invalid("invalid dictionary size"));
+    }
+    let base = 1u32 << exponent;
+    Ok(base - (base / 16) * u32::from(coded >> 5))
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+fn invalid(msg: &str) -> ArchiveError {
+    ArchiveError::InvalidArchive(format!("lzip error: {msg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an lzip member around the raw stream produced by `lzma-rs`.
+    fn member(data: &[u8]) -> Vec<u8> {
+        let mut lzma = Vec::new();
+        lzma_rs::lzma_compress(&mut &data[..], &mut lzma).unwrap();
+        let stream = &lzma[13..];
+
+        let mut crc = flate2::Crc::new();
+        crc.update(data);
+
+        // 0x17 codes an 8 MiB dictionary, matching the `lzma-rs` encoder.
+        let mut out = vec![b'L', b'Z', b'I', b'P', 1, 0x17];
+        out.extend_from_slice(stream);
+        out.extend_from_slice(&crc.sum().to_le_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        let member_size = (out.len() + 8) as u64;
+        out.extend_from_slice(&member_size.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_decompress_single_member() {
+        let data = member(b"Hello, World!\n");
+        assert_eq!(decompress(&data).unwrap(), b"Hello, World!\n");
+    }
+
+    #[test]
+    fn test_decompress_multiple_members() {
+        let mut data = member(b"first\n");
+        data.extend(member(b"second\n"));
+        assert_eq!(decompress(&data).unwrap(), b"first\nsecond\n");
+    }
+
+    #[test]
+    fn test_crc_mismatch() {
+        let mut data = member(b"Hello, World!\n");
+        let crc_offset = data.len() - TRAILER_SIZE;
+        data[crc_offset] ^= 0xFF;
+        assert!(matches!(
+            decompress(&data),
+            Err(ArchiveError::InvalidArchive(msg)) if msg.contains("CRC")
+        ));
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let mut data = member(b"Hello, World!\n");
+        let size_offset = data.len() - TRAILER_SIZE + 4;
+        data[size_offset] += 1;
+        assert!(matches!(
+            decompress(&data),
+            Err(ArchiveError::InvalidArchive(msg)) if msg.contains("size")
+        ));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = member(b"Hello, World!\n");
+        assert!(decompress(&data[..data.len() - 1]).is_err());
+        assert!(decompress(b"LZIP").is_err());
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut data = member(b"Hello, World!\n");
+        data[4] = 0;
+        assert!(matches!(
+            decompress(&data),
+            Err(ArchiveError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let data = b"Hello, lzip! ".repeat(1000);
+        for preset in [0, 6] {
+            let compressed = compress(&data, preset).unwrap();
+            assert!(compressed.len() < data.len());
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+        assert_eq!(decompress(&compress(b"", 6).unwrap()).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_dictionary_size() {
+        assert_eq!(dictionary_size(0x0C).unwrap(), 1 << 12);
+        assert_eq!(dictionary_size(0x17).unwrap(), 1 << 23);
+        // 2^13 - 2^13/16 * 1 = 7680
+        assert_eq!(dictionary_size(0x2D).unwrap(), 7680);
+        assert!(dictionary_size(0x0B).is_err());
+        assert!(dictionary_size(0x1E).is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//! Decoder and encoder for the LZW format written by Unix `compress` (`.Z`).
+//!
+//! The stream starts with the magic bytes `1F 9D` and a flags byte holding
+//! the maximum code width (9 to 16 bits) and the block-mode flag. Codes are
+//! packed least-significant bit first, starting at 9 bits wide. In block mode
+//! code 256 clears the dictionary and resets the width to 9 bits.
+//!
+//! `compress` reads and writes codes in groups of eight, so a group always
+//! spans exactly `n_bits` bytes. Whenever the code width changes (or the
+//! dictionary is cleared) the rest of the current group is padding and must
+//! be skipped; this decoder mirrors that behaviour of the reference
+//! implementation.
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+/// Magic bytes at the start of every `.Z` stream.
+pub(crate) const MAGIC: [u8; 2] = [0x1F, 0x9D];
+
+const INIT_BITS: u32 = 9;
+const MAX_BITS: u32 = 16;
+const BLOCK_MODE: u8 = 0x80;
+const BIT_MASK: u8 = 0x1F;
+const CLEAR: u32 = 256;
+
+/// A streaming decoder for `compress` data, wrapping any reader.
+pub(crate) struct LzwDecoder<R> {
+    inner: R,
+    block_mode: bool,
+    max_bits: u32,
+    n_bits: u32,
+    max_code: u32,
+    max_max_code: u32,
+    free_ent: u32,
+    old_code: Option<u32>,
+    fin_char: u8,
+    prefix: Vec<u16>,
+    suffix: Vec<u8>,
+    /// Buffered input bits, least significant first.
+    bit_buf: u32,
+    bit_count: u32,
+    /// Bits consumed since the current code width took effect; used to find
+    /// the end of the current group of codes.
+    group_bits: u64,
+    eof: bool,
+    /// Decoded bytes of the current string, stored in reverse.
+    stack: Vec<u8>,
+}
+
+impl<R: Read> LzwDecoder<R> {
+    /// Reads and validates the stream header.
+    pub(crate) fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = [0u8; 3];
+        inner
+            .read_exact(&mut header)
+            .map_err(|_| invalid("truncated header"))?;
+        if header[..2] != MAGIC {
+            return Err(invalid("not compress data"));
+        }
+
+        let max_bits = u32::from(header[2] & BIT_MASK);
+        if !(INIT_BITS..=MAX_BITS).contains(&max_bits) {
+            return Err(invalid("unsupported maximum code width"));
+        }
+        let block_mode = header[2] & BLOCK_MODE != 0;
+        let max_max_code = 1u32 << max_bits;
+
+        let mut decoder = Self {
+            inner,
+            block_mode,
+            max_bits,
+            n_bits: INIT_BITS,
+            max_code: 0,
+            max_max_code,
+            free_ent: if block_mode { CLEAR + 1 } else { CLEAR },
+            old_code: None,
+            fin_char: 0,
+            prefix: vec![0; max_max_code as usize],
+            suffix: (0..max_max_code).map(|c| c as u8).collect(),
+            bit_buf: 0,
+            bit_count: 0,
+            group_bits: 0,
+            eof: false,
+            stack: Vec::new(),
+        };
+        decoder.reset_width();
+        Ok(decoder)
+    }
+
+    /// Returns to 9-bit codes. Like `compress`, this ignores the maximum
+    /// width, so a `-b 9` stream still widens to 10 bits once the table fills.
+    fn reset_width(&mut self) {
+        self.n_bits = INIT_BITS;
+        self.max_code = (1 << INIT_BITS) - 1;
+    }
+
+    fn widen(&mut self) {
+        self.n_bits += 1;
+        self.max_code = if self.n_bits == self.max_bits {
+            self.max_max_code
+        } else {
+            (1 << self.n_bits) - 1
+        };
+    }
+
+    /// Ensures at least `n` bits are buffered. Returns `false` at end of input.
+    fn fill(&mut self, n: u32) -> io::Result<bool> {
+        while self.bit_count < n {
+            if self.eof {
+                return Ok(false);
+            }
+            let mut byte = [0u8; 1];
+            match self.inner.read(&mut byte) {
+                Ok(0) => self.eof = true,
+                Ok(_) => {
+                    self.bit_buf |= u32::from(byte[0]) << self.bit_count;
+                    self.bit_count += 8;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Skips the padding up to the end of the current group of codes.
+    fn skip_to_group_end(&mut self) -> io::Result<()> {
+        let group = u64::from(self.n_bits) * 8;
+        let mut skip = (group - self.group_bits % group) % group;
+        while skip > 0 {
+            let n = skip.min(8) as u32;
+            if !self.fill(n)? {
+                self.bit_buf = 0;
+                self.bit_count = 0;
+                break;
+            }
+            self.bit_buf >>= n;
+            self.bit_count -= n;
+            skip -= u64::from(n);
+        }
+        self.group_bits = 0;
+        Ok(())
+    }
+
+    /// Reads the next code, or `None` once fewer than `n_bits` bits remain.
+    fn next_code(&mut self) -> io::Result<Option<u32>> {
+        if self.free_ent > self.max_code {
+            self.skip_to_group_end()?;
+            self.widen();
+        }
+        if !self.fill(self.n_bits)? {
+            return Ok(None);
+        }
+        let code = self.bit_buf & ((1 << self.n_bits) - 1);
+        self.bit_buf >>= self.n_bits;
+        self.bit_count -= self.n_bits;
+        self.group_bits += u64::from(self.n_bits);
+        Ok(Some(code))
+    }
+
+    /// Decodes the next string onto the output stack. Returns `false` at the
+    /// end of the stream.
+    fn decode_next(&mut self) -> io::Result<bool> {
+        loop {
+            let Some(code) = self.next_code()? else {
+                return Ok(false);
+            };
+
+            let Some(old_code) = self.old_code else {
+                if code >= CLEAR {
+                    return Err(invalid("first code is not a literal"));
+                }
+                self.old_code = Some(code);
+                self.fin_char = code as u8;
+                self.stack.push(self.fin_char);
+                return Ok(true);
+            };
+
+            if code == CLEAR && self.block_mode {
+                // The next code re-creates entry 256, which is never
+                // referenced, so the first real new entry is 257 again.
+                self.free_ent = CLEAR;
+                self.skip_to_group_end()?;
+                self.reset_width();
+                continue;
+            }
+
+            let in_code = code;
+            let mut code = code;
+            if code >= self.free_ent {
+                // The KwKwK case: the code being defined right now. Once the
+                // table is full, codes keep widening but define nothing.
+                if code > self.free_ent || code >= self.max_max_code {
+                    return Err(invalid("corrupt input"));
+                }
+                self.stack.push(self.fin_char);
+                code = old_code;
+            }
+            while code >= CLEAR {
+                self.stack.push(self.suffix[code as usize]);
+                code = u32::from(self.prefix[code as usize]);
+            }
+            self.fin_char = self.suffix[code as usize];
+            self.stack.push(self.fin_char);
+
+            if self.free_ent < self.max_max_code {
+                self.prefix[self.free_ent as usize] = old_code as u16;
+                self.suffix[self.free_ent as usize] = self.fin_char;
+                self.free_ent += 1;
+            }
+            self.old_code = Some(in_code);
+            return Ok(true);
+        }
+    }
+}
+
+impl<R: Read> Read for LzwDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.stack.is_empty() && !self.decode_next()? {
+            return Ok(0);
+        }
+        let mut n = 0;
+        while n < buf.len() {
+            match self.stack.pop() {
+                Some(byte) => {
+                    buf[n] = byte;
+                    n += 1;
+                }
+                None => break,
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// Compresses `data` into a `.Z` stream with 16-bit codes in block mode.
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+    encode(data, MAX_BITS, true)
+}
+
+/// Encodes `data` the way `compress` does, with `max_bits` wide codes.
+///
+/// When `clear_when_full` is set the dictionary is cleared as soon as it
+/// fills up; otherwise the full dictionary is kept for the rest of the data.
+fn encode(data: &[u8], max_bits: u32, clear_when_full: bool) -> Vec<u8> {
+    struct Writer {
+        out: Vec<u8>,
+        acc: u32,
+        n_acc: u32,
+        n_bits: u32,
+        max_code: u32,
+        group_bits: u32,
+    }
+    impl Writer {
+        fn put(&mut self, value: u32, n: u32) {
+            self.acc |= value << self.n_acc;
+            self.n_acc += n;
+            while self.n_acc >= 8 {
+                self.out.push(self.acc as u8);
+                self.acc >>= 8;
+                self.n_acc -= 8;
+            }
+        }
+        fn emit(&mut self, code: u32, free_ent: u32, clear: bool, max_bits: u32) {
+            self.put(code, self.n_bits);
+            self.group_bits = (self.group_bits + self.n_bits) % (self.n_bits * 8);
+            if free_ent > self.max_code || clear {
+                if self.group_bits > 0 {
+                    let pad = self.n_bits * 8 - self.group_bits;
+                    for _ in 0..pad / 8 {
+                        self.put(0, 8);
+                    }
+                    self.put(0, pad % 8);
+                }
+                self.group_bits = 0;
+                if clear {
+                    self.n_bits = INIT_BITS;
+                    self.max_code = (1 << INIT_BITS) - 1;
+                } else {
+                    self.n_bits += 1;
+                    self.max_code = if self.n_bits == max_bits {
+                        1 << max_bits
+                    } else {
+                        (1 << self.n_bits) - 1
+                    };
+                }
+            }
+        }
+    }
+
+    let mut w = Writer {
+        out: vec![MAGIC[0], MAGIC[1], max_bits as u8 | BLOCK_MODE],
+        acc: 0,
+        n_acc: 0,
+        n_bits: INIT_BITS,
+        max_code: (1 << INIT_BITS) - 1,
+        group_bits: 0,
+    };
+    let Some((&first, rest)) = data.split_first() else {
+        return w.out;
+    };
+    let max_max_code = 1u32 << max_bits;
+    let mut table: HashMap<(u32, u8), u32> = HashMap::new();
+    let mut free_ent = CLEAR + 1;
+    let mut ent = u32::from(first);
+    for &c in rest {
+        if let Some(&code) = table.get(&(ent, c)) {
+            ent = code;
+            continue;
+        }
+        w.emit(ent, free_ent, false, max_bits);
+        if free_ent < max_max_code {
+            table.insert((ent, c), free_ent);
+            free_ent += 1;
+        } else if clear_when_full {
+            table.clear();
+            free_ent = CLEAR + 1;
+            w.emit(CLEAR, free_ent, true, max_bits);
+        }
+        ent = u32::from(c);
+    }
+    w.emit(ent, free_ent, false, max_bits);
+    if w.n_acc > 0 {
+        w.out.push(w.acc as u8);
+    }
+    w.out
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("compress: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        LzwDecoder::new(data)?.read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    /// Deterministic, poorly compressible test data followed by a repetitive
+    /// tail, so both dictionary growth and reuse are exercised.
+    fn sample_data() -> Vec<u8> {
+        let mut state = 0x1234_5678u32;
+        let mut data: Vec<u8> = (0..60_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        data.extend(b"abcabcabd".repeat(5_000));
+        data
+    }
+
+    /// `hello hello hello hello\n` as written by `compress -b 16`, verified
+    /// against gzip's independent decoder.
+    const HELLO_Z: [u8; 20] = [
+        0x1f, 0x9d, 0x90, 0x68, 0xca, 0xb0, 0x61, 0xf3, 0x06, 0x44, 0xc0, 0x81, 0x05, 0x0f, 0x12,
+        0x34, 0x28, 0x90, 0xa0, 0x02,
+    ];
+
+    #[test]
+    fn test_decode_reference_output() {
+        assert_eq!(decode(&HELLO_Z).unwrap(), b"hello hello hello hello\n");
+    }
+
+    #[test]
+    fn test_encoder_matches_reference_output() {
+        assert_eq!(encode(b"hello hello hello hello\n", 16, false), HELLO_Z);
+    }
+
+    #[test]
+    fn test_round_trip_all_code_widths() {
+        let data = sample_data();
+        for max_bits in INIT_BITS..=MAX_BITS {
+            let encoded = encode(&data, max_bits, false);
+            assert_eq!(decode(&encoded).unwrap(), data, "max_bits={max_bits}");
+        }
+    }
+
+    #[test]
+    fn test_round_trip_with_clear_codes() {
+        let data = sample_data();
+        for max_bits in [9, 12, 16] {
+            let encoded = encode(&data, max_bits, true);
+            assert_eq!(decode(&encoded).unwrap(), data, "max_bits={max_bits}");
+        }
+    }
+
+    #[test]
+    fn test_decode_empty_stream() {
+        assert_eq!(decode(&[0x1f, 0x9d, 0x90]).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_rejects_bad_magic() {
+        assert!(decode(&[0x1f, 0x8b, 0x08, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_max_bits() {
+        assert!(decode(&[0x1f, 0x9d, 0x91]).is_err());
+        assert!(decode(&[0x1f, 0x9d, 0x88]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_codes_beyond_full_table() {
+        // `-b 9` without block mode: 257 literals fill the table, after which
+        // `compress` widens to 10-bit codes, and 512 names no entry.
+        let mut bits: Vec<bool> = Vec::new();
+        let put = |code: u32, width: u32, bits: &mut Vec<bool>| {
+            bits.extend((0..width).map(|i| code >> i & 1 != 0));
+        };
+        for _ in 0..257 {
+            put(u32::from(b'a'), 9, &mut bits);
+        }
+        // Codes come in groups of eight, padded when the width changes.
+        bits.resize(bits.len().div_ceil(72) * 72, false);
+        put(512, 10, &mut bits);
+        put(512, 10, &mut bits);
+
+        let mut data = vec![0x1f, 0x9d, 0x09];
+        data.extend(bits.chunks(8).map(|byte| {
+            byte.iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, &bit)| acc | u8::from(bit) << i)
+        }));
+        assert!(decode(&data).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_code() {
+        // The first code must be a literal; 0x1FF is not.
+        assert!(decode(&[0x1f, 0x9d, 0x90, 0xff, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! Progress reporting and cancellation for long-running extractions.
+//!
+//! [`ArchiveExtractor::with_progress`](crate::ArchiveExtractor::with_progress)
+//! registers a callback that receives a [`Progress`] snapshot as entries are
+//! read, and
+//! [`ArchiveExtractor::with_cancellation`](crate::ArchiveExtractor::with_cancellation)
+//! registers a [`CancellationToken`] that aborts the extraction with
+//! [`ArchiveError::Cancelled`](crate::ArchiveError::Cancelled).
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A snapshot of how far an extraction has got.
+///
+/// # Examples
+///
+/// ```
+/// use archive::{ArchiveExtractor, Progress};
+///
+/// let extractor = ArchiveExtractor::new().with_progress(|progress: &Progress<'_>| {
+///     eprintln!(
+///         "{} entries, {} bytes read, {} bytes extracted ({})",
+///         progress.entries_done,
+///         progress.bytes_in,
+///         progress.bytes_out,
+///         progress.current_path.unwrap_or("-"),
+///     );
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress<'a> {
+    /// Number of entries fully extracted so far.
+    pub entries_done: usize,
+
+    /// Bytes of archive data consumed so far. For formats that seek, such as
+    /// ZIP and 7z, this is the furthest offset read.
+    pub bytes_in: u64,
+
+    /// Bytes of entry contents produced so far.
+    pub bytes_out: u64,
+
+    /// Path of the entry being extracted, or of the entry just finished.
+    pub current_path: Option<&'a str>,
+}
+
+/// A handle for cancelling an extraction from another thread.
+///
+/// Clones share the same state, so one clone can be handed to the extractor
+/// and another kept to call [`cancel`](Self::cancel). The token is checked
+/// between entries and while each entry's contents are read.
+///
+/// # Examples
+///
+/// ```
+/// use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat, CancellationToken};
+///
+/// let token = CancellationToken::new();
+/// let extractor = ArchiveExtractor::new().with_cancellation(token.clone());
+///
+/// token.cancel();
+/// # let data = vec![0u8; 100];
+/// let result = extractor.extract_with_format(&data, ArchiveFormat::Tar);
+/// assert!(matches!(result, Err(ArchiveError::Cancelled)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of every extraction using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
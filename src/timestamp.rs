@@ -0,0 +1,90 @@
+//! Conversions between Unix timestamps and the calendar timestamps stored by
+//! ZIP and CAB archives.
+//!
+//! Both formats store MS-DOS style wall-clock fields without a time zone,
+//! covering the years 1980 to 2107 at a two-second resolution. They are
+//! treated as UTC in both directions, so a round trip through either format
+//! preserves a timestamp up to that resolution.
+
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+/// 1980-01-01 00:00:00, the earliest MS-DOS timestamp.
+const DOS_MIN: u64 = 315_532_800;
+/// 2107-12-31 23:59:58, the latest MS-DOS timestamp.
+const DOS_MAX: u64 = 4_354_819_198;
+
+/// Converts a ZIP timestamp to seconds since the Unix epoch.
+pub(crate) fn zip_to_unix(datetime: zip::DateTime) -> Option<u64> {
+    let date = Date::from_calendar_date(
+        i32::from(datetime.year()),
+        Month::try_from(datetime.month()).ok()?,
+        datetime.day(),
+    )
+    .ok()?;
+    let time = Time::from_hms(datetime.hour(), datetime.minute(), datetime.second()).ok()?;
+    primitive_to_unix(PrimitiveDateTime::new(date, time))
+}
+
+/// Converts seconds since the Unix epoch to a ZIP timestamp, clamping to the
+/// range the format can represent.
+pub(crate) fn unix_to_zip(secs: u64) -> zip::DateTime {
+    let datetime = unix_to_primitive(secs.clamp(DOS_MIN, DOS_MAX));
+    zip::DateTime::from_date_and_time(
+        datetime.year() as u16,
+        datetime.month() as u8,
+        datetime.day(),
+        datetime.hour(),
+        datetime.minute(),
+        datetime.second(),
+    )
+    .unwrap_or_default()
+}
+
+/// Returns the time a ZIP or CAB entry written with `secs` reads back as.
+pub(crate) fn dos_round_trip(secs: u64) -> u64 {
+    let secs = secs.clamp(DOS_MIN, DOS_MAX);
+    secs - secs % 2
+}
+
+/// Converts a calendar timestamp, read as UTC, to seconds since the Unix
+/// epoch. Timestamps before the epoch have no representation and map to
+/// `None`.
+pub(crate) fn primitive_to_unix(datetime: PrimitiveDateTime) -> Option<u64> {
+    u64::try_from(datetime.assume_utc().unix_timestamp()).ok()
+}
+
+/// Converts seconds since the Unix epoch to a UTC calendar timestamp,
+/// saturating at the largest date the `time` crate supports.
+pub(crate) fn unix_to_primitive(secs: u64) -> PrimitiveDateTime {
+    let datetime = i64::try_from(secs)
+        .ok()
+        .and_then(|secs| OffsetDateTime::from_unix_timestamp(secs).ok())
+        .unwrap_or(PrimitiveDateTime::MAX.assume_utc());
+    PrimitiveDateTime::new(datetime.date(), datetime.time())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zip_round_trip() {
+        // 2024-02-29 12:34:56 UTC
+        let secs = 1_709_210_096;
+        assert_eq!(zip_to_unix(unix_to_zip(secs)), Some(secs));
+    }
+
+    #[test]
+    fn test_zip_clamps_to_dos_range() {
+        assert_eq!(unix_to_zip(0).year(), 1980);
+        assert_eq!(unix_to_zip(u64::MAX).year(), 2107);
+    }
+
+    #[test]
+    fn test_dos_round_trip() {
+        for secs in [0, 1_709_210_097, u64::MAX] {
+            let stored = dos_round_trip(secs);
+            assert_eq!(zip_to_unix(unix_to_zip(secs)), Some(stored));
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! Structural validation of ZIP archives.
+//!
+//! A ZIP archive describes every entry twice, in a local header in front of
+//! its data and again in the central directory at the end, and is found by
+//! searching for the end-of-central-directory record. Tools that read the
+//! local headers front to back and tools that read the central directory can
+//! therefore see different archives, which is how crafted ZIPs slip past
+//! scanners. [`ArchiveExtractor::validate_zip`](crate::ArchiveExtractor::validate_zip)
+//! lists everything that makes an archive ambiguous in this way, and
+//! [`ArchiveExtractor::with_strict_zip`](crate::ArchiveExtractor::with_strict_zip)
+//! refuses to extract such archives.
+
+use crate::error::Result;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+use std::ops::Range;
+
+const LOCAL_HEADER_SIGNATURE: &[u8] = b"PK\x03\x04";
+const CENTRAL_HEADER_SIGNATURE: &[u8] = b"PK\x01\x02";
+const END_RECORD_SIGNATURE: &[u8] = b"PK\x05\x06";
+const END_RECORD_LEN: usize = 22;
+
+/// Set in the general purpose flags when the CRC and sizes follow the data
+/// in a data descriptor and are zero in the local header.
+const DATA_DESCRIPTOR_FLAG: u16 = 1 << 3;
+
+/// Set in the general purpose flags when the name and comment are UTF-8.
+const UTF8_FLAG: u16 = 1 << 11;
+
+/// Something that makes a ZIP archive read differently depending on the
+/// tool.
+///
+/// Entries are named by their path in the central directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum ZipAnomaly {
+    /// The local header gives the entry a different name.
+    NameMismatch {
+        /// Path of the entry in the central directory.
+        path: String,
+        /// Name in the local header.
+        local: String,
+    },
+
+    /// The local header gives a different compression method.
+    MethodMismatch {
+        /// Path of the entry.
+        path: String,
+        /// Method in the local header.
+        local: u16,
+        /// Method in the central directory.
+        central: u16,
+    },
+
+    /// The local header gives a different CRC-32.
+    CrcMismatch {
+        /// Path of the entry.
+        path: String,
+        /// CRC-32 in the local header.
+        local: u32,
+        /// CRC-32 in the central directory.
+        central: u32,
+    },
+
+    /// The local header gives a different compressed size.
+    CompressedSizeMismatch {
+        /// Path of the entry.
+        path: String,
+        /// Compressed size in the local header.
+        local: u64,
+        /// Compressed size in the central directory.
+        central: u64,
+    },
+
+    /// The local header gives a different uncompressed size.
+    SizeMismatch {
+        /// Path of the entry.
+        path: String,
+        /// Uncompressed size in the local header.
+        local: u64,
+        /// Uncompressed size in the central directory.
+        central: u64,
+    },
+
+    /// More than one central directory record has the same name, so
+    /// different readers may pick different entries for it.
+    DuplicateName {
+        /// The repeated name.
+        path: String,
+        /// Number of records with the name.
+        count: usize,
+    },
+
+    /// More than one end-of-central-directory record was found outside the
+    /// entries' data, so different readers may pick different ones.
+    MultipleEndRecords {
+        /// Number of records found.
+        count: usize,
+    },
+
+    /// Data precedes the first entry, as in self-extracting archives or
+    /// files disguised as another format.
+    PrefixData {
+        /// Size of the data in bytes.
+        size: u64,
+    },
+
+    /// Data follows the end-of-central-directory record.
+    TrailingData {
+        /// Size of the data in bytes.
+        size: u64,
+    },
+}
+
+impl fmt::Display for ZipAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NameMismatch { path, local } => {
+                write!(f, "{}: local header names it {}", path, local)
+            }
+            Self::MethodMismatch {
+                path,
+                local,
+                central,
+            } => write!(
+                f,
+                "{}: local header method {} differs from central {}",
+                path, local, central
+            ),
+            Self::CrcMismatch {
+                path,
+                local,
+                central,
+            } => write!(
+                f,
+                "{}: local header CRC {:#010x} differs from central {:#010x}",
+                path, local, central
+            ),
+            Self::CompressedSizeMismatch {
+                path,
+                local,
+                central,
+            } => write!(
+                f,
+                "{}: local header compressed size {} differs from central {}",
+                path, local, central
+            ),
+            Self::SizeMismatch {
+                path,
+                local,
+                central,
+            } => write!(
+                f,
+                "{}: local header size {} differs from central {}",
+                path, local, central
+            ),
+            Self::DuplicateName { path, count } => {
+                write!(f, "{}: {} central directory records", path, count)
+            }
+            Self::MultipleEndRecords { count } => {
+                write!(f, "{} end of central directory records", count)
+            }
+            Self::PrefixData { size } => write!(f, "{} bytes before the first entry", size),
+            Self::TrailingData { size } => write!(f, "{} bytes after the end of the archive", size),
+        }
+    }
+}
+
+/// Joins anomalies into a single error message.
+pub(crate) fn describe(anomalies: &[ZipAnomaly]) -> String {
+    anomalies
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Cross-checks every local header against the central directory and looks
+/// for extra end records and data around the archive.
+pub(crate) fn zip_anomalies(data: &[u8]) -> Result<Vec<ZipAnomaly>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+    let mut anomalies = Vec::new();
+    // Regions that belong to entries or the central directory, where an end
+    // record signature is just data.
+    let mut regions: Vec<Range<usize>> = Vec::new();
+    let mut first_header = data.len();
+
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        let path = file.name().to_string();
+        let local_start = to_usize(file.header_start());
+        let central_start = to_usize(file.central_header_start());
+        let data_end = to_usize(file.data_start().saturating_add(file.compressed_size()));
+        let (Some(local), Some(central)) = (
+            header(data, local_start, LOCAL_HEADER_SIGNATURE, 30),
+            header(data, central_start, CENTRAL_HEADER_SIGNATURE, 46),
+        ) else {
+            continue;
+        };
+        first_header = first_header.min(local_start).min(central_start);
+        regions.push(local_start..data_end);
+        regions.push(
+            central_start
+                ..central_start
+                    + 46
+                    + usize::from(u16_at(central, 28))
+                    + usize::from(u16_at(central, 30))
+                    + usize::from(u16_at(central, 32)),
+        );
+
+        let local_name_end = 30 + usize::from(u16_at(local, 26));
+        let local_name = data
+            .get(local_start + 30..local_start + local_name_end)
+            .unwrap_or_default();
+        if local_name != file.name_raw() {
+            anomalies.push(ZipAnomaly::NameMismatch {
+                path: path.clone(),
+                local: String::from_utf8_lossy(local_name).into_owned(),
+            });
+        }
+
+        let (local_method, central_method) = (u16_at(local, 8), u16_at(central, 10));
+        if local_method != central_method {
+            anomalies.push(ZipAnomaly::MethodMismatch {
+                path: path.clone(),
+                local: local_method,
+                central: central_method,
+            });
+        }
+
+        // With a data descriptor the local header leaves the CRC and sizes
+        // zero. Sizes of ZIP64 entries live in an extra field instead.
+        if u16_at(local, 6) & DATA_DESCRIPTOR_FLAG != 0 {
+            continue;
+        }
+        let local_crc = u32_at(local, 14);
+        if local_crc != file.crc32() {
+            anomalies.push(ZipAnomaly::CrcMismatch {
+                path: path.clone(),
+                local: local_crc,
+                central: file.crc32(),
+            });
+        }
+        let local_compressed = u32_at(local, 18);
+        if local_compressed != u32::MAX && u64::from(local_compressed) != file.compressed_size() {
+            anomalies.push(ZipAnomaly::CompressedSizeMismatch {
+                path: path.clone(),
+                local: local_compressed.into(),
+                central: file.compressed_size(),
+            });
+        }
+        let local_size = u32_at(local, 22);
+        if local_size != u32::MAX && u64::from(local_size) != file.size() {
+            anomalies.push(ZipAnomaly::SizeMismatch {
+                path,
+                local: local_size.into(),
+                central: file.size(),
+            });
+        }
+    }
+
+    // The reader keeps one entry per name, so look at every record for
+    // repeated names and for where the first entry starts. Record offsets
+    // don't count data prepended to the archive.
+    let start = archive.central_directory_start();
+    let offset = to_usize(archive.offset());
+    anomalies.extend(
+        repeated_names(data, start)
+            .into_iter()
+            .map(|(path, count)| ZipAnomaly::DuplicateName { path, count }),
+    );
+    first_header = central_records(data, start)
+        .into_iter()
+        .map(|(_, local_start)| local_start.saturating_add(offset))
+        .fold(first_header, usize::min);
+
+    let end_records: Vec<(usize, usize)> = data
+        .windows(END_RECORD_SIGNATURE.len())
+        .enumerate()
+        .filter(|&(pos, window)| {
+            window == END_RECORD_SIGNATURE && !regions.iter().any(|region| region.contains(&pos))
+        })
+        .filter_map(|(pos, _)| {
+            let comment_len = usize::from(u16_at(data.get(pos..pos + END_RECORD_LEN)?, 20));
+            let end = pos + END_RECORD_LEN + comment_len;
+            (end <= data.len()).then_some((pos, end))
+        })
+        .collect();
+    if end_records.len() > 1 {
+        anomalies.push(ZipAnomaly::MultipleEndRecords {
+            count: end_records.len(),
+        });
+    }
+
+    // Readers search for the end record from the back, so the last one is
+    // the one that defines the archive.
+    if let Some(&(pos, end)) = end_records.last() {
+        let start = first_header.min(pos);
+        if start > 0 {
+            anomalies.push(ZipAnomaly::PrefixData { size: start as u64 });
+        }
+        if end < data.len() {
+            anomalies.push(ZipAnomaly::TrailingData {
+                size: (data.len() - end) as u64,
+            });
+        }
+    }
+
+    Ok(anomalies)
+}
+
+/// Walks the central directory starting at `start` and returns the names
+/// that more than one record has, with the number of records, in order of
+/// first appearance.
+pub(crate) fn repeated_names(data: &[u8], start: u64) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&[u8], usize> = HashMap::new();
+    // Names in order of first appearance.
+    let mut names = Vec::new();
+    for (name, _) in central_records(data, start) {
+        let count = counts.entry(name).or_insert(0);
+        if *count == 0 {
+            names.push(name);
+        }
+        *count += 1;
+    }
+    names
+        .into_iter()
+        .map(|name| (name, counts[name]))
+        .filter(|&(_, count)| count > 1)
+        .map(|(name, count)| (String::from_utf8_lossy(name).into_owned(), count))
+        .collect()
+}
+
+/// Returns the stored name of the central directory record at `pos`, and
+/// whether its flags mark the name as UTF-8.
+pub(crate) fn central_name(data: &[u8], pos: u64) -> Option<(&[u8], bool)> {
+    let pos = to_usize(pos);
+    let central = header(data, pos, CENTRAL_HEADER_SIGNATURE, 46)?;
+    let name = data.get(pos + 46..pos + 46 + usize::from(u16_at(central, 28)))?;
+    Some((name, u16_at(central, 8) & UTF8_FLAG != 0))
+}
+
+/// Walks the central directory starting at `start` and returns the raw name
+/// and local header offset of every record.
+fn central_records(data: &[u8], start: u64) -> Vec<(&[u8], usize)> {
+    let mut pos = to_usize(start);
+    let mut records = Vec::new();
+    while let Some(central) = header(data, pos, CENTRAL_HEADER_SIGNATURE, 46) {
+        let name_len = usize::from(u16_at(central, 28));
+        let Some(name) = data.get(pos + 46..pos + 46 + name_len) else {
+            break;
+        };
+        records.push((name, u32_at(central, 42) as usize));
+        pos += 46 + name_len + usize::from(u16_at(central, 30)) + usize::from(u16_at(central, 32));
+    }
+    records
+}
+
+/// Returns the fixed part of the header at `pos` if it has the expected
+/// signature.
+fn header<'a>(data: &'a [u8], pos: usize, signature: &[u8], len: usize) -> Option<&'a [u8]> {
+    data.get(pos..pos.checked_add(len)?)
+        .filter(|header| header.starts_with(signature))
+}
+
+fn to_usize(offset: u64) -> usize {
+    usize::try_from(offset).unwrap_or(usize::MAX)
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap())
+}
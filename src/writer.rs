@@ -0,0 +1,901 @@
+//! Archive creation implementations.
+//!
+//! This module is the counterpart to [`extractor`](crate::extractor). The main
+//! entry point is [`ArchiveWriter`], which writes a list of [`ExtractedFile`]
+//! entries into any supported format in memory.
+
+use crate::error::{ArchiveError, Result};
+use crate::extractor::{EntryMetadata, ExtractedFile, SEVENZ_UNIX_EXTENSION};
+use crate::format::ArchiveFormat;
+use crate::{lzip, lzw, timestamp, xz};
+use sevenz_rust::lzma::{CountingWriter, LZMA2Options, LZMAWriter};
+use std::io::{self, Cursor, Seek, Write};
+use std::ops::RangeInclusive;
+
+/// Permissions for regular files without a recorded mode.
+const DEFAULT_FILE_MODE: u32 = 0o644;
+/// Permissions for directories without a recorded mode.
+const DEFAULT_DIR_MODE: u32 = 0o755;
+/// Permissions for symbolic links without a recorded mode.
+const DEFAULT_LINK_MODE: u32 = 0o777;
+
+/// Unix file type bits for a regular file.
+const S_IFREG: u32 = 0o100000;
+/// Unix file type bits for a directory.
+const S_IFDIR: u32 = 0o040000;
+/// Unix file type bits for a symbolic link.
+const S_IFLNK: u32 = 0o120000;
+
+/// The compression level used when creating archives.
+///
+/// Formats use very different native scales, so the named levels are mapped
+/// onto each one: [`Fastest`](Self::Fastest) and [`Best`](Self::Best) pick the
+/// ends of the scale, and [`Default`](Self::Default) matches the format's
+/// reference tool (`gzip -6`, `xz -6`, `zstd -3`, ...). [`Level`](Self::Level)
+/// passes a native level through, clamped to the format's range.
+///
+/// TAR, AR, compress (`.Z`) and Snappy have no levels and ignore this setting,
+/// and CAB only distinguishes between [`Store`](Self::Store) and MSZIP.
+///
+/// # Examples
+///
+/// ```
+/// use archive::{ArchiveWriter, CompressionLevel};
+///
+/// let writer = ArchiveWriter::new().with_compression_level(CompressionLevel::Best);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompressionLevel {
+    /// Stores entries uncompressed where the format allows it, and uses the
+    /// fastest setting otherwise.
+    Store,
+
+    /// Favors speed over compressed size.
+    Fastest,
+
+    /// The format's usual trade-off between speed and size.
+    #[default]
+    Default,
+
+    /// Favors compressed size over speed.
+    Best,
+
+    /// A level on the format's native scale, such as `0..=9` for gzip or
+    /// `1..=22` for Zstandard.
+    Level(u32),
+}
+
+impl CompressionLevel {
+    /// Maps the level onto a native scale.
+    fn native(self, fastest: u32, default: u32, best: u32, range: RangeInclusive<u32>) -> u32 {
+        match self {
+            Self::Store => *range.start(),
+            Self::Fastest => fastest,
+            Self::Default => default,
+            Self::Best => best,
+            Self::Level(level) => level.clamp(*range.start(), *range.end()),
+        }
+    }
+
+    fn deflate(self) -> u32 {
+        self.native(1, 6, 9, 0..=9)
+    }
+
+    fn lzma_preset(self) -> u32 {
+        self.native(0, 6, 9, 0..=9)
+    }
+}
+
+/// Creates archives in any supported format.
+///
+/// The writer takes the same [`ExtractedFile`] entries that
+/// [`ArchiveExtractor`](crate::ArchiveExtractor) produces, so an extracted
+/// archive can be written back out, in its own or another format. Metadata
+/// from [`EntryMetadata`] is stored wherever the format
+/// has room for it; missing TAR and AR fields default to the Unix epoch,
+/// root ownership and `0644` (`0755` for directories) permissions, while
+/// other formats use their own defaults.
+///
+/// # Format Notes
+///
+/// - RAR archives are written as RAR 5 and need the `rar-write` feature.
+/// - Directories are written to ZIP, TAR, 7-Zip and RAR; AR, Debian packages
+///   and CAB have no directory entries and skip them.
+/// - Symbolic links are written to ZIP, TAR, 7-Zip and RAR, and skipped by
+///   the other formats.
+/// - Debian packages are written as plain AR archives, so the entries must
+///   already be `debian-binary`, `control.tar.*` and `data.tar.*`, in order.
+/// - Single-file compression formats take exactly one regular file. Gzip
+///   records its file name, without directories, and modification time in
+///   the header.
+///
+/// # Reproducible Output
+///
+/// With [`with_deterministic`](Self::with_deterministic), the same set of
+/// entries always produces byte-identical output, regardless of entry order,
+/// ownership or the time of writing.
+///
+/// # Examples
+///
+/// ```
+/// use archive::{ArchiveExtractor, ArchiveFormat, ArchiveWriter, ExtractedFile};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let entries = vec![
+///     ExtractedFile::directory("docs/"),
+///     ExtractedFile::file("docs/readme.txt", b"Hello".to_vec()),
+/// ];
+/// let data = ArchiveWriter::new().write_with_format(&entries, ArchiveFormat::TarGz)?;
+///
+/// let files = ArchiveExtractor::new().extract_with_format(&data, ArchiveFormat::TarGz)?;
+/// assert_eq!(files[1].data, b"Hello");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveWriter {
+    format: Option<ArchiveFormat>,
+    compression_level: CompressionLevel,
+    source_date_epoch: Option<u64>,
+}
+
+impl ArchiveWriter {
+    /// Creates a new archive writer with the default compression level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::ArchiveWriter;
+    ///
+    /// let writer = ArchiveWriter::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the archive format explicitly.
+    ///
+    /// When set, the [`write`](Self::write) method will use this format
+    /// instead of requiring it as a parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveFormat, ArchiveWriter};
+    ///
+    /// let writer = ArchiveWriter::new().with_format(ArchiveFormat::Zip);
+    /// ```
+    pub fn with_format(mut self, format: ArchiveFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Sets the compression level.
+    ///
+    /// See [`CompressionLevel`] for how levels map onto each format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveWriter, CompressionLevel};
+    ///
+    /// // zstd -19
+    /// let writer = ArchiveWriter::new().with_compression_level(CompressionLevel::Level(19));
+    /// ```
+    pub fn with_compression_level(mut self, level: CompressionLevel) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    /// Enables deterministic output for reproducible builds.
+    ///
+    /// `source_date_epoch` is usually taken from the `SOURCE_DATE_EPOCH`
+    /// environment variable. In deterministic mode the writer:
+    ///
+    /// - sorts entries by path, except in Debian packages, whose member order
+    ///   is fixed
+    /// - clamps modification times to `source_date_epoch`, and uses it for
+    ///   entries without one
+    /// - sets uid and gid to 0 and drops owner and group names
+    /// - normalizes permissions to `0644` for files (`0755` if any execute
+    ///   bit is set), `0755` for directories and `0777` for symlinks
+    /// - omits the file name and timestamp from gzip headers
+    /// - writes no ZIP extra fields beyond those required for large files
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveFormat, ArchiveWriter};
+    ///
+    /// let epoch = std::env::var("SOURCE_DATE_EPOCH")
+    ///     .ok()
+    ///     .and_then(|value| value.parse().ok())
+    ///     .unwrap_or(0);
+    /// let writer = ArchiveWriter::new()
+    ///     .with_format(ArchiveFormat::TarGz)
+    ///     .with_deterministic(epoch);
+    /// ```
+    pub fn with_deterministic(mut self, source_date_epoch: u64) -> Self {
+        self.source_date_epoch = Some(source_date_epoch);
+        self
+    }
+
+    /// Writes entries into an archive using the builder-configured format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveError::UnknownFormat`] if no format has been configured.
+    /// See [`write_with_format`](Self::write_with_format) for other possible errors.
+    pub fn write(&self, entries: &[ExtractedFile]) -> Result<Vec<u8>> {
+        let format = self.format.ok_or(ArchiveError::UnknownFormat)?;
+        self.write_with_format(entries, format)
+    }
+
+    /// Writes entries into an archive of the given format.
+    ///
+    /// Entries are written in the order given.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - A single-file format is given anything but one regular file, or an
+    ///   entry cannot be represented in the format ([`ArchiveError::InvalidInput`])
+    /// - An encoder reports an error ([`ArchiveError::Io`])
+    /// - A ZIP-specific error occurs ([`ArchiveError::Zip`])
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use archive::{ArchiveFormat, ArchiveWriter, ExtractedFile};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let entries = vec![ExtractedFile::file("hello.txt", b"Hello".to_vec())];
+    /// let data = ArchiveWriter::new().write_with_format(&entries, ArchiveFormat::Zip)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_with_format(
+        &self,
+        entries: &[ExtractedFile],
+        format: ArchiveFormat,
+    ) -> Result<Vec<u8>> {
+        let normalized;
+        let entries = match self.source_date_epoch {
+            Some(epoch) => {
+                normalized = normalize_entries(entries, epoch, format != ArchiveFormat::Deb);
+                normalized.as_slice()
+            }
+            None => entries,
+        };
+
+        match format {
+            ArchiveFormat::Zip => self.write_zip(entries),
+            ArchiveFormat::Ar => self.write_ar(entries),
+            ArchiveFormat::Deb => self.write_ar(entries),
+            ArchiveFormat::Tar
+            | ArchiveFormat::TarGz
+            | ArchiveFormat::TarBz2
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarZst
+            | ArchiveFormat::TarLz4
+            | ArchiveFormat::TarZ
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::TarLzip
+            | ArchiveFormat::TarBr
+            | ArchiveFormat::TarSz => self.write_tar(entries, format),
+            ArchiveFormat::SevenZ => self.write_7z(entries),
+            ArchiveFormat::Cab => self.write_cab(entries),
+            #[cfg(feature = "rar-write")]
+            ArchiveFormat::Rar => self.write_rar(entries),
+            #[cfg(not(feature = "rar-write"))]
+            ArchiveFormat::Rar => Err(ArchiveError::UnsupportedFormat(
+                "writing RAR archives requires the `rar-write` feature".to_string(),
+            )),
+            ArchiveFormat::Gz => {
+                let file = single_file(entries)?;
+                self.compress_gz(&file.data, Some(file))
+            }
+            ArchiveFormat::Bz2 => self.compress_bz2(&single_file(entries)?.data),
+            ArchiveFormat::Xz => self.compress_xz(&single_file(entries)?.data),
+            ArchiveFormat::Lz4 => self.compress_lz4(&single_file(entries)?.data),
+            ArchiveFormat::Zst => self.compress_zst(&single_file(entries)?.data),
+            ArchiveFormat::Z => self.compress_z(&single_file(entries)?.data),
+            ArchiveFormat::Lzma => self.compress_lzma(&single_file(entries)?.data),
+            ArchiveFormat::Lzip => self.compress_lzip(&single_file(entries)?.data),
+            ArchiveFormat::Br => self.compress_br(&single_file(entries)?.data),
+            ArchiveFormat::Sz => self.compress_sz(&single_file(entries)?.data),
+        }
+    }
+
+    /// Returns whether deterministic mode is on.
+    pub(crate) fn is_deterministic(&self) -> bool {
+        self.source_date_epoch.is_some()
+    }
+
+    /// Starts an archive that is written one entry at a time, for callers
+    /// that produce the entries as they go.
+    ///
+    /// ZIP and TAR archives, compressed or not, are built as entries are
+    /// added, so each entry's contents can be dropped once it has been
+    /// added; compressed TAR archives whose encoder needs all of its input
+    /// at once (XZ, LZMA, lzip and compress) hold the uncompressed TAR
+    /// stream instead. Other formats, and deterministic output, which sorts
+    /// the entries, collect them and are written when the archive is
+    /// finished.
+    pub(crate) fn entry_writer(&self, format: ArchiveFormat) -> Result<EntryWriter<'_>> {
+        let target = match format {
+            _ if self.source_date_epoch.is_some() => Target::Collect(Vec::new()),
+            ArchiveFormat::Zip => Target::Zip(zip::ZipWriter::new(Cursor::new(Vec::new()))),
+            ArchiveFormat::Tar
+            | ArchiveFormat::TarGz
+            | ArchiveFormat::TarBz2
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarZst
+            | ArchiveFormat::TarLz4
+            | ArchiveFormat::TarZ
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::TarLzip
+            | ArchiveFormat::TarBr
+            | ArchiveFormat::TarSz => Target::Tar(tar::Builder::new(self.tar_output(format)?)),
+            _ => Target::Collect(Vec::new()),
+        };
+        Ok(EntryWriter {
+            writer: self,
+            format,
+            target,
+        })
+    }
+
+    fn write_zip(&self, entries: &[ExtractedFile]) -> Result<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for entry in entries {
+            self.add_zip_entry(&mut writer, entry)?;
+        }
+        Ok(writer.finish()?.into_inner())
+    }
+
+    fn add_zip_entry<W: Write + Seek>(
+        &self,
+        writer: &mut zip::ZipWriter<W>,
+        entry: &ExtractedFile,
+    ) -> Result<()> {
+        use zip::write::SimpleFileOptions;
+
+        let metadata = &entry.metadata;
+        let mut options = match self.compression_level {
+            CompressionLevel::Store => {
+                SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored)
+            }
+            level => SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .compression_level(Some(i64::from(level.deflate()))),
+        };
+        if let Some(mtime) = metadata.mtime {
+            options = options.last_modified_time(timestamp::unix_to_zip(mtime));
+        }
+        if let Some(mode) = metadata.mode {
+            options = options.unix_permissions(mode);
+        }
+
+        if entry.is_directory {
+            writer.add_directory(entry.path.as_str(), options)?;
+        } else if let Some(target) = &metadata.link_target {
+            writer.add_symlink(entry.path.as_str(), target.as_str(), options)?;
+        } else {
+            let options = options.large_file(entry.data.len() as u64 >= u64::from(u32::MAX));
+            writer.start_file(entry.path.as_str(), options)?;
+            writer.write_all(&entry.data)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a TAR archive, compressed as `format` says.
+    fn write_tar(&self, entries: &[ExtractedFile], format: ArchiveFormat) -> Result<Vec<u8>> {
+        let mut builder = tar::Builder::new(self.tar_output(format)?);
+        for entry in entries {
+            append_tar_entry(&mut builder, entry)?;
+        }
+        self.finish_tar(builder.into_inner()?, format)
+    }
+
+    /// Returns where the TAR stream of `format` is written.
+    fn tar_output(&self, format: ArchiveFormat) -> Result<TarOutput> {
+        Ok(match format {
+            ArchiveFormat::TarGz => TarOutput::Gz(self.gz_encoder(None)?),
+            ArchiveFormat::TarBz2 => TarOutput::Bz2(self.bz2_encoder()),
+            ArchiveFormat::TarZst => TarOutput::Zst(self.zst_encoder()?),
+            ArchiveFormat::TarLz4 => TarOutput::Lz4(self.lz4_encoder()?),
+            ArchiveFormat::TarBr => TarOutput::Br(Box::new(self.br_encoder())),
+            ArchiveFormat::TarSz => {
+                TarOutput::Sz(Box::new(snap::write::FrameEncoder::new(Vec::new())))
+            }
+            _ => TarOutput::Buffer(Vec::new()),
+        })
+    }
+
+    /// Finishes the TAR stream of `format`, returning the archive.
+    fn finish_tar(&self, output: TarOutput, format: ArchiveFormat) -> Result<Vec<u8>> {
+        match output {
+            TarOutput::Buffer(tar) => match format {
+                ArchiveFormat::TarXz => self.compress_xz(&tar),
+                ArchiveFormat::TarZ => self.compress_z(&tar),
+                ArchiveFormat::TarLzma => self.compress_lzma(&tar),
+                ArchiveFormat::TarLzip => self.compress_lzip(&tar),
+                _ => Ok(tar),
+            },
+            TarOutput::Gz(encoder) => Ok(encoder.finish()?),
+            TarOutput::Bz2(encoder) => Ok(encoder.finish()?),
+            TarOutput::Zst(encoder) => Ok(encoder.finish()?),
+            TarOutput::Lz4(encoder) => {
+                let (output, result) = encoder.finish();
+                result?;
+                Ok(output)
+            }
+            TarOutput::Br(encoder) => Ok(encoder.into_inner()),
+            TarOutput::Sz(encoder) => encoder.into_inner().map_err(|e| e.into_error().into()),
+        }
+    }
+
+    fn write_ar(&self, entries: &[ExtractedFile]) -> Result<Vec<u8>> {
+        let mut builder = ar::Builder::new(Vec::new());
+
+        for entry in entries.iter().filter(|entry| is_regular_file(entry)) {
+            let metadata = &entry.metadata;
+            let mut header =
+                ar::Header::new(entry.path.as_bytes().to_vec(), entry.data.len() as u64);
+            header.set_mtime(metadata.mtime.unwrap_or(0));
+            header.set_uid(metadata.uid.unwrap_or(0) as u32);
+            header.set_gid(metadata.gid.unwrap_or(0) as u32);
+            header.set_mode(S_IFREG | metadata.mode.unwrap_or(DEFAULT_FILE_MODE));
+            builder.append(&header, entry.data.as_slice())?;
+        }
+
+        Ok(builder.into_inner()?)
+    }
+
+    fn write_7z(&self, entries: &[ExtractedFile]) -> Result<Vec<u8>> {
+        let mut writer =
+            sevenz_rust::SevenZWriter::new(Cursor::new(Vec::new())).map_err(sevenz_error)?;
+        // The encoder only implements LZMA and LZMA2, so even `Store`
+        // compresses, using the fastest preset.
+        writer.set_content_methods(vec![
+            LZMA2Options::with_preset(self.compression_level.lzma_preset()).into(),
+        ]);
+
+        for entry in entries {
+            let metadata = &entry.metadata;
+            let mut archive_entry = sevenz_rust::SevenZArchiveEntry::new();
+            archive_entry.name = entry.path.trim_end_matches('/').to_string();
+            archive_entry.is_directory = entry.is_directory;
+            if let Some(mtime) = metadata.mtime {
+                archive_entry.has_last_modified_date = true;
+                archive_entry.last_modified_date =
+                    sevenz_rust::nt_time::FileTime::from_unix_time(mtime as i64)
+                        .map_err(|e| ArchiveError::InvalidInput(format!("7z timestamp: {}", e)))?;
+            }
+
+            // Like p7zip, store Unix modes in the upper half of the Windows
+            // attributes, and symlink targets as the entry's contents.
+            let data = match &metadata.link_target {
+                Some(target) => target.as_bytes(),
+                None => entry.data.as_slice(),
+            };
+            let mode = if entry.is_directory {
+                metadata.mode.map(|mode| S_IFDIR | mode)
+            } else if entry.is_symlink() {
+                Some(S_IFLNK | metadata.mode.unwrap_or(DEFAULT_LINK_MODE))
+            } else {
+                metadata.mode.map(|mode| S_IFREG | mode)
+            };
+            if let Some(mode) = mode {
+                let dos_attributes = if entry.is_directory { 0x10 } else { 0x20 };
+                archive_entry.has_windows_attributes = true;
+                archive_entry.windows_attributes =
+                    (mode << 16) | SEVENZ_UNIX_EXTENSION | dos_attributes;
+            }
+
+            // Empty files and directories are stored without a data stream.
+            let reader = (!entry.is_directory && !data.is_empty()).then_some(data);
+            writer
+                .push_archive_entry(archive_entry, reader)
+                .map_err(sevenz_error)?;
+        }
+
+        Ok(writer.finish()?.into_inner())
+    }
+
+    fn write_cab(&self, entries: &[ExtractedFile]) -> Result<Vec<u8>> {
+        let files: Vec<&ExtractedFile> = entries
+            .iter()
+            .filter(|entry| is_regular_file(entry))
+            .collect();
+
+        let compression = match self.compression_level {
+            CompressionLevel::Store => cab::CompressionType::None,
+            _ => cab::CompressionType::MsZip,
+        };
+        let mut builder = cab::CabinetBuilder::new();
+        let folder = builder.add_folder(compression);
+        for file in &files {
+            // Cabinet paths use Windows separators.
+            let file_builder = folder.add_file(file.path.replace('/', "\\"));
+            if let Some(mtime) = file.metadata.mtime {
+                file_builder.set_datetime(timestamp::unix_to_primitive(mtime));
+            }
+        }
+
+        // The writer hands out the files in the order they were added.
+        let mut writer = builder.build(Cursor::new(Vec::new()))?;
+        for file in files {
+            if let Some(mut file_writer) = writer.next_file()? {
+                file_writer.write_all(&file.data)?;
+            }
+        }
+
+        Ok(writer.finish()?.into_inner())
+    }
+
+    #[cfg(feature = "rar-write")]
+    fn write_rar(&self, entries: &[ExtractedFile]) -> Result<Vec<u8>> {
+        let level = self.compression_level.native(1, 3, 5, 0..=5);
+        let mut builder = rars::Builder::new(rars::ArchiveVersion::Rar50)
+            .compression_level(Some(level as u8))
+            .store(self.compression_level == CompressionLevel::Store);
+
+        for entry in entries {
+            let metadata = &entry.metadata;
+            let name = entry.path.trim_end_matches('/').as_bytes().to_vec();
+            // RAR 5 stores 32-bit Unix timestamps.
+            let mtime = metadata
+                .mtime
+                .map(|mtime| u32::try_from(mtime).unwrap_or(u32::MAX));
+
+            if entry.is_directory {
+                builder.add_directory(name, mtime, metadata.mode)
+            } else if let Some(target) = &metadata.link_target {
+                builder.add_unix_symlink(
+                    name,
+                    target.as_bytes().to_vec(),
+                    false,
+                    mtime,
+                    metadata.mode,
+                )
+            } else {
+                let mode = metadata.mode.map(|mode| S_IFREG | mode);
+                builder.add_bytes(name, entry.data.clone(), mtime, mode)
+            }
+            .map_err(rar_error)?;
+        }
+
+        builder.to_bytes().map_err(rar_error)
+    }
+
+    // Single-stream compression methods, shared by single-file formats and
+    // compressed TAR archives
+
+    fn compress_gz(&self, data: &[u8], file: Option<&ExtractedFile>) -> Result<Vec<u8>> {
+        let mut encoder = self.gz_encoder(file)?;
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    fn gz_encoder(
+        &self,
+        file: Option<&ExtractedFile>,
+    ) -> Result<flate2::write::GzEncoder<Vec<u8>>> {
+        let mut builder = flate2::GzBuilder::new();
+        if let Some(file) = file.filter(|_| self.source_date_epoch.is_none()) {
+            let name = file.path.rsplit('/').next().unwrap_or_default();
+            if name.contains('\0') {
+                return Err(ArchiveError::InvalidInput(
+                    "gzip file names cannot contain NUL bytes".to_string(),
+                ));
+            }
+            if !name.is_empty() {
+                builder = builder.filename(name);
+            }
+            // The header holds a 32-bit timestamp; zero means "not set".
+            if let Some(mtime) = file.metadata.mtime {
+                builder = builder.mtime(u32::try_from(mtime).unwrap_or(0));
+            }
+        }
+
+        let level = flate2::Compression::new(self.compression_level.deflate());
+        Ok(builder.write(Vec::new(), level))
+    }
+
+    fn compress_bz2(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = self.bz2_encoder();
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    fn bz2_encoder(&self) -> bzip2::write::BzEncoder<Vec<u8>> {
+        let level = self.compression_level.native(1, 6, 9, 1..=9);
+        bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::new(level))
+    }
+
+    fn compress_xz(&self, data: &[u8]) -> Result<Vec<u8>> {
+        xz::compress(data, self.compression_level.lzma_preset())
+    }
+
+    fn compress_lzma(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let options = LZMA2Options::with_preset(self.compression_level.lzma_preset());
+        let mut output = Vec::new();
+        let mut writer = LZMAWriter::new_use_header(
+            CountingWriter::new(&mut output),
+            &options,
+            Some(data.len() as u64),
+        )?;
+        writer.write_all(data)?;
+        writer.finish()?;
+        drop(writer);
+        Ok(output)
+    }
+
+    fn compress_lzip(&self, data: &[u8]) -> Result<Vec<u8>> {
+        lzip::compress(data, self.compression_level.lzma_preset())
+    }
+
+    fn compress_zst(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = self.zst_encoder()?;
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    fn zst_encoder(&self) -> Result<zstd::stream::write::Encoder<'static, Vec<u8>>> {
+        let level = self.compression_level.native(1, 3, 19, 1..=22);
+        Ok(zstd::stream::write::Encoder::new(Vec::new(), level as i32)?)
+    }
+
+    fn compress_lz4(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = self.lz4_encoder()?;
+        encoder.write_all(data)?;
+        let (output, result) = encoder.finish();
+        result?;
+        Ok(output)
+    }
+
+    fn lz4_encoder(&self) -> Result<lz4::Encoder<Vec<u8>>> {
+        let level = self.compression_level.native(0, 0, 12, 0..=12);
+        Ok(lz4::EncoderBuilder::new().level(level).build(Vec::new())?)
+    }
+
+    fn compress_z(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(lzw::compress(data))
+    }
+
+    fn compress_br(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut writer = self.br_encoder();
+        writer.write_all(data)?;
+        Ok(writer.into_inner())
+    }
+
+    fn br_encoder(&self) -> brotli::CompressorWriter<Vec<u8>> {
+        let quality = self.compression_level.native(0, 11, 11, 0..=11);
+        brotli::CompressorWriter::new(Vec::new(), 4096, quality, 22)
+    }
+
+    fn compress_sz(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+        encoder.write_all(data)?;
+        encoder.into_inner().map_err(|e| e.into_error().into())
+    }
+}
+
+/// An archive being written one entry at a time, see
+/// [`ArchiveWriter::entry_writer`].
+pub(crate) struct EntryWriter<'a> {
+    writer: &'a ArchiveWriter,
+    format: ArchiveFormat,
+    target: Target,
+}
+
+enum Target {
+    Zip(zip::ZipWriter<Cursor<Vec<u8>>>),
+    Tar(tar::Builder<TarOutput>),
+    /// Entries kept until the archive is finished.
+    Collect(Vec<ExtractedFile>),
+}
+
+/// Where a TAR stream being built goes: straight into an encoder, or into
+/// a buffer for plain TAR and for encoders that take all of their input at
+/// once.
+enum TarOutput {
+    Buffer(Vec<u8>),
+    Gz(flate2::write::GzEncoder<Vec<u8>>),
+    Bz2(bzip2::write::BzEncoder<Vec<u8>>),
+    Zst(zstd::stream::write::Encoder<'static, Vec<u8>>),
+    Lz4(lz4::Encoder<Vec<u8>>),
+    Br(Box<brotli::CompressorWriter<Vec<u8>>>),
+    Sz(Box<snap::write::FrameEncoder<Vec<u8>>>),
+}
+
+impl EntryWriter<'_> {
+    /// Adds the next entry.
+    pub(crate) fn add(&mut self, entry: ExtractedFile) -> Result<()> {
+        match &mut self.target {
+            Target::Zip(writer) => self.writer.add_zip_entry(writer, &entry),
+            Target::Tar(builder) => append_tar_entry(builder, &entry),
+            Target::Collect(entries) => {
+                entries.push(entry);
+                Ok(())
+            }
+        }
+    }
+
+    /// Finishes the archive, returning its bytes.
+    pub(crate) fn finish(self) -> Result<Vec<u8>> {
+        match self.target {
+            Target::Zip(zip) => Ok(zip.finish()?.into_inner()),
+            Target::Tar(builder) => self.writer.finish_tar(builder.into_inner()?, self.format),
+            Target::Collect(entries) => self.writer.write_with_format(&entries, self.format),
+        }
+    }
+}
+
+impl Write for TarOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TarOutput::Buffer(buffer) => buffer.write(buf),
+            TarOutput::Gz(encoder) => encoder.write(buf),
+            TarOutput::Bz2(encoder) => encoder.write(buf),
+            TarOutput::Zst(encoder) => encoder.write(buf),
+            TarOutput::Lz4(encoder) => encoder.write(buf),
+            TarOutput::Br(encoder) => encoder.write(buf),
+            TarOutput::Sz(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TarOutput::Buffer(buffer) => buffer.flush(),
+            TarOutput::Gz(encoder) => encoder.flush(),
+            TarOutput::Bz2(encoder) => encoder.flush(),
+            TarOutput::Zst(encoder) => encoder.flush(),
+            TarOutput::Lz4(encoder) => encoder.flush(),
+            TarOutput::Br(encoder) => encoder.flush(),
+            TarOutput::Sz(encoder) => encoder.flush(),
+        }
+    }
+}
+
+fn append_tar_entry<W: Write>(builder: &mut tar::Builder<W>, entry: &ExtractedFile) -> Result<()> {
+    let metadata = &entry.metadata;
+    let mut header = tar::Header::new_gnu();
+    header.set_mtime(metadata.mtime.unwrap_or(0));
+    header.set_uid(metadata.uid.unwrap_or(0));
+    header.set_gid(metadata.gid.unwrap_or(0));
+    if let Some(owner) = &metadata.owner {
+        header.set_username(owner)?;
+    }
+    if let Some(group) = &metadata.group {
+        header.set_groupname(group)?;
+    }
+
+    if entry.is_directory {
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(metadata.mode.unwrap_or(DEFAULT_DIR_MODE));
+        header.set_size(0);
+        builder.append_data(&mut header, &entry.path, io::empty())?;
+    } else if let Some(target) = &metadata.link_target {
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_mode(metadata.mode.unwrap_or(DEFAULT_LINK_MODE));
+        header.set_size(0);
+        builder.append_link(&mut header, &entry.path, target)?;
+    } else {
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_mode(metadata.mode.unwrap_or(DEFAULT_FILE_MODE));
+        header.set_size(entry.data.len() as u64);
+        builder.append_data(&mut header, &entry.path, entry.data.as_slice())?;
+    }
+    Ok(())
+}
+
+/// Applies the normalizations of deterministic mode, returning copies of the
+/// entries, sorted by path if `sort` is set.
+fn normalize_entries(
+    entries: &[ExtractedFile],
+    source_date_epoch: u64,
+    sort: bool,
+) -> Vec<ExtractedFile> {
+    let mut normalized: Vec<ExtractedFile> = entries
+        .iter()
+        .map(|entry| {
+            let mode = if entry.is_directory {
+                DEFAULT_DIR_MODE
+            } else if entry.is_symlink() {
+                DEFAULT_LINK_MODE
+            } else if entry.metadata.mode.is_some_and(|mode| mode & 0o111 != 0) {
+                0o755
+            } else {
+                DEFAULT_FILE_MODE
+            };
+            let mtime = entry
+                .metadata
+                .mtime
+                .map_or(source_date_epoch, |mtime| mtime.min(source_date_epoch));
+            ExtractedFile {
+                path: entry.path.clone(),
+                data: entry.data.clone(),
+                is_directory: entry.is_directory,
+                metadata: EntryMetadata {
+                    mtime: Some(mtime),
+                    mode: Some(mode),
+                    uid: Some(0),
+                    gid: Some(0),
+                    owner: None,
+                    group: None,
+                    link_target: entry.metadata.link_target.clone(),
+                    ..EntryMetadata::default()
+                },
+                digests: Vec::new(),
+            }
+        })
+        .collect();
+    if sort {
+        normalized.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    normalized
+}
+
+/// Returns the only entry of a single-file format, which must be a regular file.
+fn single_file(entries: &[ExtractedFile]) -> Result<&ExtractedFile> {
+    match entries {
+        [file] if is_regular_file(file) => Ok(file),
+        _ => Err(ArchiveError::InvalidInput(format!(
+            "single-file formats take exactly one regular file, got {} entries",
+            entries.len()
+        ))),
+    }
+}
+
+fn is_regular_file(entry: &ExtractedFile) -> bool {
+    !entry.is_directory && !entry.is_symlink()
+}
+
+fn sevenz_error(err: sevenz_rust::Error) -> ArchiveError {
+    ArchiveError::InvalidInput(format!("7z error: {}", err))
+}
+
+#[cfg(feature = "rar-write")]
+fn rar_error(err: rars::Error) -> ArchiveError {
+    ArchiveError::InvalidInput(format!("RAR error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_levels() {
+        assert_eq!(CompressionLevel::Store.native(1, 6, 9, 0..=9), 0);
+        assert_eq!(CompressionLevel::Fastest.native(1, 6, 9, 0..=9), 1);
+        assert_eq!(CompressionLevel::Default.native(1, 6, 9, 0..=9), 6);
+        assert_eq!(CompressionLevel::Best.native(1, 6, 9, 0..=9), 9);
+        assert_eq!(CompressionLevel::Level(3).native(1, 6, 9, 0..=9), 3);
+        assert_eq!(CompressionLevel::Level(30).native(1, 3, 19, 1..=22), 22);
+        assert_eq!(CompressionLevel::Level(0).native(1, 3, 19, 1..=22), 1);
+    }
+
+    #[test]
+    fn test_write_without_format_returns_unknown() {
+        let result = ArchiveWriter::new().write(&[]);
+        assert!(matches!(result, Err(ArchiveError::UnknownFormat)));
+    }
+
+    #[test]
+    fn test_single_file_rejects_other_inputs() {
+        let two = [
+            ExtractedFile::file("a.txt", b"a".to_vec()),
+            ExtractedFile::file("b.txt", b"b".to_vec()),
+        ];
+        assert!(matches!(
+            single_file(&two),
+            Err(ArchiveError::InvalidInput(_))
+        ));
+        assert!(single_file(&[]).is_err());
+        assert!(single_file(&[ExtractedFile::directory("dir/")]).is_err());
+        assert!(single_file(&[ExtractedFile::symlink("link", "target")]).is_err());
+    }
+}
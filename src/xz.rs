@@ -0,0 +1,148 @@
+//! Encoder for the XZ container format (`.xz`).
+//!
+//! The LZMA2 encoder only produces a raw LZMA2 stream, so this module wraps
+//! it in the container: a stream header, a single block holding the whole
+//! input with a CRC-32 check, the index describing that block, and the
+//! stream footer. Empty input produces a stream with no blocks, as `xz` does.
+
+use crate::error::Result;
+use sevenz_rust::lzma::{CountingWriter, LZMA2Options, LZMA2Writer};
+use std::io::Write;
+
+/// Magic bytes at the start of every XZ stream.
+const HEADER_MAGIC: [u8; 6] = [0xFD, b'7', b'z', b'X', b'Z', 0x00];
+/// Magic bytes at the end of every XZ stream.
+const FOOTER_MAGIC: [u8; 2] = [b'Y', b'Z'];
+/// Stream flags selecting a CRC-32 integrity check.
+const STREAM_FLAGS: [u8; 2] = [0x00, 0x01];
+/// Filter ID of LZMA2.
+const FILTER_LZMA2: u8 = 0x21;
+/// Size in bytes of the CRC-32 check stored after each block.
+const CHECK_SIZE: usize = 4;
+
+/// Compresses `data` into an XZ stream using an LZMA preset from 0 to 9.
+pub(crate) fn compress(data: &[u8], preset: u32) -> Result<Vec<u8>> {
+    let options = LZMA2Options::with_preset(preset);
+
+    let mut output = HEADER_MAGIC.to_vec();
+    output.extend_from_slice(&STREAM_FLAGS);
+    output.extend_from_slice(&crc32(&STREAM_FLAGS).to_le_bytes());
+
+    let mut index = vec![0x00];
+    if data.is_empty() {
+        write_varint(&mut index, 0);
+    } else {
+        let block_start = output.len();
+        let mut header = vec![0x00, 0x00, FILTER_LZMA2, 0x01, dict_size_property(&options)];
+        header.resize(header.len().next_multiple_of(4), 0);
+        // The size byte counts the header, including the CRC, in 4-byte units.
+        header[0] = ((header.len() + 4) / 4 - 1) as u8;
+        let header_crc = crc32(&header);
+        output.extend_from_slice(&header);
+        output.extend_from_slice(&header_crc.to_le_bytes());
+
+        let mut writer = LZMA2Writer::new(CountingWriter::new(&mut output), &options);
+        writer.write_all(data)?;
+        writer.finish()?;
+        drop(writer);
+
+        let unpadded_size = output.len() - block_start + CHECK_SIZE;
+        pad_to_four(&mut output);
+        output.extend_from_slice(&crc32(data).to_le_bytes());
+
+        write_varint(&mut index, 1);
+        write_varint(&mut index, unpadded_size as u64);
+        write_varint(&mut index, data.len() as u64);
+    }
+    pad_to_four(&mut index);
+    let index_crc = crc32(&index);
+    index.extend_from_slice(&index_crc.to_le_bytes());
+    output.extend_from_slice(&index);
+
+    let mut footer = ((index.len() / 4 - 1) as u32).to_le_bytes().to_vec();
+    footer.extend_from_slice(&STREAM_FLAGS);
+    output.extend_from_slice(&crc32(&footer).to_le_bytes());
+    output.extend_from_slice(&footer);
+    output.extend_from_slice(&FOOTER_MAGIC);
+    Ok(output)
+}
+
+/// Encodes the dictionary size as the LZMA2 properties byte: the smallest
+/// value of the form `2^n` or `3 * 2^(n-1)` that is not below the size.
+fn dict_size_property(options: &LZMA2Options) -> u8 {
+    (0u8..40)
+        .find(|&prop| {
+            let size = (2u64 | u64::from(prop & 1)) << (prop / 2 + 11);
+            size >= u64::from(options.dict_size)
+        })
+        .unwrap_or(40)
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn pad_to_four(output: &mut Vec<u8>) {
+    output.resize(output.len().next_multiple_of(4), 0);
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompress(data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        lzma_rs::xz_decompress(&mut &data[..], &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = b"Hello, XZ! ".repeat(1000);
+        for preset in [0, 6] {
+            let compressed = compress(&data, preset).unwrap();
+            assert!(compressed.starts_with(&HEADER_MAGIC));
+            assert!(compressed.ends_with(&FOOTER_MAGIC));
+            assert_eq!(compressed.len() % 4, 0);
+            assert_eq!(decompress(&compressed), data);
+        }
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let compressed = compress(b"", 6).unwrap();
+        // Stream header, empty index and stream footer.
+        assert_eq!(compressed.len(), 12 + 8 + 12);
+        assert_eq!(decompress(&compressed), b"");
+    }
+
+    #[test]
+    fn test_dict_size_property() {
+        let mut options = LZMA2Options::with_preset(6);
+        options.dict_size = 8 << 20;
+        assert_eq!(dict_size_property(&options), 22);
+        options.dict_size = 3 << 20;
+        assert_eq!(dict_size_property(&options), 19);
+        options.dict_size = 4096;
+        assert_eq!(dict_size_property(&options), 0);
+    }
+
+    #[test]
+    fn test_write_varint() {
+        let mut output = Vec::new();
+        write_varint(&mut output, 0x7F);
+        write_varint(&mut output, 0x80);
+        write_varint(&mut output, 300);
+        assert_eq!(output, [0x7F, 0x80, 0x01, 0xAC, 0x02]);
+    }
+}
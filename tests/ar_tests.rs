@@ -4,6 +4,7 @@ mod common;
 
 use archive::{ArchiveExtractor, ArchiveFormat};
 use common::{assert_contains_file, read_test_archive};
+use std::collections::BTreeMap;
 
 #[test]
 fn test_ar() {
@@ -17,3 +18,162 @@ fn test_ar() {
     assert!(!files.is_empty(), "Expected non-empty archive");
     assert_contains_file(&files, "hello.txt");
 }
+
+/// A member header and contents, padded to an even length.
+fn member(name: &[u8], contents: &[u8]) -> Vec<u8> {
+    let mut member = format!("{:<16}{:<12}{:<6}{:<6}{:<8}", "", 0, 0, 0, 644).into_bytes();
+    member[..name.len()].copy_from_slice(name);
+    member.extend_from_slice(format!("{:<10}`\n", contents.len()).as_bytes());
+    member.extend_from_slice(contents);
+    if contents.len() % 2 == 1 {
+        member.push(b'\n');
+    }
+    member
+}
+
+/// A BSD member, whose name is stored in front of its contents.
+fn bsd_member(name: &[u8], contents: &[u8]) -> Vec<u8> {
+    let mut stored = name.to_vec();
+    stored.resize(name.len().next_multiple_of(4), 0);
+    let field = format!("#1/{}", stored.len());
+    stored.extend_from_slice(contents);
+    member(field.as_bytes(), &stored)
+}
+
+/// Lays out `members` after `table(offsets)`, where `offsets` are the header
+/// offsets of the members; the table's length must not depend on them.
+fn archive(table: impl Fn(&[u32]) -> Vec<u8>, members: &[Vec<u8>]) -> Vec<u8> {
+    let mut offsets = Vec::new();
+    let mut pos = 8 + table(&vec![0; members.len()]).len();
+    for member in members {
+        offsets.push(pos as u32);
+        pos += member.len();
+    }
+    let mut data = b"!<arch>\n".to_vec();
+    data.extend_from_slice(&table(&offsets));
+    data.extend(members.iter().flatten());
+    data
+}
+
+fn paths(data: &[u8]) -> Vec<String> {
+    ArchiveExtractor::new()
+        .extract_with_format(data, ArchiveFormat::Ar)
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path)
+        .collect()
+}
+
+fn symbol_map(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .map(|&(symbol, member)| (symbol.to_string(), member.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_gnu_static_library() {
+    let long_name = "an_object_with_a_long_name.o";
+    let names = format!("{}/\n", long_name);
+    let table = |offsets: &[u32]| {
+        let symbols = [
+            ("long_fn", 0),
+            ("short_fn", 1),
+            ("shared", 0),
+            ("shared", 1),
+        ];
+        let mut contents = (symbols.len() as u32).to_be_bytes().to_vec();
+        for (_, member) in symbols {
+            contents.extend_from_slice(&offsets[member].to_be_bytes());
+        }
+        for (symbol, _) in symbols {
+            contents.extend_from_slice(symbol.as_bytes());
+            contents.push(0);
+        }
+        let mut table = member(b"/", &contents);
+        table.extend(member(b"//", names.as_bytes()));
+        table
+    };
+    let data = archive(
+        table,
+        &[member(b"/0", b"long"), member(b"short.o/", b"short")],
+    );
+
+    assert_eq!(paths(&data), [long_name, "short.o"]);
+    assert_eq!(
+        ArchiveExtractor::new().ar_symbols(&data).unwrap(),
+        symbol_map(&[
+            ("long_fn", long_name),
+            ("shared", long_name),
+            ("short_fn", "short.o"),
+        ])
+    );
+    assert_eq!(
+        ArchiveFormat::from_filename("libfoo.a").unwrap(),
+        ArchiveFormat::Ar
+    );
+}
+
+#[test]
+fn test_bsd_static_library() {
+    let long_name = "an_object_with_a_long_name.o";
+    let table = |offsets: &[u32]| {
+        let names = b"long_fn\0short_fn\0";
+        let mut contents = 16u32.to_le_bytes().to_vec();
+        for (name, offset) in [(0u32, offsets[0]), (8, offsets[1])] {
+            contents.extend_from_slice(&name.to_le_bytes());
+            contents.extend_from_slice(&offset.to_le_bytes());
+        }
+        contents.extend_from_slice(&(names.len() as u32).to_le_bytes());
+        contents.extend_from_slice(names);
+        bsd_member(b"__.SYMDEF SORTED", &contents)
+    };
+    let data = archive(
+        table,
+        &[
+            bsd_member(long_name.as_bytes(), b"long"),
+            bsd_member(b"short.o", b"short"),
+        ],
+    );
+
+    assert_eq!(paths(&data), [long_name, "short.o"]);
+    assert_eq!(
+        ArchiveExtractor::new().ar_symbols(&data).unwrap(),
+        symbol_map(&[("long_fn", long_name), ("short_fn", "short.o")])
+    );
+}
+
+#[test]
+fn test_plain_symbol_table_is_hidden() {
+    let table = |offsets: &[u32]| {
+        let mut contents = 8u32.to_le_bytes().to_vec();
+        contents.extend_from_slice(&0u32.to_le_bytes());
+        contents.extend_from_slice(&offsets[0].to_le_bytes());
+        contents.extend_from_slice(&4u32.to_le_bytes());
+        contents.extend_from_slice(b"foo\0");
+        member(b"__.SYMDEF", &contents)
+    };
+    let data = archive(table, &[member(b"foo.o", b"foo")]);
+
+    assert_eq!(paths(&data), ["foo.o"]);
+    assert_eq!(
+        ArchiveExtractor::new().ar_symbols(&data).unwrap(),
+        symbol_map(&[("foo", "foo.o")])
+    );
+}
+
+#[test]
+fn test_archive_without_symbol_table() {
+    let data = read_test_archive("archive.ar");
+    assert!(
+        ArchiveExtractor::new()
+            .ar_symbols(&data)
+            .unwrap()
+            .is_empty()
+    );
+    assert!(
+        ArchiveExtractor::new()
+            .ar_symbols(b"not an archive")
+            .is_err()
+    );
+}
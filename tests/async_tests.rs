@@ -0,0 +1,165 @@
+//! Tests for the tokio-based async extraction API.
+#![cfg(feature = "async")]
+
+mod common;
+
+use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat, CancellationToken, ExtractedFile};
+use common::sample_entries;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::AsyncWriteExt;
+
+fn write(format: ArchiveFormat) -> Vec<u8> {
+    common::write(&sample_entries(), format)
+}
+
+fn summary(files: &[ExtractedFile]) -> Vec<(&str, &[u8], bool)> {
+    files
+        .iter()
+        .map(|f| (f.path.as_str(), f.data.as_slice(), f.is_directory))
+        .collect()
+}
+
+/// A reader fed in small chunks by a separate task, like a network stream.
+fn chunked(data: Vec<u8>) -> tokio::io::DuplexStream {
+    let (mut writer, reader) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        for chunk in data.chunks(1000) {
+            if writer.write_all(chunk).await.is_err() {
+                return;
+            }
+        }
+    });
+    reader
+}
+
+#[tokio::test]
+async fn test_async_matches_sync_for_every_format() {
+    for format in [
+        ArchiveFormat::Tar,
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarBz2,
+        ArchiveFormat::TarXz,
+        ArchiveFormat::TarZst,
+        ArchiveFormat::TarLzip,
+        ArchiveFormat::Ar,
+        ArchiveFormat::Zip,
+        ArchiveFormat::SevenZ,
+        ArchiveFormat::Cab,
+        #[cfg(feature = "rar-write")]
+        ArchiveFormat::Rar,
+    ] {
+        let data = write(format);
+        let expected = ArchiveExtractor::new()
+            .extract_with_format(&data, format)
+            .unwrap();
+        let files = ArchiveExtractor::new()
+            .extract_with_format_async(chunked(data), format)
+            .await
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_eq!(summary(&files), summary(&expected), "{:?}", format);
+    }
+}
+
+#[tokio::test]
+async fn test_async_single_file() {
+    let entries = [ExtractedFile::file("notes.txt", b"notes".to_vec())];
+    for format in [ArchiveFormat::Gz, ArchiveFormat::Zst, ArchiveFormat::Xz] {
+        let data = common::write(&entries, format);
+        let files = ArchiveExtractor::new()
+            .with_source_filename(format!("notes.txt.{}", format.name().to_lowercase()))
+            .with_format(format)
+            .extract_async(chunked(data))
+            .await
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_eq!(files.len(), 1, "{:?}", format);
+        assert_eq!(files[0].data, b"notes", "{:?}", format);
+    }
+}
+
+#[tokio::test]
+async fn test_async_without_format() {
+    let result = ArchiveExtractor::new()
+        .extract_async(chunked(write(ArchiveFormat::Tar)))
+        .await;
+    assert!(matches!(result, Err(ArchiveError::UnknownFormat)));
+}
+
+#[tokio::test]
+async fn test_async_entries_arrive_in_order() {
+    let mut entries = ArchiveExtractor::new()
+        .entries_async(chunked(write(ArchiveFormat::TarGz)), ArchiveFormat::TarGz);
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next_entry().await {
+        paths.push(entry.unwrap().path);
+    }
+    let expected: Vec<String> = sample_entries().into_iter().map(|e| e.path).collect();
+    assert_eq!(paths, expected);
+    assert!(entries.next_entry().await.is_none());
+}
+
+#[tokio::test]
+async fn test_async_limits() {
+    for format in [ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+        let result = ArchiveExtractor::new()
+            .with_max_file_size(64 * 1024)
+            .extract_with_format_async(chunked(write(format)), format)
+            .await;
+        assert!(
+            matches!(result, Err(ArchiveError::FileTooLarge { .. })),
+            "{:?}",
+            format
+        );
+
+        let result = ArchiveExtractor::new()
+            .with_max_total_size(64 * 1024)
+            .extract_with_format_async(chunked(write(format)), format)
+            .await;
+        assert!(
+            matches!(result, Err(ArchiveError::TotalSizeTooLarge { .. })),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_async_invalid_archive() {
+    let result = ArchiveExtractor::new()
+        .extract_with_format_async(chunked(b"not an archive".to_vec()), ArchiveFormat::TarGz)
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_async_cancellation() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let result = ArchiveExtractor::new()
+        .with_cancellation(token)
+        .extract_with_format_async(chunked(write(ArchiveFormat::Tar)), ArchiveFormat::Tar)
+        .await;
+    assert!(matches!(result, Err(ArchiveError::Cancelled)));
+}
+
+#[tokio::test]
+async fn test_dropping_entries_stops_extraction() {
+    let entries: Vec<ExtractedFile> = (0..100)
+        .map(|i| ExtractedFile::file(format!("file{}.txt", i), vec![b'x'; 1024]))
+        .collect();
+    let data = common::write(&entries, ArchiveFormat::Tar);
+
+    let extracted = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&extracted);
+    let mut stream = ArchiveExtractor::new()
+        .with_progress(move |progress| {
+            counter.store(progress.entries_done, Ordering::Relaxed);
+        })
+        .entries_async(chunked(data), ArchiveFormat::Tar);
+    stream.next_entry().await.unwrap().unwrap();
+    drop(stream);
+
+    // Give the worker time to notice.
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert!(extracted.load(Ordering::Relaxed) < 100);
+}
@@ -0,0 +1,135 @@
+//! Tests for zero-copy extraction with ArchiveExtractor::extract_borrowed.
+
+mod common;
+
+use archive::{
+    ArchiveError, ArchiveExtractor, ArchiveFormat, BorrowedFile, CompressionLevel, ExtractedFile,
+};
+use common::{README, sample_entries, write_with_level};
+
+/// The sample entries followed by one whose name needs an AR long-name
+/// record and whose odd size needs AR padding.
+fn write(format: ArchiveFormat, level: CompressionLevel) -> Vec<u8> {
+    let mut entries = sample_entries();
+    entries.push(ExtractedFile::file(
+        "docs/a_rather_long_member_name.bin",
+        vec![3u8; 100_001],
+    ));
+    write_with_level(&entries, format, level)
+}
+
+/// Whether `file` points into `data` rather than at a copy.
+fn points_into(file: &BorrowedFile<'_>, data: &[u8]) -> bool {
+    let range = data.as_ptr_range();
+    let contents = file.data.as_ptr_range();
+    range.start <= contents.start && contents.end <= range.end
+}
+
+fn assert_same_as_copying(data: &[u8], format: ArchiveFormat, borrowed: &[BorrowedFile<'_>]) {
+    let copied = ArchiveExtractor::new()
+        .extract_with_format(data, format)
+        .unwrap();
+    assert_eq!(borrowed.len(), copied.len(), "{:?}", format);
+    for (b, c) in borrowed.iter().zip(&copied) {
+        assert_eq!(b.path, c.path, "{:?}", format);
+        assert_eq!(&*b.data, c.data.as_slice(), "{:?} {}", format, b.path);
+        assert_eq!(b.is_directory, c.is_directory, "{:?}", format);
+        assert_eq!(b.metadata, c.metadata, "{:?}", format);
+    }
+}
+
+#[test]
+fn test_uncompressed_entries_are_borrowed() {
+    for format in [ArchiveFormat::Tar, ArchiveFormat::Ar, ArchiveFormat::Zip] {
+        let data = write(format, CompressionLevel::Store);
+        let files = ArchiveExtractor::new()
+            .extract_borrowed_with_format(&data, format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_same_as_copying(&data, format, &files);
+
+        for file in files.iter().filter(|f| !f.is_directory && !f.is_symlink()) {
+            assert!(file.is_borrowed(), "{:?} {}", format, file.path);
+            assert!(points_into(file, &data), "{:?} {}", format, file.path);
+        }
+    }
+}
+
+#[test]
+fn test_compressed_entries_are_copied() {
+    for (format, level) in [
+        (ArchiveFormat::Zip, CompressionLevel::Default),
+        (ArchiveFormat::TarGz, CompressionLevel::Store),
+        (ArchiveFormat::SevenZ, CompressionLevel::Default),
+    ] {
+        let data = write(format, level);
+        let files = ArchiveExtractor::new()
+            .extract_borrowed_with_format(&data, format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_same_as_copying(&data, format, &files);
+        let large = files.iter().find(|f| f.path.ends_with(".bin")).unwrap();
+        assert!(!large.is_borrowed(), "{:?}", format);
+    }
+}
+
+#[test]
+fn test_borrowed_with_filters_and_configured_format() {
+    let data = write(ArchiveFormat::Tar, CompressionLevel::Store);
+    let files = ArchiveExtractor::new()
+        .with_format(ArchiveFormat::Tar)
+        .with_include("**/*.txt")
+        .extract_borrowed(&data)
+        .unwrap();
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "docs/readme.txt",
+            "docs/empty.txt",
+            "docs/last.txt",
+            "readme.txt"
+        ]
+    );
+    assert_eq!(&*files[0].data, README);
+
+    let result = ArchiveExtractor::new().extract_borrowed(&data);
+    assert!(matches!(result, Err(ArchiveError::UnknownFormat)));
+}
+
+#[test]
+fn test_borrowed_entries_count_toward_limits() {
+    for format in [ArchiveFormat::Tar, ArchiveFormat::Ar, ArchiveFormat::Zip] {
+        let data = write(format, CompressionLevel::Store);
+        let result = ArchiveExtractor::new()
+            .with_max_file_size(64 * 1024)
+            .extract_borrowed_with_format(&data, format);
+        assert!(
+            matches!(result, Err(ArchiveError::FileTooLarge { .. })),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_stored_zip_crc_still_checked() {
+    let mut data = write(ArchiveFormat::Zip, CompressionLevel::Store);
+    let position = data
+        .windows(README.len())
+        .position(|window| window == README)
+        .unwrap();
+    data[position] = b'J';
+
+    let result = ArchiveExtractor::new().extract_borrowed_with_format(&data, ArchiveFormat::Zip);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_into_owned() {
+    let data = write(ArchiveFormat::Tar, CompressionLevel::Store);
+    let files = ArchiveExtractor::new()
+        .extract_borrowed_with_format(&data, ArchiveFormat::Tar)
+        .unwrap();
+    let owned: Vec<ExtractedFile> = files.into_iter().map(BorrowedFile::into_owned).collect();
+    drop(data);
+    assert_eq!(owned[1].data, README);
+}
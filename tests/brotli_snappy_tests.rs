@@ -0,0 +1,132 @@
+//! Tests for Brotli and framed Snappy decompression (br, sz, tar.br, tar.sz)
+
+mod common;
+
+use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat};
+use common::assert_contains_file;
+use std::io::Write;
+
+const HELLO: &[u8] = b"Hello, World!\n";
+
+fn brotli(data: &[u8]) -> Vec<u8> {
+    let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 9, 22);
+    encoder.write_all(data).unwrap();
+    encoder.into_inner()
+}
+
+fn snappy(data: &[u8]) -> Vec<u8> {
+    let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+    encoder.write_all(data).unwrap();
+    encoder.into_inner().unwrap()
+}
+
+fn build_tar() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(HELLO.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "test-data/hello.txt", HELLO)
+        .unwrap();
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn test_single_br_decompression() {
+    let data = brotli(HELLO);
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Br)
+        .expect("Failed to decompress brotli data");
+
+    assert_eq!(files.len(), 1, "Expected single decompressed file");
+    assert_eq!(files[0].path, "data");
+    assert_eq!(files[0].data, HELLO);
+}
+
+#[test]
+fn test_single_sz_decompression() {
+    let data = snappy(HELLO);
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Sz)
+        .expect("Failed to decompress snappy data");
+
+    assert_eq!(files.len(), 1, "Expected single decompressed file");
+    assert_eq!(files[0].path, "data");
+    assert_eq!(files[0].data, HELLO);
+}
+
+#[test]
+fn test_tar_br() {
+    let data = brotli(&build_tar());
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::TarBr)
+        .expect("Failed to extract tar.br");
+
+    let hello = assert_contains_file(&files, "hello.txt");
+    assert_eq!(hello.data, HELLO);
+}
+
+#[test]
+fn test_tar_sz() {
+    let data = snappy(&build_tar());
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::TarSz)
+        .expect("Failed to extract tar.sz");
+
+    let hello = assert_contains_file(&files, "hello.txt");
+    assert_eq!(hello.data, HELLO);
+}
+
+#[test]
+fn test_builder_source_filename_derives_path() {
+    let extractor = ArchiveExtractor::new()
+        .with_source_filename("hello.txt.br")
+        .with_format_from_filename()
+        .expect("Failed to infer format");
+    let files = extractor.extract(&brotli(HELLO)).unwrap();
+    assert_eq!(files[0].path, "hello.txt");
+
+    let extractor = ArchiveExtractor::new()
+        .with_source_filename("hello.txt.sz")
+        .with_format_from_filename()
+        .expect("Failed to infer format");
+    let files = extractor.extract(&snappy(HELLO)).unwrap();
+    assert_eq!(files[0].path, "hello.txt");
+}
+
+#[test]
+fn test_single_file_size_limit() {
+    let data = vec![0u8; 1024];
+    let extractor = ArchiveExtractor::new().with_max_file_size(100);
+
+    let result = extractor.extract_with_format(&brotli(&data), ArchiveFormat::Br);
+    assert!(matches!(result, Err(ArchiveError::FileTooLarge { .. })));
+
+    let result = extractor.extract_with_format(&snappy(&data), ArchiveFormat::Sz);
+    assert!(matches!(result, Err(ArchiveError::FileTooLarge { .. })));
+}
+
+#[test]
+fn test_invalid_data() {
+    let extractor = ArchiveExtractor::new();
+
+    assert!(
+        extractor
+            .extract_with_format(b"not snappy", ArchiveFormat::Sz)
+            .is_err()
+    );
+    assert!(
+        extractor
+            .extract_with_format(&[0xFF; 16], ArchiveFormat::Br)
+            .is_err()
+    );
+}
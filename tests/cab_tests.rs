@@ -0,0 +1,132 @@
+//! Tests for Microsoft Cabinet (CAB) extraction
+
+mod common;
+
+use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat, DuplicatePolicy};
+use common::assert_contains_file;
+use std::io::{Cursor, Write};
+
+/// Builds a cabinet with one uncompressed folder and one MSZIP folder.
+fn build_test_cabinet() -> Vec<u8> {
+    let mut builder = cab::CabinetBuilder::new();
+    builder
+        .add_folder(cab::CompressionType::None)
+        .add_file("hello.txt");
+    {
+        let folder = builder.add_folder(cab::CompressionType::MsZip);
+        folder.add_file("docs\\readme.txt");
+        folder.add_file("docs\\nested\\deep.txt");
+    }
+
+    let mut writer = builder.build(Cursor::new(Vec::new())).unwrap();
+    while let Some(mut file) = writer.next_file().unwrap() {
+        let contents: &[u8] = match file.file_name() {
+            "hello.txt" => b"Hello, World!\n",
+            "docs\\readme.txt" => b"Read me first\n",
+            _ => b"Deep nested content\n",
+        };
+        file.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_cab_multi_folder() {
+    let data = build_test_cabinet();
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Cab)
+        .expect("Failed to extract cabinet");
+
+    assert_eq!(files.len(), 3);
+    let hello = assert_contains_file(&files, "hello.txt");
+    assert_eq!(hello.data, b"Hello, World!\n");
+    let readme = assert_contains_file(&files, "readme.txt");
+    assert_eq!(readme.data, b"Read me first\n");
+}
+
+#[test]
+fn test_cab_paths_use_forward_slashes() {
+    let data = build_test_cabinet();
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Cab)
+        .expect("Failed to extract cabinet");
+
+    let deep = assert_contains_file(&files, "docs/nested/deep.txt");
+    assert_eq!(deep.data, b"Deep nested content\n");
+    assert!(files.iter().all(|f| !f.path.contains('\\')));
+}
+
+#[test]
+fn test_cab_size_limits() {
+    let data = build_test_cabinet();
+
+    let result = ArchiveExtractor::new()
+        .with_max_file_size(10)
+        .extract_with_format(&data, ArchiveFormat::Cab);
+    assert!(matches!(result, Err(ArchiveError::FileTooLarge { .. })));
+
+    let result = ArchiveExtractor::new()
+        .with_max_total_size(20)
+        .extract_with_format(&data, ArchiveFormat::Cab);
+    assert!(matches!(
+        result,
+        Err(ArchiveError::TotalSizeTooLarge { .. })
+    ));
+}
+
+#[test]
+fn test_cab_invalid_data() {
+    let extractor = ArchiveExtractor::new();
+
+    let result = extractor.extract_with_format(b"not a cabinet", ArchiveFormat::Cab);
+    assert!(matches!(result, Err(ArchiveError::InvalidArchive(_))));
+}
+
+#[test]
+fn test_cab_repeated_names() {
+    let mut builder = cab::CabinetBuilder::new();
+    {
+        let folder = builder.add_folder(cab::CompressionType::MsZip);
+        folder.add_file("a.txt");
+        folder.add_file("a.txt");
+    }
+    let mut writer = builder.build(Cursor::new(Vec::new())).unwrap();
+    for contents in [b"first", b"later"] {
+        writer
+            .next_file()
+            .unwrap()
+            .unwrap()
+            .write_all(contents)
+            .unwrap();
+    }
+    let data = writer.finish().unwrap().into_inner();
+
+    // Only the first file can be read, so the second must not be handed out
+    // with its contents.
+    for policy in [
+        DuplicatePolicy::KeepAll,
+        DuplicatePolicy::Error,
+        DuplicatePolicy::KeepLast,
+    ] {
+        let result = ArchiveExtractor::new()
+            .with_duplicate_policy(policy)
+            .extract_with_format(&data, ArchiveFormat::Cab);
+        assert!(
+            matches!(result, Err(ArchiveError::DuplicateEntry { ref path, .. }) if path == "a.txt"),
+            "{:?}: {:?}",
+            policy,
+            result
+        );
+    }
+
+    let files = ArchiveExtractor::new()
+        .with_duplicate_policy(DuplicatePolicy::KeepFirst)
+        .extract_with_format(&data, ArchiveFormat::Cab)
+        .unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].data, b"first");
+}
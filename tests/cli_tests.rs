@@ -0,0 +1,246 @@
+//! Tests for the `archive` command-line tool.
+#![cfg(feature = "cli")]
+
+mod common;
+
+use archive::{ArchiveExtractor, ArchiveFormat, CompressionLevel, ExtractedFile};
+use common::{LARGE_SIZE, README, sample_entries, write_with_level};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A fresh directory for one test.
+fn scratch(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("cli_tests")
+        .join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_archive(dir: &Path, name: &str, entries: &[ExtractedFile]) -> PathBuf {
+    write_archive_with_level(dir, name, entries, CompressionLevel::Default)
+}
+
+fn write_archive_with_level(
+    dir: &Path,
+    name: &str,
+    entries: &[ExtractedFile],
+    level: CompressionLevel,
+) -> PathBuf {
+    let format = ArchiveFormat::from_filename(name).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, write_with_level(entries, format, level)).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_archive"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn json(output: &Output) -> serde_json::Value {
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_list() {
+    let dir = scratch("list");
+    let archive = write_archive(&dir, "sample.tar.gz", &sample_entries());
+    let archive = archive.to_str().unwrap();
+
+    let output = run(&["list", archive]);
+    assert!(output.status.success());
+    let lines: Vec<String> = stdout(&output).lines().map(str::to_string).collect();
+    assert_eq!(lines.len(), 6);
+    assert!(lines[0].starts_with('d') && lines[0].ends_with("docs/"));
+    assert!(lines[1].ends_with("12  docs/readme.txt"));
+    assert!(lines[5].starts_with('l') && lines[5].ends_with("readme.txt -> docs/readme.txt"));
+
+    let output = run(&["list", "--json", archive]);
+    let entries = json(&output);
+    assert_eq!(entries.as_array().unwrap().len(), 6);
+    assert_eq!(entries[2]["path"], "docs/large.bin");
+    assert_eq!(entries[2]["type"], "file");
+    assert_eq!(entries[2]["size"], LARGE_SIZE);
+    assert_eq!(entries[5]["type"], "symlink");
+    assert_eq!(entries[5]["link_target"], "docs/readme.txt");
+}
+
+#[test]
+fn test_extract() {
+    let dir = scratch("extract");
+    let archive = write_archive(&dir, "sample.zip", &sample_entries());
+    let out = dir.join("out");
+
+    let output = run(&[
+        "extract",
+        archive.to_str().unwrap(),
+        "-C",
+        out.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(fs::read(out.join("docs/readme.txt")).unwrap(), README);
+    assert_eq!(
+        fs::read(out.join("docs/large.bin")).unwrap().len(),
+        LARGE_SIZE
+    );
+    #[cfg(unix)]
+    assert_eq!(
+        fs::read_link(out.join("readme.txt")).unwrap(),
+        Path::new("docs/readme.txt")
+    );
+}
+
+#[test]
+fn test_extract_refuses_paths_outside_directory() {
+    let dir = scratch("traversal");
+    let entries = [
+        ExtractedFile::file("safe.txt", b"safe".to_vec()),
+        ExtractedFile::file("../escaped.txt", b"evil".to_vec()),
+    ];
+    let archive = write_archive(&dir, "evil.zip", &entries);
+    let out = dir.join("out");
+
+    let output = run(&[
+        "extract",
+        archive.to_str().unwrap(),
+        "-C",
+        out.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("outside the target directory"));
+    assert!(!dir.join("escaped.txt").exists());
+    // Nothing is written once any path is refused.
+    assert!(!out.join("safe.txt").exists());
+}
+
+#[test]
+fn test_test() {
+    let dir = scratch("test");
+    let archive = write_archive_with_level(
+        &dir,
+        "sample.zip",
+        &sample_entries(),
+        CompressionLevel::Store,
+    );
+
+    let output = run(&["test", archive.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("docs/readme.txt: OK"));
+
+    let mut data = fs::read(&archive).unwrap();
+    let position = data
+        .windows(README.len())
+        .position(|window| window == README)
+        .unwrap();
+    data[position] = b'J';
+    fs::write(&archive, data).unwrap();
+
+    let output = run(&["test", "--json", archive.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let report = json(&output);
+    assert_eq!(report["ok"], false);
+    let failed = &report["entries"][1];
+    assert_eq!(failed["path"], "docs/readme.txt");
+    assert!(
+        failed["error"]
+            .as_str()
+            .unwrap()
+            .contains("Checksum mismatch")
+    );
+}
+
+#[test]
+fn test_detect_and_format_override() {
+    let dir = scratch("detect");
+    let archive = write_archive(&dir, "sample.tar.xz", &sample_entries());
+
+    let output = run(&["detect", archive.to_str().unwrap()]);
+    assert_eq!(stdout(&output).trim(), "TAR.XZ");
+    let output = run(&["detect", "--json", archive.to_str().unwrap()]);
+    assert_eq!(json(&output)["format"], "TAR.XZ");
+
+    let renamed = dir.join("download");
+    fs::rename(&archive, &renamed).unwrap();
+    let renamed = renamed.to_str().unwrap();
+    // Without content detection the format can only come from the name.
+    let output = run(&["list", renamed]);
+    #[cfg(not(any(feature = "detect-infer", feature = "detect-libmagic")))]
+    {
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("--format"));
+    }
+    #[cfg(any(feature = "detect-infer", feature = "detect-libmagic"))]
+    assert!(output.status.success());
+
+    let output = run(&["--format", "tar.xz", "list", renamed]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output).lines().count(), 6);
+}
+
+#[test]
+fn test_size_limits() {
+    let dir = scratch("limits");
+    let archive = write_archive(&dir, "sample.tar", &sample_entries());
+    let archive = archive.to_str().unwrap();
+    let out = dir.join("out");
+    let out = out.to_str().unwrap();
+
+    let output = run(&["extract", "--max-file-size", "1K", archive, "-C", out]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("File too large"));
+
+    let output = run(&["extract", "--max-total-size", "4K", archive, "-C", out]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Total extraction size"));
+
+    let output = run(&["extract", "--max-file-size", "2M", archive, "-C", out]);
+    assert!(output.status.success());
+
+    let output = run(&["list", "--max-file-size", "lots", archive]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_convert() {
+    let dir = scratch("convert");
+    let archive = write_archive(&dir, "sample.tar", &sample_entries());
+    let converted = dir.join("sample.cab");
+
+    let output = run(&[
+        "convert",
+        "--json",
+        archive.to_str().unwrap(),
+        converted.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let result = json(&output);
+    assert_eq!(result["format"], "CAB");
+    let losses = result["losses"].as_array().unwrap();
+    assert!(losses.iter().any(|loss| loss["type"] == "symlink"));
+
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&fs::read(&converted).unwrap(), ArchiveFormat::Cab)
+        .unwrap();
+    assert_eq!(files.len(), 4);
+
+    let output = dir.join("sample.out");
+    let output = run(&[
+        "convert",
+        "--to",
+        "zip",
+        "--level",
+        "store",
+        archive.to_str().unwrap(),
+        output.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+}
@@ -1,12 +1,14 @@
 //! Common test utilities and helpers
 
-use archive::ExtractedFile;
+use archive::{ArchiveExtractor, ArchiveFormat, ArchiveWriter, CompressionLevel, ExtractedFile};
 use std::fs;
 use std::path::Path;
 
+#[allow(dead_code)]
 pub const TEST_ARCHIVES_DIR: &str = "test-archives";
 
 /// Helper to read a test archive file
+#[allow(dead_code)]
 pub fn read_test_archive(filename: &str) -> Vec<u8> {
     let path = Path::new(TEST_ARCHIVES_DIR).join(filename);
     fs::read(&path).unwrap_or_else(|e| {
@@ -33,3 +35,73 @@ pub fn assert_contains_file<'a>(
             )
         })
 }
+
+/// Contents of `docs/readme.txt` in [`sample_entries`].
+#[allow(dead_code)]
+pub const README: &[u8] = b"Hello, world";
+
+/// Size of `docs/large.bin` in [`sample_entries`], enough to span several
+/// read chunks.
+#[allow(dead_code)]
+pub const LARGE_SIZE: usize = 3 * 512 * 1024;
+
+/// Multi-entry formats that `ArchiveWriter` creates and `ArchiveExtractor`
+/// reads back, for round-trip tests.
+#[allow(dead_code)]
+pub const MULTI_ENTRY_FORMATS: &[ArchiveFormat] = &[
+    ArchiveFormat::Zip,
+    ArchiveFormat::Tar,
+    ArchiveFormat::TarGz,
+    ArchiveFormat::TarXz,
+    ArchiveFormat::Ar,
+    ArchiveFormat::SevenZ,
+    ArchiveFormat::Cab,
+    #[cfg(feature = "rar-write")]
+    ArchiveFormat::Rar,
+];
+
+/// A directory, a short file, a large file, an empty file, a file after
+/// those and a symlink, in that order.
+#[allow(dead_code)]
+pub fn sample_entries() -> Vec<ExtractedFile> {
+    vec![
+        ExtractedFile::directory("docs/"),
+        ExtractedFile::file("docs/readme.txt", README),
+        ExtractedFile::file(
+            "docs/large.bin",
+            (0..LARGE_SIZE)
+                .map(|i| (i % 253) as u8)
+                .collect::<Vec<u8>>(),
+        ),
+        ExtractedFile::file("docs/empty.txt", Vec::new()),
+        ExtractedFile::file("docs/last.txt", b"last".to_vec()),
+        ExtractedFile::symlink("readme.txt", "docs/readme.txt"),
+    ]
+}
+
+/// Writes `entries` as `format` with the default compression level.
+#[allow(dead_code)]
+pub fn write(entries: &[ExtractedFile], format: ArchiveFormat) -> Vec<u8> {
+    write_with_level(entries, format, CompressionLevel::Default)
+}
+
+/// Writes `entries` as `format` with compression `level`.
+#[allow(dead_code)]
+pub fn write_with_level(
+    entries: &[ExtractedFile],
+    format: ArchiveFormat,
+    level: CompressionLevel,
+) -> Vec<u8> {
+    ArchiveWriter::new()
+        .with_compression_level(level)
+        .write_with_format(entries, format)
+        .unwrap_or_else(|e| panic!("failed to write {:?}: {}", format, e))
+}
+
+/// Writes `entries` as `format` and extracts them again.
+#[allow(dead_code)]
+pub fn round_trip(entries: &[ExtractedFile], format: ArchiveFormat) -> Vec<ExtractedFile> {
+    ArchiveExtractor::new()
+        .extract_with_format(&write(entries, format), format)
+        .unwrap_or_else(|e| panic!("failed to read back {:?}: {}", format, e))
+}
@@ -0,0 +1,261 @@
+//! Tests for converting archives between formats.
+
+mod common;
+
+use archive::{
+    ArchiveConverter, ArchiveError, ArchiveExtractor, ArchiveFormat, ArchiveWriter, ConversionLoss,
+    DuplicatePolicy, EntryMetadata, ExtractedFile, MetadataField,
+};
+use common::{assert_contains_file, write};
+
+const HELLO: &[u8] = b"Hello, World!\n";
+/// An even number of seconds, so that it survives ZIP and CAB timestamps.
+const MTIME: u64 = 1_700_000_000;
+
+fn full_metadata() -> EntryMetadata {
+    EntryMetadata {
+        mtime: Some(MTIME),
+        mode: Some(0o640),
+        uid: Some(1000),
+        gid: Some(100),
+        owner: Some("alice".to_string()),
+        group: Some("users".to_string()),
+        link_target: None,
+        ..EntryMetadata::default()
+    }
+}
+
+fn sample_entries() -> Vec<ExtractedFile> {
+    vec![
+        ExtractedFile::directory("test-data/"),
+        ExtractedFile::file("test-data/hello.txt", HELLO).with_metadata(full_metadata()),
+        ExtractedFile::symlink("test-data/link.txt", "hello.txt"),
+    ]
+}
+
+fn read(data: &[u8], format: ArchiveFormat) -> Vec<ExtractedFile> {
+    ArchiveExtractor::new()
+        .extract_with_format(data, format)
+        .unwrap_or_else(|e| panic!("failed to read {:?}: {}", format, e))
+}
+
+fn find<'a>(files: &'a [ExtractedFile], path: &str) -> &'a ExtractedFile {
+    files
+        .iter()
+        .find(|f| f.path.trim_end_matches('/') == path)
+        .unwrap_or_else(|| panic!("{} not found", path))
+}
+
+#[test]
+fn test_zip_to_tar_zst() {
+    let zip = write(&sample_entries(), ArchiveFormat::Zip);
+    let conversion = archive::convert(&zip, ArchiveFormat::Zip, ArchiveFormat::TarZst).unwrap();
+    assert!(conversion.losses.is_empty(), "{:?}", conversion.losses);
+
+    let files = read(&conversion.data, ArchiveFormat::TarZst);
+    assert!(find(&files, "test-data").is_directory);
+    let hello = assert_contains_file(&files, "hello.txt");
+    assert_eq!(hello.data, HELLO);
+    assert_eq!(hello.metadata.mtime, Some(MTIME));
+    assert_eq!(hello.metadata.mode, Some(0o640));
+    let link = find(&files, "test-data/link.txt");
+    assert_eq!(link.metadata.link_target.as_deref(), Some("hello.txt"));
+}
+
+#[test]
+fn test_sevenz_to_zip() {
+    let sevenz = write(&sample_entries(), ArchiveFormat::SevenZ);
+    let conversion = archive::convert(&sevenz, ArchiveFormat::SevenZ, ArchiveFormat::Zip).unwrap();
+    assert!(conversion.losses.is_empty(), "{:?}", conversion.losses);
+
+    let files = read(&conversion.data, ArchiveFormat::Zip);
+    let hello = assert_contains_file(&files, "hello.txt");
+    assert_eq!(hello.data, HELLO);
+    assert_eq!(hello.metadata.mtime, Some(MTIME));
+    assert_eq!(hello.metadata.mode, Some(0o640));
+    let link = find(&files, "test-data/link.txt");
+    assert_eq!(link.metadata.link_target.as_deref(), Some("hello.txt"));
+}
+
+#[test]
+fn test_tar_to_zip_reports_ownership_loss() {
+    let tar = write(&sample_entries(), ArchiveFormat::Tar);
+    let conversion = archive::convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Zip).unwrap();
+
+    assert!(conversion.losses.contains(&ConversionLoss::Metadata {
+        path: "test-data/hello.txt".to_string(),
+        fields: vec![
+            MetadataField::Uid,
+            MetadataField::Gid,
+            MetadataField::Owner,
+            MetadataField::Group,
+        ],
+    }));
+    assert!(!conversion.losses.iter().any(|loss| matches!(
+        loss,
+        ConversionLoss::Directory { .. } | ConversionLoss::Symlink { .. }
+    )));
+}
+
+#[test]
+fn test_tar_to_cab_drops_directories_and_symlinks() {
+    let tar = write(&sample_entries(), ArchiveFormat::Tar);
+    let conversion = archive::convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Cab).unwrap();
+
+    assert!(conversion.losses.contains(&ConversionLoss::Directory {
+        path: "test-data/".to_string(),
+    }));
+    assert!(conversion.losses.contains(&ConversionLoss::Symlink {
+        path: "test-data/link.txt".to_string(),
+        target: "hello.txt".to_string(),
+    }));
+    assert!(conversion.losses.iter().any(|loss| matches!(
+        loss,
+        ConversionLoss::Metadata { path, fields }
+            if path == "test-data/hello.txt" && fields.contains(&MetadataField::Mode)
+    )));
+
+    let files = read(&conversion.data, ArchiveFormat::Cab);
+    assert_eq!(files.len(), 1);
+    let hello = assert_contains_file(&files, "hello.txt");
+    assert_eq!(hello.data, HELLO);
+    assert_eq!(hello.metadata.mtime, Some(MTIME));
+}
+
+#[test]
+fn test_convert_to_single_file() {
+    let tar = write(&sample_entries(), ArchiveFormat::Tar);
+    let conversion = archive::convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Xz).unwrap();
+    assert_eq!(read(&conversion.data, ArchiveFormat::Xz)[0].data, HELLO);
+    assert!(conversion.losses.iter().any(|loss| matches!(
+        loss,
+        ConversionLoss::Metadata { fields, .. } if fields.contains(&MetadataField::Mtime)
+    )));
+
+    let mut entries = sample_entries();
+    entries.push(ExtractedFile::file(
+        "test-data/other.txt",
+        b"other".to_vec(),
+    ));
+    let tar = write(&entries, ArchiveFormat::Tar);
+    let result = archive::convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Xz);
+    assert!(matches!(result, Err(ArchiveError::InvalidInput(_))));
+}
+
+#[test]
+fn test_convert_respects_size_limits() {
+    let entries = vec![ExtractedFile::file("big.bin", vec![0u8; 4096])];
+    let zip = write(&entries, ArchiveFormat::Zip);
+
+    let converter =
+        ArchiveConverter::new().with_extractor(ArchiveExtractor::new().with_max_file_size(1024));
+    let result = converter.convert(&zip, ArchiveFormat::Zip, ArchiveFormat::Tar);
+    assert!(matches!(result, Err(ArchiveError::FileTooLarge { .. })));
+
+    let converter =
+        ArchiveConverter::new().with_extractor(ArchiveExtractor::new().with_max_total_size(1024));
+    let result = converter.convert(&zip, ArchiveFormat::Zip, ArchiveFormat::Tar);
+    assert!(matches!(
+        result,
+        Err(ArchiveError::FileTooLarge { .. } | ArchiveError::TotalSizeTooLarge { .. })
+    ));
+}
+
+#[test]
+fn test_convert_uses_writer_settings() {
+    let tar = write(&sample_entries(), ArchiveFormat::Tar);
+    let converter = ArchiveConverter::new().with_writer(ArchiveWriter::new().with_deterministic(0));
+
+    let first = converter
+        .convert(&tar, ArchiveFormat::Tar, ArchiveFormat::TarGz)
+        .unwrap();
+    let second = converter
+        .convert(&tar, ArchiveFormat::Tar, ArchiveFormat::TarGz)
+        .unwrap();
+    assert_eq!(first.data, second.data);
+
+    let files = read(&first.data, ArchiveFormat::TarGz);
+    assert_eq!(find(&files, "test-data/hello.txt").metadata.mtime, Some(0));
+}
+
+#[test]
+fn test_streamed_conversion_matches_writer() {
+    let zip = write(&sample_entries(), ArchiveFormat::Zip);
+    let entries = read(&zip, ArchiveFormat::Zip);
+    for format in [
+        ArchiveFormat::Zip,
+        ArchiveFormat::Tar,
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarBz2,
+        ArchiveFormat::TarXz,
+        ArchiveFormat::TarZst,
+        ArchiveFormat::TarLz4,
+        ArchiveFormat::TarZ,
+        ArchiveFormat::TarLzma,
+        ArchiveFormat::TarLzip,
+        ArchiveFormat::TarBr,
+        ArchiveFormat::TarSz,
+    ] {
+        let conversion = archive::convert(&zip, ArchiveFormat::Zip, format).unwrap();
+        let files = read(&conversion.data, format);
+        assert_eq!(assert_contains_file(&files, "hello.txt").data, HELLO);
+        // The entries were written one at a time, with the same result.
+        assert_eq!(conversion.data, write(&entries, format), "{:?}", format);
+    }
+}
+
+#[test]
+fn test_convert_reports_rounded_mtime() {
+    let entries = vec![
+        ExtractedFile::file("odd.txt", HELLO).with_metadata(EntryMetadata {
+            mtime: Some(MTIME + 1),
+            ..EntryMetadata::default()
+        }),
+        ExtractedFile::file("old.txt", HELLO).with_metadata(EntryMetadata {
+            mtime: Some(0),
+            ..EntryMetadata::default()
+        }),
+    ];
+    let tar = write(&entries, ArchiveFormat::Tar);
+    let conversion = archive::convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Zip).unwrap();
+
+    assert!(conversion.losses.contains(&ConversionLoss::Mtime {
+        path: "odd.txt".to_string(),
+        original: MTIME + 1,
+        stored: MTIME,
+    }));
+    // 1980-01-01, the earliest time ZIP can store.
+    assert!(conversion.losses.contains(&ConversionLoss::Mtime {
+        path: "old.txt".to_string(),
+        original: 0,
+        stored: 315_532_800,
+    }));
+    let files = read(&conversion.data, ArchiveFormat::Zip);
+    assert_eq!(find(&files, "odd.txt").metadata.mtime, Some(MTIME));
+    assert_eq!(find(&files, "old.txt").metadata.mtime, Some(315_532_800));
+}
+
+#[test]
+fn test_convert_to_gz_reports_base_name() {
+    let entries = vec![ExtractedFile::file("docs/readme.txt", HELLO)];
+    let tar = write(&entries, ArchiveFormat::Tar);
+    let conversion = archive::convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Gz).unwrap();
+
+    assert!(conversion.losses.contains(&ConversionLoss::Path {
+        path: "docs/readme.txt".to_string(),
+        stored: "readme.txt".to_string(),
+    }));
+}
+
+#[test]
+fn test_convert_fails_on_duplicates_under_keep_last() {
+    let entries = vec![
+        ExtractedFile::file("same.txt", b"first".to_vec()),
+        ExtractedFile::file("same.txt", b"second".to_vec()),
+    ];
+    let tar = write(&entries, ArchiveFormat::Tar);
+    let converter = ArchiveConverter::new()
+        .with_extractor(ArchiveExtractor::new().with_duplicate_policy(DuplicatePolicy::KeepLast));
+    let result = converter.convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Zip);
+    assert!(matches!(result, Err(ArchiveError::DuplicateEntry { .. })));
+}
@@ -0,0 +1,301 @@
+//! Tests for duplicate and colliding entry paths.
+
+mod common;
+
+use archive::{
+    ArchiveError, ArchiveExtractor, ArchiveFormat, CompressionLevel, DuplicatePolicy,
+    ExtractedFile, HashAlgorithm, ZipAnomaly,
+};
+
+fn write(entries: &[ExtractedFile], format: ArchiveFormat) -> Vec<u8> {
+    common::write_with_level(entries, format, CompressionLevel::Store)
+}
+
+fn repeated_tar() -> Vec<u8> {
+    write(
+        &[
+            ExtractedFile::file("a.txt", b"first".to_vec()),
+            ExtractedFile::file("b.txt", b"other".to_vec()),
+            ExtractedFile::file("./a.txt", b"second".to_vec()),
+        ],
+        ArchiveFormat::Tar,
+    )
+}
+
+fn extract(
+    extractor: ArchiveExtractor,
+    data: &[u8],
+    format: ArchiveFormat,
+) -> Vec<(String, Vec<u8>)> {
+    extractor
+        .extract_with_format(data, format)
+        .unwrap()
+        .into_iter()
+        .map(|file| (file.path, file.data))
+        .collect()
+}
+
+fn assert_duplicate(result: archive::Result<Vec<ExtractedFile>>, path: &str, existing: &str) {
+    match result {
+        Err(ArchiveError::DuplicateEntry {
+            path: p,
+            existing: e,
+        }) => assert_eq!((p.as_str(), e.as_str()), (path, existing)),
+        other => panic!("expected DuplicateEntry, got {:?}", other.map(|f| f.len())),
+    }
+}
+
+#[test]
+fn test_keep_all_by_default() {
+    // Paths are normalized, so "./a.txt" repeats "a.txt".
+    let files = extract(ArchiveExtractor::new(), &repeated_tar(), ArchiveFormat::Tar);
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[2].0, "a.txt");
+}
+
+#[test]
+fn test_error_policy() {
+    let result = ArchiveExtractor::new()
+        .with_duplicate_policy(DuplicatePolicy::Error)
+        .extract_with_format(&repeated_tar(), ArchiveFormat::Tar);
+    assert_duplicate(result, "a.txt", "a.txt");
+}
+
+#[test]
+fn test_keep_first_skips_later_copies_unread() {
+    // The second copy would exceed the total size limit if it were read.
+    let data = write(
+        &[
+            ExtractedFile::file("a.txt", vec![b'a'; 600]),
+            ExtractedFile::file("a.txt", vec![b'b'; 600]),
+        ],
+        ArchiveFormat::Tar,
+    );
+    let files = extract(
+        ArchiveExtractor::new()
+            .with_max_total_size(1000)
+            .with_duplicate_policy(DuplicatePolicy::KeepFirst),
+        &data,
+        ArchiveFormat::Tar,
+    );
+    assert_eq!(files, [("a.txt".to_string(), vec![b'a'; 600])]);
+}
+
+#[test]
+fn test_keep_last() {
+    let files = extract(
+        ArchiveExtractor::new().with_duplicate_policy(DuplicatePolicy::KeepLast),
+        &repeated_tar(),
+        ArchiveFormat::Tar,
+    );
+    assert_eq!(
+        files,
+        [
+            ("b.txt".to_string(), b"other".to_vec()),
+            ("a.txt".to_string(), b"second".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn test_keep_last_borrowed_hashed_and_single_entry() {
+    let data = repeated_tar();
+    let extractor = ArchiveExtractor::new().with_duplicate_policy(DuplicatePolicy::KeepLast);
+
+    let borrowed = extractor
+        .extract_borrowed_with_format(&data, ArchiveFormat::Tar)
+        .unwrap();
+    assert_eq!(borrowed.len(), 2);
+    assert!(borrowed.iter().all(|file| file.is_borrowed()));
+    assert_eq!(&*borrowed[0].data, b"other");
+    assert_eq!(&*borrowed[1].data, b"second");
+
+    let hashed = extractor
+        .clone()
+        .with_hash(HashAlgorithm::Md5)
+        .hash_with_format(&data, ArchiveFormat::Tar)
+        .unwrap();
+    assert_eq!(hashed.len(), 2);
+
+    let entry = extractor
+        .extract_entry_with_format(&data, ArchiveFormat::Tar, "a.txt")
+        .unwrap();
+    assert_eq!(entry.data, b"second");
+}
+
+#[test]
+fn test_case_folding() {
+    let data = write(
+        &[
+            ExtractedFile::file("docs/README", b"upper".to_vec()),
+            ExtractedFile::file("docs/readme", b"lower".to_vec()),
+        ],
+        ArchiveFormat::Tar,
+    );
+    let strict = ArchiveExtractor::new().with_duplicate_policy(DuplicatePolicy::Error);
+
+    assert_eq!(
+        strict
+            .clone()
+            .extract_with_format(&data, ArchiveFormat::Tar)
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_duplicate(
+        strict
+            .with_case_folding(true)
+            .extract_with_format(&data, ArchiveFormat::Tar),
+        "docs/readme",
+        "docs/README",
+    );
+}
+
+#[test]
+fn test_unicode_normalization() {
+    let composed = "caf\u{e9}.txt";
+    let decomposed = "cafe\u{301}.txt";
+    let data = write(
+        &[
+            ExtractedFile::file(composed, b"nfc".to_vec()),
+            ExtractedFile::file(decomposed, b"nfd".to_vec()),
+        ],
+        ArchiveFormat::Tar,
+    );
+    let strict = ArchiveExtractor::new().with_duplicate_policy(DuplicatePolicy::Error);
+
+    assert!(
+        strict
+            .clone()
+            .with_case_folding(true)
+            .extract_with_format(&data, ArchiveFormat::Tar)
+            .is_ok()
+    );
+    assert_duplicate(
+        strict
+            .with_unicode_normalization(true)
+            .extract_with_format(&data, ArchiveFormat::Tar),
+        decomposed,
+        composed,
+    );
+}
+
+#[test]
+fn test_directories() {
+    let strict = ArchiveExtractor::new().with_duplicate_policy(DuplicatePolicy::Error);
+
+    // Repeated directory entries are harmless.
+    let data = write(
+        &[
+            ExtractedFile::directory("docs/"),
+            ExtractedFile::directory("docs"),
+        ],
+        ArchiveFormat::Tar,
+    );
+    assert_eq!(
+        strict
+            .clone()
+            .extract_with_format(&data, ArchiveFormat::Tar)
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // A file replacing a directory is not.
+    let data = write(
+        &[
+            ExtractedFile::directory("docs/"),
+            ExtractedFile::file("docs", b"file".to_vec()),
+        ],
+        ArchiveFormat::Tar,
+    );
+    assert_duplicate(
+        strict.extract_with_format(&data, ArchiveFormat::Tar),
+        "docs",
+        "docs/",
+    );
+}
+
+#[test]
+fn test_filtered_entries_do_not_collide() {
+    let files = extract(
+        ArchiveExtractor::new()
+            .with_duplicate_policy(DuplicatePolicy::Error)
+            .with_filter(|header| header.size != Some(6)),
+        &repeated_tar(),
+        ArchiveFormat::Tar,
+    );
+    assert_eq!(files.len(), 2);
+}
+
+#[test]
+fn test_seven_zip_and_zip_collisions() {
+    let entries = [
+        ExtractedFile::file("Makefile", b"upper".to_vec()),
+        ExtractedFile::file("makefile", b"lower".to_vec()),
+    ];
+    for format in [ArchiveFormat::SevenZ, ArchiveFormat::Zip] {
+        let data = write(&entries, format);
+        let extractor = ArchiveExtractor::new().with_case_folding(true);
+
+        let files = extract(
+            extractor
+                .clone()
+                .with_duplicate_policy(DuplicatePolicy::KeepFirst),
+            &data,
+            format,
+        );
+        assert_eq!(files, [("Makefile".to_string(), b"upper".to_vec())]);
+
+        assert_duplicate(
+            extractor
+                .with_duplicate_policy(DuplicatePolicy::Error)
+                .extract_with_format(&data, format),
+            "makefile",
+            "Makefile",
+        );
+    }
+}
+
+#[test]
+fn test_zip_repeated_central_records() {
+    // The ZIP writer refuses duplicate names, so rename b.txt to a.txt in
+    // both of its headers.
+    let mut zip = write(
+        &[
+            ExtractedFile::file("a.txt", b"first".to_vec()),
+            ExtractedFile::file("b.txt", b"other".to_vec()),
+        ],
+        ArchiveFormat::Zip,
+    );
+    for i in 0..zip.len() - 5 {
+        if &zip[i..i + 5] == b"b.txt" {
+            zip[i] = b'a';
+        }
+    }
+
+    assert_eq!(
+        ArchiveExtractor::new().validate_zip(&zip).unwrap(),
+        [ZipAnomaly::DuplicateName {
+            path: "a.txt".into(),
+            count: 2,
+        }]
+    );
+    for policy in [DuplicatePolicy::Error, DuplicatePolicy::KeepFirst] {
+        assert_duplicate(
+            ArchiveExtractor::new()
+                .with_duplicate_policy(policy)
+                .extract_with_format(&zip, ArchiveFormat::Zip),
+            "a.txt",
+            "a.txt",
+        );
+    }
+
+    // The ZIP reader only exposes the last record.
+    let files = extract(
+        ArchiveExtractor::new().with_duplicate_policy(DuplicatePolicy::KeepLast),
+        &zip,
+        ArchiveFormat::Zip,
+    );
+    assert_eq!(files, [("a.txt".to_string(), b"other".to_vec())]);
+}
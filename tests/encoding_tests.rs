@@ -0,0 +1,175 @@
+//! Tests for entry names that are not UTF-8.
+
+use archive::{ArchiveExtractor, ArchiveFormat, NameEncoding};
+
+/// "テスト.txt" in Shift-JIS.
+const SHIFT_JIS_NAME: &[u8] = b"\x83\x65\x83\x58\x83\x67.txt";
+/// "中文.txt" in GBK.
+const GBK_NAME: &[u8] = b"\xd6\xd0\xce\xc4.txt";
+
+const UTF8_FLAG: u16 = 1 << 11;
+
+/// Builds a ZIP archive with a single stored entry whose name is `name`
+/// exactly, with the given flags and extra field in both headers.
+fn zip_with_name(name: &[u8], flags: u16, extra: &[u8]) -> Vec<u8> {
+    let contents = b"hi";
+    let crc = crc32fast::hash(contents);
+    let fields = |zip: &mut Vec<u8>| {
+        zip.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        zip.extend_from_slice(&flags.to_le_bytes());
+        zip.extend_from_slice(&0u16.to_le_bytes()); // stored
+        zip.extend_from_slice(&0u16.to_le_bytes()); // time
+        zip.extend_from_slice(&0x21u16.to_le_bytes()); // date, 1980-01-01
+        zip.extend_from_slice(&crc.to_le_bytes());
+        zip.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+    };
+
+    let mut zip = b"PK\x03\x04".to_vec();
+    fields(&mut zip);
+    zip.extend_from_slice(name);
+    zip.extend_from_slice(extra);
+    zip.extend_from_slice(contents);
+
+    let central_start = zip.len();
+    zip.extend_from_slice(b"PK\x01\x02");
+    zip.extend_from_slice(&20u16.to_le_bytes()); // version made by
+    fields(&mut zip);
+    zip.extend_from_slice(&[0; 10]); // comment length, disk, attributes
+    zip.extend_from_slice(&0u32.to_le_bytes()); // local header offset
+    zip.extend_from_slice(name);
+    zip.extend_from_slice(extra);
+    let central_len = zip.len() - central_start;
+
+    zip.extend_from_slice(b"PK\x05\x06");
+    zip.extend_from_slice(&[0; 4]);
+    zip.extend_from_slice(&1u16.to_le_bytes());
+    zip.extend_from_slice(&1u16.to_le_bytes());
+    zip.extend_from_slice(&(central_len as u32).to_le_bytes());
+    zip.extend_from_slice(&(central_start as u32).to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes());
+    zip
+}
+
+/// An Info-ZIP Unicode Path extra field giving `name` for `stored_name`.
+fn unicode_path_field(stored_name: &[u8], name: &str) -> Vec<u8> {
+    let mut field = 0x7075u16.to_le_bytes().to_vec();
+    field.extend_from_slice(&(5 + name.len() as u16).to_le_bytes());
+    field.push(1);
+    field.extend_from_slice(&crc32fast::hash(stored_name).to_le_bytes());
+    field.extend_from_slice(name.as_bytes());
+    field
+}
+
+fn tar_with_name(name: &[u8]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.as_old_mut().name[..name.len()].copy_from_slice(name);
+    header.set_size(2);
+    header.set_mode(0o644);
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_cksum();
+    builder.append(&header, &b"hi"[..]).unwrap();
+    builder.into_inner().unwrap()
+}
+
+fn paths(extractor: &ArchiveExtractor, data: &[u8], format: ArchiveFormat) -> Vec<String> {
+    extractor
+        .extract_with_format(data, format)
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path)
+        .collect()
+}
+
+#[test]
+fn test_zip_names_default_to_cp437() {
+    let name = b"Cura\x87ao.txt";
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&zip_with_name(name, 0, &[]), ArchiveFormat::Zip)
+        .unwrap();
+    assert_eq!(files[0].path, "Curaçao.txt");
+    assert_eq!(files[0].metadata.raw_name.as_deref(), Some(&name[..]));
+}
+
+#[test]
+fn test_zip_fallback_encoding() {
+    let zip = zip_with_name(SHIFT_JIS_NAME, 0, &[]);
+    assert_ne!(
+        paths(&ArchiveExtractor::new(), &zip, ArchiveFormat::Zip),
+        ["テスト.txt"]
+    );
+
+    let extractor = ArchiveExtractor::new().with_name_encoding(NameEncoding::ShiftJis);
+    assert_eq!(paths(&extractor, &zip, ArchiveFormat::Zip), ["テスト.txt"]);
+
+    let entry = extractor
+        .extract_entry_with_format(&zip, ArchiveFormat::Zip, "テスト.txt")
+        .unwrap();
+    assert_eq!(entry.data, b"hi");
+    assert_eq!(entry.metadata.raw_name.as_deref(), Some(SHIFT_JIS_NAME));
+}
+
+#[test]
+fn test_zip_utf8_names_ignore_fallback() {
+    let name = "日本.txt";
+    let extractor = ArchiveExtractor::new().with_name_encoding(NameEncoding::Gbk);
+
+    for flags in [UTF8_FLAG, 0] {
+        let zip = zip_with_name(name.as_bytes(), flags, &[]);
+        assert_eq!(paths(&extractor, &zip, ArchiveFormat::Zip), [name]);
+    }
+}
+
+#[test]
+fn test_zip_unicode_path_extra_field() {
+    let extra = unicode_path_field(SHIFT_JIS_NAME, "テスト.txt");
+    let zip = zip_with_name(SHIFT_JIS_NAME, 0, &extra);
+
+    // The extra field wins over the fallback encoding.
+    let files = ArchiveExtractor::new()
+        .with_name_encoding(NameEncoding::Gbk)
+        .extract_with_format(&zip, ArchiveFormat::Zip)
+        .unwrap();
+    assert_eq!(files[0].path, "テスト.txt");
+    assert_eq!(files[0].metadata.raw_name.as_deref(), Some(SHIFT_JIS_NAME));
+}
+
+#[test]
+fn test_tar_fallback_encoding() {
+    let tar = tar_with_name(GBK_NAME);
+
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&tar, ArchiveFormat::Tar)
+        .unwrap();
+    assert_eq!(files[0].path, "\u{fffd}\u{fffd}\u{fffd}\u{fffd}.txt");
+    assert_eq!(files[0].metadata.raw_name.as_deref(), Some(GBK_NAME));
+
+    let extractor = ArchiveExtractor::new().with_name_encoding(NameEncoding::Gbk);
+    assert_eq!(paths(&extractor, &tar, ArchiveFormat::Tar), ["中文.txt"]);
+}
+
+#[test]
+fn test_ar_fallback_encoding() {
+    let mut builder = ar::Builder::new(Vec::new());
+    builder
+        .append(&ar::Header::new(SHIFT_JIS_NAME.to_vec(), 2), &b"hi"[..])
+        .unwrap();
+    let ar = builder.into_inner().unwrap();
+
+    let extractor = ArchiveExtractor::new().with_name_encoding(NameEncoding::ShiftJis);
+    let files = extractor
+        .extract_with_format(&ar, ArchiveFormat::Ar)
+        .unwrap();
+    assert_eq!(files[0].path, "テスト.txt");
+    assert_eq!(files[0].metadata.raw_name.as_deref(), Some(SHIFT_JIS_NAME));
+}
+
+#[test]
+fn test_decode() {
+    assert_eq!(NameEncoding::Gbk.decode(GBK_NAME), "中文.txt");
+    assert_eq!(NameEncoding::Cp437.decode(b"\xb0\xff"), "░\u{a0}");
+    assert_eq!(NameEncoding::ShiftJis.decode(b"\x83"), "\u{fffd}");
+}
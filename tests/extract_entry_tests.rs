@@ -0,0 +1,134 @@
+//! Tests for reading a single entry by path with ArchiveExtractor::extract_entry.
+
+mod common;
+
+use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat, ExtractedFile};
+use common::{MULTI_ENTRY_FORMATS, README, sample_entries};
+
+fn write(format: ArchiveFormat) -> Vec<u8> {
+    common::write(&sample_entries(), format)
+}
+
+#[test]
+fn test_extract_entry_every_format() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let data = write(format);
+        let entry = ArchiveExtractor::new()
+            .extract_entry_with_format(&data, format, "docs/readme.txt")
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_eq!(entry.path, "docs/readme.txt", "{:?}", format);
+        assert_eq!(entry.data, README, "{:?}", format);
+
+        let empty = ArchiveExtractor::new()
+            .extract_entry_with_format(&data, format, "docs/empty.txt")
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert!(empty.data.is_empty(), "{:?}", format);
+    }
+}
+
+#[test]
+fn test_extract_entry_not_found() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let data = write(format);
+        let result =
+            ArchiveExtractor::new().extract_entry_with_format(&data, format, "docs/missing.txt");
+        assert!(
+            matches!(&result, Err(ArchiveError::EntryNotFound(path)) if path == "docs/missing.txt"),
+            "{:?}: {:?}",
+            format,
+            result.map(|entry| entry.path)
+        );
+    }
+}
+
+#[test]
+fn test_extract_entry_uses_configured_format() {
+    let data = write(ArchiveFormat::TarGz);
+    let entry = ArchiveExtractor::new()
+        .with_format(ArchiveFormat::TarGz)
+        .extract_entry(&data, "docs/readme.txt")
+        .unwrap();
+    assert_eq!(entry.data, README);
+
+    let result = ArchiveExtractor::new().extract_entry(&data, "docs/readme.txt");
+    assert!(matches!(result, Err(ArchiveError::UnknownFormat)));
+}
+
+#[test]
+fn test_extract_directory_entry() {
+    for format in [
+        ArchiveFormat::Zip,
+        ArchiveFormat::Tar,
+        ArchiveFormat::SevenZ,
+    ] {
+        let data = write(format);
+        for path in ["docs", "docs/", "./docs"] {
+            let entry = ArchiveExtractor::new()
+                .extract_entry_with_format(&data, format, path)
+                .unwrap_or_else(|e| panic!("{:?} {}: {}", format, path, e));
+            assert!(entry.is_directory, "{:?} {}", format, path);
+        }
+    }
+}
+
+#[test]
+fn test_only_the_entry_counts_toward_limits() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let data = write(format);
+        let extractor = ArchiveExtractor::new()
+            .with_max_file_size(1024)
+            .with_max_total_size(1024);
+
+        // The large entry in front is skipped without counting.
+        let entry = extractor
+            .extract_entry_with_format(&data, format, "docs/last.txt")
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_eq!(entry.data, b"last", "{:?}", format);
+
+        let result = extractor.extract_entry_with_format(&data, format, "docs/large.bin");
+        assert!(
+            matches!(result, Err(ArchiveError::FileTooLarge { .. })),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_streaming_stops_at_entry() {
+    let entries = [
+        ExtractedFile::file("first.txt", b"first".to_vec()),
+        ExtractedFile::file("second.txt", b"second".to_vec()),
+    ];
+    let mut data = common::write(&entries, ArchiveFormat::Tar);
+    // Corrupt everything after the first entry's header and data block.
+    data.truncate(1024);
+    data.extend_from_slice(&[0xFF; 1024]);
+
+    let extractor = ArchiveExtractor::new();
+    assert!(
+        extractor
+            .extract_with_format(&data, ArchiveFormat::Tar)
+            .is_err()
+    );
+
+    let entry = extractor
+        .extract_entry_with_format(&data, ArchiveFormat::Tar, "first.txt")
+        .unwrap();
+    assert_eq!(entry.data, b"first");
+}
+
+#[test]
+fn test_extract_entry_single_file() {
+    let entries = [ExtractedFile::file("notes.txt", b"notes".to_vec())];
+    let data = common::write(&entries, ArchiveFormat::Gz);
+
+    let extractor = ArchiveExtractor::new();
+    let entry = extractor
+        .extract_entry_with_format(&data, ArchiveFormat::Gz, "notes.txt")
+        .unwrap();
+    assert_eq!(entry.data, b"notes");
+
+    let result = extractor.extract_entry_with_format(&data, ArchiveFormat::Gz, "other.txt");
+    assert!(matches!(result, Err(ArchiveError::EntryNotFound(_))));
+}
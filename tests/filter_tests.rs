@@ -0,0 +1,143 @@
+//! Tests for selecting entries with include/exclude patterns and filters.
+
+mod common;
+
+use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat, ExtractedFile};
+use common::{MULTI_ENTRY_FORMATS, write};
+
+fn project_entries() -> Vec<ExtractedFile> {
+    vec![
+        ExtractedFile::directory("src/"),
+        ExtractedFile::file("src/main.rs", b"fn main() {}\n".to_vec()),
+        ExtractedFile::file("src/main.rs.bak", b"old\n".to_vec()),
+        ExtractedFile::file("src/config/app.json", b"{}\n".to_vec()),
+        ExtractedFile::file("package.json", b"{\"name\": \"demo\"}\n".to_vec()),
+        ExtractedFile::file("assets/big.bin", vec![0u8; 256 * 1024]),
+    ]
+}
+
+fn paths(files: &[ExtractedFile]) -> Vec<&str> {
+    let mut paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    paths.sort_unstable();
+    paths
+}
+
+#[test]
+fn test_include_pattern() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let files = ArchiveExtractor::new()
+            .with_include("**/*.json")
+            .extract_with_format(&write(&project_entries(), format), format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_eq!(
+            paths(&files),
+            ["package.json", "src/config/app.json"],
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_include_and_exclude_patterns() {
+    let extractor = ArchiveExtractor::new()
+        .with_include("src/**")
+        .with_include("package.json")
+        .with_exclude("**/*.bak")
+        .with_exclude("src/config/**");
+
+    for &format in MULTI_ENTRY_FORMATS {
+        let files = extractor
+            .extract_with_format(&write(&project_entries(), format), format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_eq!(
+            paths(&files),
+            ["package.json", "src/main.rs"],
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_filter_sees_header() {
+    let extractor = ArchiveExtractor::new()
+        .with_filter(|header| !header.is_directory && header.size.is_some_and(|size| size < 1024));
+
+    for &format in MULTI_ENTRY_FORMATS {
+        let files = extractor
+            .extract_with_format(&write(&project_entries(), format), format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert_eq!(
+            paths(&files),
+            [
+                "package.json",
+                "src/config/app.json",
+                "src/main.rs",
+                "src/main.rs.bak"
+            ],
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_skipped_entries_do_not_count_toward_limits() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let data = write(&project_entries(), format);
+        let limited = ArchiveExtractor::new()
+            .with_max_file_size(64 * 1024)
+            .with_max_total_size(64 * 1024);
+
+        let result = limited.clone().extract_with_format(&data, format);
+        assert!(
+            matches!(result, Err(ArchiveError::FileTooLarge { .. })),
+            "{:?}",
+            format
+        );
+
+        let files = limited
+            .with_exclude("assets/**")
+            .extract_with_format(&data, format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert!(files.iter().all(|f| !f.path.starts_with("assets")));
+    }
+}
+
+#[test]
+fn test_single_file_filtered_before_decompression() {
+    // Not valid bzip2 data: extracting it fails, but a filtered-out entry is
+    // never decompressed.
+    let garbage = b"BZh9 not really bzip2".to_vec();
+    let extractor = ArchiveExtractor::new().with_source_filename("notes.txt.bz2");
+    assert!(
+        extractor
+            .extract_with_format(&garbage, ArchiveFormat::Bz2)
+            .is_err()
+    );
+
+    let files = extractor
+        .with_exclude("*.txt")
+        .extract_with_format(&garbage, ArchiveFormat::Bz2)
+        .unwrap();
+    assert!(files.is_empty());
+}
+
+#[test]
+fn test_gzip_filtered_by_header_name() {
+    let entries = [ExtractedFile::file("notes.txt", b"notes".to_vec())];
+    let data = write(&entries, ArchiveFormat::Gz);
+
+    let included = ArchiveExtractor::new()
+        .with_include("*.txt")
+        .extract_with_format(&data, ArchiveFormat::Gz)
+        .unwrap();
+    assert_eq!(paths(&included), ["notes.txt"]);
+
+    let excluded = ArchiveExtractor::new()
+        .with_include("*.json")
+        .extract_with_format(&data, ArchiveFormat::Gz)
+        .unwrap();
+    assert!(excluded.is_empty());
+}
@@ -0,0 +1,187 @@
+//! Tests for content hashing during extraction and hash-only extraction.
+
+mod common;
+
+use archive::{
+    ArchiveError, ArchiveExtractor, ArchiveFormat, CompressionLevel, ExtractedFile, HashAlgorithm,
+};
+use common::{LARGE_SIZE, MULTI_ENTRY_FORMATS, README, sample_entries, write, write_with_level};
+use sha2::Digest as _;
+
+const HELLO_DIGESTS: [(HashAlgorithm, &str); 4] = [
+    (
+        HashAlgorithm::Sha256,
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+    ),
+    (
+        HashAlgorithm::Sha1,
+        "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d",
+    ),
+    (
+        HashAlgorithm::Blake3,
+        "ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f",
+    ),
+    (HashAlgorithm::Md5, "5d41402abc4b2a76b9719d911017c592"),
+];
+
+fn sha256_hex(data: &[u8]) -> String {
+    sha2::Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[test]
+fn test_known_digests() {
+    let data = write(
+        &[ExtractedFile::file("hello.txt", b"hello".to_vec())],
+        ArchiveFormat::Tar,
+    );
+    let mut extractor = ArchiveExtractor::new();
+    for (algorithm, _) in HELLO_DIGESTS {
+        extractor = extractor.with_hash(algorithm);
+    }
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Tar)
+        .unwrap();
+
+    assert_eq!(files[0].digests.len(), HELLO_DIGESTS.len());
+    for ((algorithm, hex), digest) in HELLO_DIGESTS.iter().zip(&files[0].digests) {
+        assert_eq!(digest.algorithm, *algorithm);
+        assert_eq!(digest.to_hex(), *hex, "{}", algorithm);
+        assert_eq!(files[0].digest(*algorithm), Some(digest));
+    }
+}
+
+#[test]
+fn test_digests_for_every_format() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let files = ArchiveExtractor::new()
+            .with_hash(HashAlgorithm::Sha256)
+            .extract_with_format(&write(&sample_entries(), format), format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        for file in &files {
+            if file.is_directory {
+                assert!(file.digests.is_empty(), "{:?}", format);
+                continue;
+            }
+            assert_eq!(
+                file.digest(HashAlgorithm::Sha256).unwrap().to_hex(),
+                sha256_hex(&file.data),
+                "{:?} {}",
+                format,
+                file.path
+            );
+        }
+    }
+}
+
+#[test]
+fn test_single_file_digest() {
+    let data = write(
+        &[ExtractedFile::file("hello.txt", b"hello".to_vec())],
+        ArchiveFormat::Xz,
+    );
+    let files = ArchiveExtractor::new()
+        .with_hash(HashAlgorithm::Md5)
+        .extract_with_format(&data, ArchiveFormat::Xz)
+        .unwrap();
+    assert_eq!(
+        files[0].digests[0].to_hex(),
+        "5d41402abc4b2a76b9719d911017c592"
+    );
+}
+
+#[test]
+fn test_no_digests_by_default() {
+    let files = ArchiveExtractor::new()
+        .extract_with_format(
+            &write(&sample_entries(), ArchiveFormat::Zip),
+            ArchiveFormat::Zip,
+        )
+        .unwrap();
+    assert!(files.iter().all(|f| f.digests.is_empty()));
+}
+
+#[test]
+fn test_borrowed_digests() {
+    let data = write_with_level(
+        &sample_entries(),
+        ArchiveFormat::Zip,
+        CompressionLevel::Store,
+    );
+    let files = ArchiveExtractor::new()
+        .with_hash(HashAlgorithm::Sha256)
+        .extract_borrowed_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    let readme = files.iter().find(|f| f.path == "docs/readme.txt").unwrap();
+    assert!(readme.is_borrowed());
+    assert_eq!(readme.digests[0].to_hex(), sha256_hex(README));
+}
+
+#[test]
+fn test_hash_only() {
+    let data = write(&sample_entries(), ArchiveFormat::TarGz);
+    let entries = ArchiveExtractor::new()
+        .hash_with_format(&data, ArchiveFormat::TarGz)
+        .unwrap();
+
+    let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "docs/readme.txt",
+            "docs/large.bin",
+            "docs/empty.txt",
+            "docs/last.txt"
+        ]
+    );
+    assert_eq!(entries[0].size, README.len() as u64);
+    assert_eq!(entries[1].size, LARGE_SIZE as u64);
+    // SHA-256 unless asked otherwise.
+    assert_eq!(entries[0].digests.len(), 1);
+    assert_eq!(
+        entries[0].digest(HashAlgorithm::Sha256).unwrap().to_hex(),
+        sha256_hex(README)
+    );
+
+    let extractor = ArchiveExtractor::new()
+        .with_format(ArchiveFormat::TarGz)
+        .with_hash(HashAlgorithm::Blake3)
+        .with_hash(HashAlgorithm::Sha1)
+        .with_include("**/large.bin");
+    let entries = extractor.hash(&data).unwrap();
+    let files = extractor.extract(&data).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].digests, files[0].digests);
+}
+
+#[test]
+fn test_hash_only_limits() {
+    let result = ArchiveExtractor::new()
+        .with_max_file_size(1024 * 1024)
+        .hash_with_format(
+            &write(&sample_entries(), ArchiveFormat::Zip),
+            ArchiveFormat::Zip,
+        );
+    assert!(matches!(result, Err(ArchiveError::FileTooLarge { .. })));
+}
+
+#[test]
+fn test_algorithm_names() {
+    for (algorithm, _) in HELLO_DIGESTS {
+        assert_eq!(
+            algorithm.name().parse::<HashAlgorithm>().unwrap(),
+            algorithm
+        );
+        assert_eq!(algorithm.to_string(), algorithm.name());
+    }
+    assert_eq!(
+        "SHA-256".parse::<HashAlgorithm>().unwrap(),
+        HashAlgorithm::Sha256
+    );
+    assert!(matches!(
+        "crc32".parse::<HashAlgorithm>(),
+        Err(ArchiveError::InvalidInput(_))
+    ));
+}
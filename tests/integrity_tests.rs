@@ -0,0 +1,233 @@
+//! Tests for integrity testing with ArchiveExtractor::test and typed
+//! checksum errors.
+
+mod common;
+
+use archive::{
+    ArchiveError, ArchiveExtractor, ArchiveFormat, CancellationToken, CompressionLevel,
+    ExtractedFile,
+};
+use common::{MULTI_ENTRY_FORMATS, README, sample_entries, write_with_level};
+
+/// Bytes that no compressor can shrink, so they are stored as they are.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+/// The sample entries followed by `docs/noise.bin`, whose contents are
+/// stored as they are in every format.
+fn write(format: ArchiveFormat, level: CompressionLevel) -> Vec<u8> {
+    let mut entries = sample_entries();
+    entries.push(ExtractedFile::file("docs/noise.bin", noise(4096)));
+    write_with_level(&entries, format, level)
+}
+
+/// Flips a byte inside the first copy of `needle` in `data`.
+fn corrupt(data: &mut [u8], needle: &[u8]) {
+    let position = data
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .expect("contents stored as they are");
+    data[position + needle.len() / 2] ^= 0xff;
+}
+
+fn assert_mismatch(err: &ArchiveError, expected_path: &str) {
+    match err {
+        ArchiveError::ChecksumMismatch {
+            path,
+            expected,
+            actual,
+        } => {
+            assert_eq!(path, expected_path);
+            assert_ne!(expected, actual);
+        }
+        other => panic!("expected a checksum mismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_intact_archives_pass() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let data = write(format, CompressionLevel::Default);
+        let report = ArchiveExtractor::new()
+            .test_with_format(&data, format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        assert!(report.is_ok(), "{:?}: {:?}", format, report);
+
+        let files = ArchiveExtractor::new()
+            .extract_with_format(&data, format)
+            .unwrap();
+        assert_eq!(report.entries.len(), files.len(), "{:?}", format);
+        for (check, file) in report.entries.iter().zip(&files) {
+            assert_eq!(check.path, file.path, "{:?}", format);
+            assert_eq!(check.size, file.data.len() as u64, "{:?}", format);
+        }
+    }
+}
+
+#[test]
+fn test_zip_crc_mismatch() {
+    let mut data = write(ArchiveFormat::Zip, CompressionLevel::Store);
+    corrupt(&mut data, README);
+
+    let err = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Zip)
+        .unwrap_err();
+    assert!(matches!(
+        &err,
+        ArchiveError::ChecksumMismatch { expected, actual, .. }
+            if *expected == crc32fast::hash(README)
+                && *actual == crc32fast::hash(b"Hello,\xdfworld")
+    ));
+    assert_mismatch(&err, "docs/readme.txt");
+
+    let err = ArchiveExtractor::new()
+        .extract_borrowed_with_format(&data, ArchiveFormat::Zip)
+        .unwrap_err();
+    assert_mismatch(&err, "docs/readme.txt");
+}
+
+#[test]
+fn test_zip_test_goes_on_past_bad_entry() {
+    let mut data = write(ArchiveFormat::Zip, CompressionLevel::Store);
+    corrupt(&mut data, README);
+
+    let report = ArchiveExtractor::new()
+        .test_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    assert!(!report.is_ok());
+    assert!(report.error.is_none());
+    assert_eq!(report.entries.len(), 7);
+
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].size, README.len() as u64);
+    assert_mismatch(failures[0].error.as_ref().unwrap(), "docs/readme.txt");
+    assert!(report.entries[6].is_ok());
+    assert_eq!(report.entries[6].size, 4096);
+}
+
+#[test]
+fn test_tar_header_checksum_mismatch() {
+    let mut data = write(ArchiveFormat::Tar, CompressionLevel::Default);
+    let header = data
+        .windows(15)
+        .position(|window| window == b"docs/readme.txt")
+        .unwrap();
+    // A byte of the mode field.
+    data[header + 101] ^= 0x01;
+
+    let err = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Tar)
+        .unwrap_err();
+    assert_mismatch(&err, "docs/readme.txt");
+
+    let report = ArchiveExtractor::new()
+        .test_with_format(&data, ArchiveFormat::Tar)
+        .unwrap();
+    let paths: Vec<&str> = report.entries.iter().map(|c| c.path.as_str()).collect();
+    assert_eq!(paths, ["docs/", "docs/readme.txt"]);
+    assert!(report.entries[0].is_ok());
+    assert_mismatch(report.entries[1].error.as_ref().unwrap(), "docs/readme.txt");
+}
+
+#[test]
+fn test_7z_crc_mismatch() {
+    let mut data = write(ArchiveFormat::SevenZ, CompressionLevel::Default);
+    corrupt(&mut data, &noise(4096)[1000..1100]);
+
+    let err = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::SevenZ)
+        .unwrap_err();
+    assert_mismatch(&err, "docs/noise.bin");
+
+    let report = ArchiveExtractor::new()
+        .test_with_format(&data, ArchiveFormat::SevenZ)
+        .unwrap();
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(failures.len(), 1, "{:?}", report);
+    assert_eq!(failures[0].path, "docs/noise.bin");
+    assert_eq!(failures[0].size, 4096);
+    assert_eq!(report.entries.len(), 7);
+}
+
+#[test]
+fn test_7z_zero_crc_is_checked() {
+    // These four bytes bring the CRC-32 of the contents to zero.
+    let mut contents = noise(4092);
+    contents.extend_from_slice(&[31, 214, 220, 28]);
+    assert_eq!(crc32fast::hash(&contents), 0);
+    let entries = vec![ExtractedFile::file("zero.bin", contents)];
+    let mut data = write_with_level(&entries, ArchiveFormat::SevenZ, CompressionLevel::Default);
+    corrupt(&mut data, &noise(4096)[1000..1100]);
+
+    let err = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::SevenZ)
+        .unwrap_err();
+    assert_mismatch(&err, "zero.bin");
+}
+
+#[test]
+fn test_rar_crc_mismatch() {
+    // RAR 5 archives written by ArchiveWriter check contents with BLAKE2sp
+    // rather than CRC-32; RAR 4 archives use CRC-32.
+    let mut builder = rars::Builder::new(rars::ArchiveVersion::Rar40).store(true);
+    builder
+        .add_bytes(b"docs/readme.txt".to_vec(), README.to_vec(), None, None)
+        .unwrap();
+    let mut data = builder.to_bytes().unwrap();
+    corrupt(&mut data, README);
+
+    let err = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Rar)
+        .unwrap_err();
+    assert_mismatch(&err, "docs/readme.txt");
+}
+
+#[test]
+fn test_failure_outside_entries() {
+    let report = ArchiveExtractor::new()
+        .test_with_format(b"not a zip archive", ArchiveFormat::Zip)
+        .unwrap();
+    assert!(!report.is_ok());
+    assert!(report.entries.is_empty());
+    assert!(report.error.is_some());
+
+    let data = write(ArchiveFormat::Zip, CompressionLevel::Default);
+    let report = ArchiveExtractor::new()
+        .with_max_file_size(1024)
+        .test_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    assert!(matches!(
+        report.error,
+        Some(ArchiveError::FileTooLarge { .. })
+    ));
+}
+
+#[test]
+fn test_configured_format_and_cancellation() {
+    let data = write(ArchiveFormat::TarGz, CompressionLevel::Default);
+    let report = ArchiveExtractor::new()
+        .with_format(ArchiveFormat::TarGz)
+        .test(&data)
+        .unwrap();
+    assert!(report.is_ok());
+
+    let result = ArchiveExtractor::new().test(&data);
+    assert!(matches!(result, Err(ArchiveError::UnknownFormat)));
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let result = ArchiveExtractor::new()
+        .with_cancellation(token)
+        .test_with_format(&data, ArchiveFormat::TarGz);
+    assert!(matches!(result, Err(ArchiveError::Cancelled)));
+}
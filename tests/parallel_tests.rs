@@ -0,0 +1,228 @@
+//! Tests for parallel ZIP extraction.
+#![cfg(feature = "parallel")]
+
+mod common;
+
+use archive::{
+    ArchiveError, ArchiveExtractor, ArchiveFormat, CancellationToken, CompressionLevel,
+    ExtractedFile,
+};
+use std::sync::{Arc, Mutex};
+
+/// Many entries of different sizes, so workers finish out of order.
+fn sample_entries() -> Vec<ExtractedFile> {
+    let mut entries = vec![ExtractedFile::directory("data/")];
+    for i in 0..64 {
+        let size = (i * 7919) % (64 * 1024);
+        entries.push(ExtractedFile::file(
+            format!("data/file{:02}.bin", i),
+            vec![(i % 251) as u8; size],
+        ));
+    }
+    entries.push(ExtractedFile::symlink("latest", "data/file63.bin"));
+    entries
+}
+
+fn write_zip() -> Vec<u8> {
+    common::write(&sample_entries(), ArchiveFormat::Zip)
+}
+
+#[test]
+fn test_parallel_matches_sequential() {
+    let data = write_zip();
+    let sequential = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    let parallel = ArchiveExtractor::new()
+        .with_parallel(true)
+        .extract_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+
+    assert_eq!(parallel.len(), sequential.len());
+    for (a, b) in parallel.iter().zip(&sequential) {
+        assert_eq!(a.path, b.path);
+        assert_eq!(a.data, b.data);
+        assert_eq!(a.is_directory, b.is_directory);
+        assert_eq!(a.metadata, b.metadata);
+    }
+    assert_eq!(
+        parallel.last().unwrap().metadata.link_target.as_deref(),
+        Some("data/file63.bin")
+    );
+}
+
+#[test]
+fn test_parallel_total_size_limit() {
+    let data = write_zip();
+    // ZIP stores the symlink's target as its contents, which counts too.
+    let total: usize = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Zip)
+        .unwrap()
+        .iter()
+        .map(|f| f.data.len())
+        .sum();
+
+    let result = ArchiveExtractor::new()
+        .with_parallel(true)
+        .with_max_total_size(total / 2)
+        .extract_with_format(&data, ArchiveFormat::Zip);
+    assert!(matches!(
+        result,
+        Err(ArchiveError::TotalSizeTooLarge { limit, .. }) if limit == total / 2
+    ));
+
+    let files = ArchiveExtractor::new()
+        .with_parallel(true)
+        .with_max_total_size(total)
+        .extract_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    assert_eq!(files.len(), sample_entries().len());
+}
+
+#[test]
+fn test_parallel_file_size_limit() {
+    let result = ArchiveExtractor::new()
+        .with_parallel(true)
+        .with_max_file_size(1024)
+        .extract_with_format(&write_zip(), ArchiveFormat::Zip);
+    assert!(matches!(result, Err(ArchiveError::FileTooLarge { .. })));
+}
+
+#[test]
+fn test_parallel_with_filters() {
+    let files = ArchiveExtractor::new()
+        .with_parallel(true)
+        .with_include("data/file1?.bin")
+        .extract_with_format(&write_zip(), ArchiveFormat::Zip)
+        .unwrap();
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let expected: Vec<String> = (10..20).map(|i| format!("data/file{}.bin", i)).collect();
+    assert_eq!(paths, expected);
+}
+
+#[test]
+fn test_parallel_cancellation() {
+    let token = CancellationToken::new();
+    let trigger = token.clone();
+    let result = ArchiveExtractor::new()
+        .with_parallel(true)
+        .with_cancellation(token)
+        .with_filter(move |header| {
+            // Cancel while the headers are still being read.
+            if header.path == "data/file05.bin" {
+                trigger.cancel();
+            }
+            true
+        })
+        .extract_with_format(&write_zip(), ArchiveFormat::Zip);
+    assert!(matches!(result, Err(ArchiveError::Cancelled)));
+}
+
+#[test]
+fn test_parallel_digests() {
+    let data = write_zip();
+    let extractor = ArchiveExtractor::new().with_hash(archive::HashAlgorithm::Sha256);
+    let sequential = extractor
+        .extract_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    let parallel = extractor
+        .with_parallel(true)
+        .extract_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    for (a, b) in parallel.iter().zip(&sequential) {
+        assert_eq!(a.digests, b.digests, "{}", a.path);
+    }
+    assert!(!parallel[1].digests.is_empty());
+}
+
+#[test]
+fn test_parallel_progress_from_workers() {
+    let caller = std::thread::current().id();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    let files = ArchiveExtractor::new()
+        .with_parallel(true)
+        .with_progress(move |progress| {
+            let from_worker = std::thread::current().id() != caller;
+            recorded
+                .lock()
+                .unwrap()
+                .push((progress.entries_done, progress.bytes_out, from_worker));
+        })
+        .extract_with_format(&write_zip(), ArchiveFormat::Zip)
+        .unwrap();
+
+    let events = events.lock().unwrap();
+    // Progress arrives while the workers decompress, not after they are done.
+    assert!(events.iter().all(|&(_, _, from_worker)| from_worker));
+    let entries_done = events.iter().map(|&(done, _, _)| done).max().unwrap();
+    let bytes_out = events.iter().map(|&(_, bytes, _)| bytes).max().unwrap();
+    assert_eq!(entries_done, files.len());
+    assert_eq!(
+        bytes_out,
+        files.iter().map(|f| f.data.len() as u64).sum::<u64>()
+    );
+}
+
+#[test]
+fn test_parallel_hash_only() {
+    let data = write_zip();
+    let extractor = ArchiveExtractor::new().with_hash(archive::HashAlgorithm::Sha256);
+    let sequential = extractor
+        .hash_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    let parallel = extractor
+        .with_parallel(true)
+        .hash_with_format(&data, ArchiveFormat::Zip)
+        .unwrap();
+    assert_eq!(parallel, sequential);
+}
+
+#[test]
+fn test_parallel_reports_first_failure() {
+    // Incompressible entries, stored as they are, so their bytes can be
+    // found and corrupted in the archive.
+    let mut state = 0x2545_f491_u32;
+    let entries: Vec<ExtractedFile> = (0..256)
+        .map(|i| {
+            let contents: Vec<u8> = (0..64 * 1024)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u8
+                })
+                .collect();
+            ExtractedFile::file(format!("noise{:03}.bin", i), contents)
+        })
+        .collect();
+    let mut data = common::write_with_level(&entries, ArchiveFormat::Zip, CompressionLevel::Store);
+    for index in [2, 200] {
+        let needle = &entries[index].data[..32];
+        let position = data
+            .windows(needle.len())
+            .position(|window| window == needle)
+            .unwrap();
+        data[position + 16] ^= 0xff;
+    }
+
+    let entries_done = Arc::new(Mutex::new(0));
+    let seen = Arc::clone(&entries_done);
+    let err = ArchiveExtractor::new()
+        .with_parallel(true)
+        .with_progress(move |progress| {
+            let mut seen = seen.lock().unwrap();
+            *seen = (*seen).max(progress.entries_done);
+        })
+        .extract_with_format(&data, ArchiveFormat::Zip)
+        .unwrap_err();
+
+    // The earlier of the two corrupt entries is reported, as it would be
+    // without workers.
+    match err {
+        ArchiveError::ChecksumMismatch { path, .. } => assert_eq!(path, "noise002.bin"),
+        other => panic!("expected a checksum mismatch, got {:?}", other),
+    }
+    // The entries after it are abandoned rather than all decompressed.
+    assert!(*entries_done.lock().unwrap() < entries.len() / 2);
+}
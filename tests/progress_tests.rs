@@ -0,0 +1,164 @@
+//! Tests for progress reporting and cancellation during extraction.
+
+mod common;
+
+use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat, CancellationToken, ExtractedFile};
+use common::{MULTI_ENTRY_FORMATS, sample_entries, write};
+use std::sync::{Arc, Mutex};
+
+/// (entries_done, bytes_in, bytes_out, current_path) for every callback.
+type Reports = Arc<Mutex<Vec<(usize, u64, u64, Option<String>)>>>;
+
+fn recording_extractor() -> (ArchiveExtractor, Reports) {
+    let reports: Reports = Arc::default();
+    let sink = Arc::clone(&reports);
+    let extractor = ArchiveExtractor::new().with_progress(move |progress| {
+        sink.lock().unwrap().push((
+            progress.entries_done,
+            progress.bytes_in,
+            progress.bytes_out,
+            progress.current_path.map(str::to_string),
+        ));
+    });
+    (extractor, reports)
+}
+
+#[test]
+fn test_progress_reported_for_every_format() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let data = write(&sample_entries(), format);
+        let (extractor, reports) = recording_extractor();
+        let files = extractor
+            .extract_with_format(&data, format)
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+
+        let reports = reports.lock().unwrap();
+        assert!(!reports.is_empty(), "{:?}", format);
+        for pair in reports.windows(2) {
+            assert!(pair[0].0 <= pair[1].0, "{:?}: {:?}", format, pair);
+            assert!(pair[0].1 <= pair[1].1, "{:?}: {:?}", format, pair);
+            assert!(pair[0].2 <= pair[1].2, "{:?}: {:?}", format, pair);
+        }
+
+        let total: u64 = files.iter().map(|f| f.data.len() as u64).sum();
+        let (entries_done, bytes_in, bytes_out, path) = reports.last().unwrap().clone();
+        assert_eq!(entries_done, files.len(), "{:?}", format);
+        assert_eq!(bytes_out, total, "{:?}", format);
+        assert!(
+            bytes_in > 0 && bytes_in <= data.len() as u64,
+            "{:?}",
+            format
+        );
+        assert!(path.is_some(), "{:?}", format);
+    }
+}
+
+#[test]
+fn test_progress_within_large_entry() {
+    let data = write(&sample_entries(), ArchiveFormat::Tar);
+    let (extractor, reports) = recording_extractor();
+    extractor
+        .extract_with_format(&data, ArchiveFormat::Tar)
+        .unwrap();
+
+    // The large entry is reported while it is read, before it is done.
+    let reports = reports.lock().unwrap();
+    let partial = reports
+        .iter()
+        .filter(|(_, _, _, path)| path.as_deref() == Some("docs/large.bin"))
+        .count();
+    assert!(partial >= 3, "{:?}", reports);
+}
+
+#[test]
+fn test_progress_for_single_file_format() {
+    let entries = [ExtractedFile::file("notes.txt", b"notes".to_vec())];
+    let data = write(&entries, ArchiveFormat::Zst);
+
+    let (extractor, reports) = recording_extractor();
+    extractor
+        .with_source_filename("notes.txt.zst")
+        .extract_with_format(&data, ArchiveFormat::Zst)
+        .unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(
+        reports.last().unwrap(),
+        &(1, data.len() as u64, 5, Some("notes.txt".to_string()))
+    );
+}
+
+#[test]
+fn test_cancelled_before_extraction() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let extractor = ArchiveExtractor::new().with_cancellation(token);
+
+    for &format in MULTI_ENTRY_FORMATS {
+        let result = extractor.extract_with_format(&write(&sample_entries(), format), format);
+        assert!(
+            matches!(result, Err(ArchiveError::Cancelled)),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_cancelled_during_extraction() {
+    for &format in MULTI_ENTRY_FORMATS {
+        let data = write(&sample_entries(), format);
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        let reports: Reports = Arc::default();
+        let sink = Arc::clone(&reports);
+        let extractor = ArchiveExtractor::new()
+            .with_cancellation(token)
+            .with_progress(move |progress| {
+                sink.lock().unwrap().push((
+                    progress.entries_done,
+                    progress.bytes_in,
+                    progress.bytes_out,
+                    progress.current_path.map(str::to_string),
+                ));
+                if progress.current_path == Some("docs/readme.txt") {
+                    trigger.cancel();
+                }
+            });
+
+        let result = extractor.extract_with_format(&data, format);
+        assert!(
+            matches!(result, Err(ArchiveError::Cancelled)),
+            "{:?}: {:?}",
+            format,
+            result.map(|files| files.len())
+        );
+        // Nothing after the cancelling entry was finished.
+        let reports = reports.lock().unwrap();
+        assert!(
+            reports
+                .iter()
+                .all(|(_, _, _, path)| path.as_deref() != Some("docs/last.txt")),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_cancelled_single_file() {
+    let entries = [ExtractedFile::file("big.bin", vec![1u8; 4 * 1024 * 1024])];
+    let data = write(&entries, ArchiveFormat::Gz);
+
+    let token = CancellationToken::new();
+    let trigger = token.clone();
+    let result = ArchiveExtractor::new()
+        .with_cancellation(token)
+        .with_progress(move |progress| {
+            if progress.bytes_out >= 1024 * 1024 {
+                trigger.cancel();
+            }
+        })
+        .extract_with_format(&data, ArchiveFormat::Gz);
+    assert!(matches!(result, Err(ArchiveError::Cancelled)));
+}
@@ -0,0 +1,106 @@
+//! Tests for RAR archive extraction
+
+mod common;
+
+use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat};
+use common::assert_contains_file;
+use rars::ArchiveVersion;
+
+/// Builds a RAR archive in the given on-disk version.
+fn build_test_rar(version: ArchiveVersion, store: bool) -> Vec<u8> {
+    let mut builder = rars::Builder::new(version).store(store);
+    builder
+        .add_directory(b"test-data".to_vec(), None, None)
+        .unwrap();
+    builder
+        .add_bytes(
+            b"test-data/hello.txt".to_vec(),
+            b"Hello, World!\n".to_vec(),
+            None,
+            None,
+        )
+        .unwrap();
+    builder
+        .add_bytes(
+            b"test-data/repeated.txt".to_vec(),
+            b"compressible line\n".repeat(200),
+            None,
+            None,
+        )
+        .unwrap();
+    builder.to_bytes().unwrap()
+}
+
+fn assert_test_rar_contents(data: &[u8]) {
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(data, ArchiveFormat::Rar)
+        .expect("Failed to extract RAR archive");
+
+    assert_eq!(files.len(), 3);
+    let dir = assert_contains_file(&files, "test-data");
+    assert!(dir.is_directory);
+    let hello = assert_contains_file(&files, "test-data/hello.txt");
+    assert_eq!(hello.data, b"Hello, World!\n");
+    let repeated = assert_contains_file(&files, "test-data/repeated.txt");
+    assert_eq!(repeated.data, b"compressible line\n".repeat(200));
+}
+
+#[test]
+fn test_rar4_stored() {
+    assert_test_rar_contents(&build_test_rar(ArchiveVersion::Rar29, true));
+}
+
+#[test]
+fn test_rar4_compressed() {
+    assert_test_rar_contents(&build_test_rar(ArchiveVersion::Rar29, false));
+}
+
+#[test]
+fn test_rar5_stored() {
+    assert_test_rar_contents(&build_test_rar(ArchiveVersion::Rar50, true));
+}
+
+#[test]
+fn test_rar5_compressed() {
+    assert_test_rar_contents(&build_test_rar(ArchiveVersion::Rar50, false));
+}
+
+#[test]
+fn test_rar_format_from_filename() {
+    let data = build_test_rar(ArchiveVersion::Rar50, false);
+    let extractor = ArchiveExtractor::new()
+        .with_source_filename("upload.rar")
+        .with_format_from_filename()
+        .expect("Failed to infer format");
+
+    let files = extractor.extract(&data).expect("Failed to extract");
+    assert_contains_file(&files, "hello.txt");
+}
+
+#[test]
+fn test_rar_size_limits() {
+    let data = build_test_rar(ArchiveVersion::Rar50, false);
+
+    let result = ArchiveExtractor::new()
+        .with_max_file_size(1024)
+        .extract_with_format(&data, ArchiveFormat::Rar);
+    assert!(matches!(result, Err(ArchiveError::FileTooLarge { .. })));
+
+    let result = ArchiveExtractor::new()
+        .with_max_total_size(1024)
+        .extract_with_format(&data, ArchiveFormat::Rar);
+    assert!(matches!(
+        result,
+        Err(ArchiveError::TotalSizeTooLarge { .. })
+    ));
+}
+
+#[test]
+fn test_rar_invalid_data() {
+    let extractor = ArchiveExtractor::new();
+
+    let result = extractor.extract_with_format(b"not a rar archive", ArchiveFormat::Rar);
+    assert!(matches!(result, Err(ArchiveError::InvalidArchive(_))));
+}
@@ -0,0 +1,179 @@
+//! Tests for the `serde` feature.
+#![cfg(feature = "serde")]
+
+use archive::{
+    ArchiveConverter, ArchiveExtractor, ArchiveFormat, ArchiveWriter, CompressionLevel,
+    ConversionLoss, EntryHeader, EntryMetadata, ExtractedFile, HashAlgorithm, HashedEntry,
+    MetadataField,
+};
+use serde_json::json;
+
+#[test]
+fn test_format_serializes_as_identifier() {
+    assert_eq!(
+        serde_json::to_value(ArchiveFormat::TarGz).unwrap(),
+        json!("tar.gz")
+    );
+    assert_eq!(
+        serde_json::to_value(ArchiveFormat::SevenZ).unwrap(),
+        json!("7z")
+    );
+
+    let format: ArchiveFormat = serde_json::from_value(json!("TAR.ZST")).unwrap();
+    assert_eq!(format, ArchiveFormat::TarZst);
+    assert!(serde_json::from_value::<ArchiveFormat>(json!("tarball")).is_err());
+}
+
+#[test]
+fn test_extracted_file_round_trip() {
+    let file =
+        ExtractedFile::file("bin/tool", vec![0, 1, 2, 0xfe, 0xff]).with_metadata(EntryMetadata {
+            mtime: Some(1_700_000_000),
+            mode: Some(0o755),
+            owner: Some("root".into()),
+            ..EntryMetadata::default()
+        });
+
+    let value = serde_json::to_value(&file).unwrap();
+    assert_eq!(value["path"], "bin/tool");
+    assert_eq!(value["data"], "AAEC/v8=");
+    assert_eq!(value["metadata"]["mode"], 0o755);
+    assert_eq!(value["metadata"]["uid"], serde_json::Value::Null);
+
+    let back: ExtractedFile = serde_json::from_value(value).unwrap();
+    assert_eq!(back.path, file.path);
+    assert_eq!(back.data, file.data);
+    assert_eq!(back.metadata, file.metadata);
+    assert!(back.digests.is_empty());
+}
+
+#[test]
+fn test_header_leaves_out_data() {
+    let file = ExtractedFile::symlink("latest", "v1.2.3");
+    let value = serde_json::to_value(file.header()).unwrap();
+
+    assert!(value.get("data").is_none());
+    assert_eq!(value["size"], 0);
+    assert_eq!(value["metadata"]["link_target"], "v1.2.3");
+
+    let back: EntryHeader = serde_json::from_value(value).unwrap();
+    assert_eq!(back, file.header());
+}
+
+#[test]
+fn test_raw_name_serializes_as_base64() {
+    let tar = ArchiveWriter::new()
+        .write_with_format(
+            &[ExtractedFile::file("hi.txt", b"hi".to_vec())],
+            ArchiveFormat::Tar,
+        )
+        .unwrap();
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&tar, ArchiveFormat::Tar)
+        .unwrap();
+
+    let value = serde_json::to_value(&files[0]).unwrap();
+    assert_eq!(value["metadata"]["raw_name"], "aGkudHh0");
+
+    let back: ExtractedFile = serde_json::from_value(value).unwrap();
+    assert_eq!(back.metadata, files[0].metadata);
+}
+
+#[test]
+fn test_metadata_fields_default_when_missing() {
+    let metadata: EntryMetadata = serde_json::from_value(json!({ "mtime": 42 })).unwrap();
+    assert_eq!(
+        metadata,
+        EntryMetadata {
+            mtime: Some(42),
+            ..EntryMetadata::default()
+        }
+    );
+}
+
+#[test]
+fn test_hashed_entries_serialize_hex_digests() {
+    let entries = vec![ExtractedFile::file("hello.txt", b"hello".to_vec())];
+    let zip = ArchiveWriter::new()
+        .with_compression_level(CompressionLevel::Store)
+        .write_with_format(&entries, ArchiveFormat::Zip)
+        .unwrap();
+    let hashed = ArchiveExtractor::new()
+        .with_hash(HashAlgorithm::Md5)
+        .hash_with_format(&zip, ArchiveFormat::Zip)
+        .unwrap();
+
+    let value = serde_json::to_value(&hashed).unwrap();
+    assert_eq!(
+        value,
+        json!([{
+            "path": "hello.txt",
+            "size": 5,
+            "digests": [{ "algorithm": "md5", "hex": "5d41402abc4b2a76b9719d911017c592" }],
+        }])
+    );
+
+    let back: Vec<HashedEntry> = serde_json::from_value(value).unwrap();
+    assert_eq!(back, hashed);
+}
+
+#[test]
+fn test_integrity_report_serializes_error_messages() {
+    let entries = vec![ExtractedFile::file("hello.txt", b"Hello, world".to_vec())];
+    let mut zip = ArchiveWriter::new()
+        .with_compression_level(CompressionLevel::Store)
+        .write_with_format(&entries, ArchiveFormat::Zip)
+        .unwrap();
+    let pos = zip
+        .windows(12)
+        .position(|window| window == b"Hello, world")
+        .unwrap();
+    zip[pos] ^= 0xff;
+
+    let report = ArchiveExtractor::new()
+        .test_with_format(&zip, ArchiveFormat::Zip)
+        .unwrap();
+    let value = serde_json::to_value(&report).unwrap();
+
+    assert_eq!(value["error"], serde_json::Value::Null);
+    assert_eq!(value["entries"][0]["path"], "hello.txt");
+    assert_eq!(
+        value["entries"][0]["error"],
+        report.entries[0].error.as_ref().unwrap().to_string()
+    );
+}
+
+#[test]
+fn test_conversion_losses_are_tagged() {
+    let entries = vec![
+        ExtractedFile::directory("docs/"),
+        ExtractedFile::file("docs/a.txt", b"a".to_vec()).with_metadata(EntryMetadata {
+            uid: Some(1000),
+            ..EntryMetadata::default()
+        }),
+        ExtractedFile::symlink("docs/b.txt", "a.txt"),
+    ];
+    let tar = ArchiveWriter::new()
+        .write_with_format(&entries, ArchiveFormat::Tar)
+        .unwrap();
+    let conversion = ArchiveConverter::new()
+        .convert(&tar, ArchiveFormat::Tar, ArchiveFormat::Cab)
+        .unwrap();
+
+    let value = serde_json::to_value(&conversion.losses).unwrap();
+    let kinds: Vec<_> = value
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|loss| loss["kind"].as_str().unwrap())
+        .collect();
+    assert!(kinds.contains(&"directory"));
+    assert!(kinds.contains(&"symlink"));
+
+    let back: Vec<ConversionLoss> = serde_json::from_value(value).unwrap();
+    assert_eq!(back, conversion.losses);
+    assert!(back.iter().any(|loss| matches!(
+        loss,
+        ConversionLoss::Metadata { fields, .. } if fields.contains(&MetadataField::Uid)
+    )));
+}
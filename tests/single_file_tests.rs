@@ -1,9 +1,16 @@
-//! Tests for single-file decompression (gz, bz2, xz, lz4, zst)
+//! Tests for single-file decompression (gz, bz2, xz, lz4, zst, Z, lzma, lz)
 
 mod common;
 
-use archive::{ArchiveExtractor, ArchiveFormat};
+use archive::{ArchiveError, ArchiveExtractor, ArchiveFormat, GzipNamePrecedence};
 use common::read_test_archive;
+use std::io::Write;
+
+fn gzip(builder: flate2::GzBuilder) -> Vec<u8> {
+    let mut encoder = builder.write(Vec::new(), flate2::Compression::default());
+    encoder.write_all(b"Hello, World!\n").unwrap();
+    encoder.finish().unwrap()
+}
 
 #[test]
 fn test_single_gz_decompression() {
@@ -75,6 +82,48 @@ fn test_single_zst_decompression() {
     assert_eq!(content.trim(), "Hello, World!");
 }
 
+#[test]
+fn test_single_z_decompression() {
+    let data = read_test_archive("hello.txt.Z");
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Z)
+        .expect("Failed to decompress hello.txt.Z");
+
+    assert_eq!(files.len(), 1, "Expected single decompressed file");
+    let content = String::from_utf8_lossy(&files[0].data);
+    assert_eq!(content.trim(), "Hello, World!");
+}
+
+#[test]
+fn test_single_lzma_decompression() {
+    let data = read_test_archive("hello.txt.lzma");
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Lzma)
+        .expect("Failed to decompress hello.txt.lzma");
+
+    assert_eq!(files.len(), 1, "Expected single decompressed file");
+    let content = String::from_utf8_lossy(&files[0].data);
+    assert_eq!(content.trim(), "Hello, World!");
+}
+
+#[test]
+fn test_single_lzip_decompression() {
+    let data = read_test_archive("hello.txt.lz");
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Lzip)
+        .expect("Failed to decompress hello.txt.lz");
+
+    assert_eq!(files.len(), 1, "Expected single decompressed file");
+    let content = String::from_utf8_lossy(&files[0].data);
+    assert_eq!(content.trim(), "Hello, World!");
+}
+
 #[test]
 fn test_gz_extracts_original_filename() {
     let data = read_test_archive("hello.txt.gz");
@@ -150,6 +199,39 @@ fn test_zst_uses_data_as_filename() {
     assert_eq!(files[0].path, "data");
 }
 
+#[test]
+fn test_z_uses_data_as_filename() {
+    let data = read_test_archive("hello.txt.Z");
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::Z)
+        .expect("Failed to decompress hello.txt.Z");
+
+    assert_eq!(files.len(), 1);
+    // compress format doesn't store original filename
+    assert_eq!(files[0].path, "data");
+}
+
+#[test]
+fn test_z_invalid_data() {
+    let extractor = ArchiveExtractor::new();
+
+    let result = extractor.extract_with_format(b"not compressed", ArchiveFormat::Z);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_lzip_corrupt_trailer() {
+    let mut data = read_test_archive("hello.txt.lz");
+    // The CRC-32 is the first field of the 20-byte trailer
+    let crc_offset = data.len() - 20;
+    data[crc_offset] ^= 0xFF;
+
+    let result = ArchiveExtractor::new().extract_with_format(&data, ArchiveFormat::Lzip);
+    assert!(matches!(result, Err(ArchiveError::InvalidArchive(_))));
+}
+
 // Builder API tests
 
 #[test]
@@ -200,6 +282,42 @@ fn test_builder_zst_source_filename_derives_path() {
     assert_eq!(files[0].path, "hello.txt");
 }
 
+#[test]
+fn test_builder_z_source_filename_derives_path() {
+    let data = read_test_archive("hello.txt.Z");
+    let extractor = ArchiveExtractor::new()
+        .with_source_filename("hello.txt.Z")
+        .with_format(ArchiveFormat::Z);
+
+    let files = extractor.extract(&data).expect("Failed to decompress");
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "hello.txt");
+}
+
+#[test]
+fn test_builder_lzma_source_filename_derives_path() {
+    let data = read_test_archive("hello.txt.lzma");
+    let extractor = ArchiveExtractor::new()
+        .with_source_filename("hello.txt.lzma")
+        .with_format(ArchiveFormat::Lzma);
+
+    let files = extractor.extract(&data).expect("Failed to decompress");
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "hello.txt");
+}
+
+#[test]
+fn test_builder_lzip_source_filename_derives_path() {
+    let data = read_test_archive("hello.txt.lz");
+    let extractor = ArchiveExtractor::new()
+        .with_source_filename("hello.txt.lz")
+        .with_format(ArchiveFormat::Lzip);
+
+    let files = extractor.extract(&data).expect("Failed to decompress");
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "hello.txt");
+}
+
 #[test]
 fn test_builder_gz_header_filename_takes_priority() {
     let data = read_test_archive("hello.txt.gz");
@@ -217,6 +335,168 @@ fn test_builder_gz_header_filename_takes_priority() {
     );
 }
 
+#[test]
+fn test_gz_header_metadata() {
+    let data = gzip(
+        flate2::GzBuilder::new()
+            .filename("notes.txt")
+            .mtime(1_700_000_000)
+            .comment("build 42")
+            .extra(b"AB\x02\x00hi".to_vec())
+            .operating_system(3),
+    );
+
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Gz)
+        .unwrap();
+    let metadata = &files[0].metadata;
+    assert_eq!(metadata.mtime, Some(1_700_000_000));
+    assert_eq!(metadata.comment.as_deref(), Some("build 42"));
+    assert_eq!(metadata.os, Some(3));
+    assert_eq!(metadata.extra.as_deref(), Some(&b"AB\x02\x00hi"[..]));
+    assert_eq!(metadata.raw_name.as_deref(), Some(&b"notes.txt"[..]));
+}
+
+#[test]
+fn test_gz_header_without_optional_fields() {
+    // A zero timestamp means none was recorded; Latin-1 text is decoded.
+    let data = gzip(flate2::GzBuilder::new().comment(b"caf\xe9".to_vec()));
+
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Gz)
+        .unwrap();
+    let metadata = &files[0].metadata;
+    assert_eq!(files[0].path, "data");
+    assert_eq!(metadata.mtime, None);
+    assert_eq!(metadata.comment.as_deref(), Some("café"));
+    assert_eq!(metadata.extra, None);
+    assert_eq!(metadata.raw_name, None);
+}
+
+#[test]
+fn test_gz_name_precedence() {
+    let data = gzip(flate2::GzBuilder::new().filename("invoice.pdf.exe"));
+    let path = |extractor: ArchiveExtractor| {
+        extractor
+            .extract_with_format(&data, ArchiveFormat::Gz)
+            .unwrap()
+            .remove(0)
+            .path
+    };
+
+    let cases = [
+        (
+            GzipNamePrecedence::Header,
+            "invoice.pdf.exe",
+            "invoice.pdf.exe",
+        ),
+        (
+            GzipNamePrecedence::SourceFilename,
+            "invoice.pdf",
+            "invoice.pdf.exe",
+        ),
+        (
+            GzipNamePrecedence::SourceFilenameOnly,
+            "invoice.pdf",
+            "data",
+        ),
+    ];
+    for (precedence, with_source, without_source) in cases {
+        let extractor = ArchiveExtractor::new().with_gzip_name_precedence(precedence);
+        assert_eq!(
+            path(extractor.clone().with_source_filename("invoice.pdf.gz")),
+            with_source
+        );
+        assert_eq!(path(extractor), without_source);
+    }
+}
+
+/// Extracts a gzip file whose header names it `name`, received as
+/// `upload.txt.gz`, and returns the entry's path.
+fn gz_path_for_header_name(name: &str) -> String {
+    let data = gzip(flate2::GzBuilder::new().filename(name));
+    ArchiveExtractor::new()
+        .with_source_filename("upload.txt.gz")
+        .extract_with_format(&data, ArchiveFormat::Gz)
+        .unwrap()
+        .remove(0)
+        .path
+}
+
+#[test]
+fn test_gz_header_name_traversal_is_rejected() {
+    assert_eq!(gz_path_for_header_name("../../.bashrc"), "upload.txt");
+}
+
+#[test]
+fn test_gz_header_name_windows_traversal_is_rejected() {
+    assert_eq!(
+        gz_path_for_header_name("..\\..\\autoexec.bat"),
+        "upload.txt"
+    );
+}
+
+#[test]
+fn test_gz_header_name_traversal_inside_path_is_rejected() {
+    assert_eq!(
+        gz_path_for_header_name("docs/../../etc/passwd"),
+        "upload.txt"
+    );
+}
+
+#[test]
+fn test_gz_header_name_parent_only_is_rejected() {
+    assert_eq!(gz_path_for_header_name(".."), "upload.txt");
+}
+
+#[test]
+fn test_gz_header_name_absolute_path_is_stripped() {
+    assert_eq!(gz_path_for_header_name("/etc/passwd"), "passwd");
+}
+
+#[test]
+fn test_gz_header_name_windows_absolute_path_is_stripped() {
+    assert_eq!(gz_path_for_header_name("C:\\Windows\\win.ini"), "win.ini");
+}
+
+#[test]
+fn test_gz_header_name_directories_are_stripped() {
+    assert_eq!(
+        gz_path_for_header_name("build/out/report.csv"),
+        "report.csv"
+    );
+}
+
+#[test]
+fn test_gz_header_name_control_characters_are_rejected() {
+    assert_eq!(gz_path_for_header_name("report\x1b[2J.csv"), "upload.txt");
+    assert_eq!(gz_path_for_header_name("report.csv\n.bashrc"), "upload.txt");
+}
+
+#[test]
+fn test_gz_header_name_empty_is_rejected() {
+    assert_eq!(gz_path_for_header_name(""), "upload.txt");
+}
+
+#[test]
+fn test_gz_header_name_directory_only_is_rejected() {
+    assert_eq!(gz_path_for_header_name("build/"), "upload.txt");
+    assert_eq!(gz_path_for_header_name("build/."), "upload.txt");
+}
+
+#[test]
+fn test_gz_rejected_header_name_falls_back_to_data() {
+    let data = gzip(flate2::GzBuilder::new().filename("../../.bashrc"));
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Gz)
+        .unwrap();
+    assert_eq!(files[0].path, "data");
+    assert_eq!(
+        files[0].metadata.raw_name.as_deref(),
+        Some(&b"../../.bashrc"[..])
+    );
+}
+
 #[test]
 fn test_builder_format_from_filename() {
     let data = read_test_archive("hello.txt.bz2");
@@ -109,6 +109,58 @@ fn test_tar_zst() {
     assert_contains_file(&files, "hello.txt");
 }
 
+#[test]
+fn test_tar_z() {
+    let data = read_test_archive("archive.tar.Z");
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::TarZ)
+        .expect("Failed to extract archive.tar.Z");
+
+    assert!(!files.is_empty(), "Expected non-empty archive");
+    assert_contains_file(&files, "hello.txt");
+}
+
+#[test]
+fn test_taz() {
+    let data = read_test_archive("archive.taz");
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::TarZ)
+        .expect("Failed to extract archive.taz");
+
+    assert!(!files.is_empty(), "Expected non-empty archive");
+    assert_contains_file(&files, "hello.txt");
+}
+
+#[test]
+fn test_tar_lzma() {
+    let data = read_test_archive("archive.tar.lzma");
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::TarLzma)
+        .expect("Failed to extract archive.tar.lzma");
+
+    assert!(!files.is_empty(), "Expected non-empty archive");
+    assert_contains_file(&files, "hello.txt");
+}
+
+#[test]
+fn test_tar_lz() {
+    let data = read_test_archive("archive.tar.lz");
+    let extractor = ArchiveExtractor::new();
+
+    let files = extractor
+        .extract_with_format(&data, ArchiveFormat::TarLzip)
+        .expect("Failed to extract archive.tar.lz");
+
+    assert!(!files.is_empty(), "Expected non-empty archive");
+    assert_contains_file(&files, "hello.txt");
+}
+
 #[test]
 fn test_nested_tar_gz() {
     let data = read_test_archive("nested.tar.gz");
@@ -138,6 +190,7 @@ fn test_all_tar_formats_produce_same_structure() {
     let tar_bz2 = read_test_archive("archive.tar.bz2");
     let tar_xz = read_test_archive("archive.tar.xz");
     let tar_zst = read_test_archive("archive.tar.zst");
+    let tar_z = read_test_archive("archive.tar.Z");
 
     let files_tar = extractor
         .extract_with_format(&tar, ArchiveFormat::Tar)
@@ -154,12 +207,16 @@ fn test_all_tar_formats_produce_same_structure() {
     let files_tar_zst = extractor
         .extract_with_format(&tar_zst, ArchiveFormat::TarZst)
         .unwrap();
+    let files_tar_z = extractor
+        .extract_with_format(&tar_z, ArchiveFormat::TarZ)
+        .unwrap();
 
     // All should have the same number of files
     assert_eq!(files_tar.len(), files_tar_gz.len());
     assert_eq!(files_tar.len(), files_tar_bz2.len());
     assert_eq!(files_tar.len(), files_tar_xz.len());
     assert_eq!(files_tar.len(), files_tar_zst.len());
+    assert_eq!(files_tar.len(), files_tar_z.len());
 
     // All should contain the same files
     for file in &files_tar {
@@ -183,5 +240,10 @@ fn test_all_tar_formats_produce_same_structure() {
             "tar.zst missing file: {}",
             file.path
         );
+        assert!(
+            files_tar_z.iter().any(|f| f.path == file.path),
+            "tar.Z missing file: {}",
+            file.path
+        );
     }
 }
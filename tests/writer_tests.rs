@@ -0,0 +1,473 @@
+//! Tests for archive creation: every format is written with ArchiveWriter and
+//! read back with ArchiveExtractor.
+
+mod common;
+
+use archive::{
+    ArchiveError, ArchiveExtractor, ArchiveFormat, ArchiveWriter, CompressionLevel, EntryMetadata,
+    ExtractedFile,
+};
+use common::assert_contains_file;
+
+const HELLO: &[u8] = b"Hello, World!\n";
+/// 2023-11-14 22:13:20 UTC, an even number of seconds so that it survives
+/// the two-second resolution of ZIP and CAB timestamps.
+const MTIME: u64 = 1_700_000_000;
+
+fn long_name() -> String {
+    format!("test-data/{}/deep.txt", "nested".repeat(20))
+}
+
+fn sample_entries() -> Vec<ExtractedFile> {
+    let metadata = EntryMetadata {
+        mtime: Some(MTIME),
+        mode: Some(0o640),
+        uid: Some(1000),
+        gid: Some(100),
+        owner: Some("alice".to_string()),
+        group: Some("users".to_string()),
+        link_target: None,
+        ..EntryMetadata::default()
+    };
+    vec![
+        ExtractedFile::directory("test-data/"),
+        ExtractedFile::file("test-data/hello.txt", HELLO).with_metadata(metadata),
+        ExtractedFile::file("test-data/empty.txt", Vec::new()),
+        ExtractedFile::file(long_name(), b"deep".to_vec()),
+        ExtractedFile::symlink("test-data/link.txt", "hello.txt"),
+    ]
+}
+
+fn round_trip(format: ArchiveFormat) -> Vec<ExtractedFile> {
+    common::round_trip(&sample_entries(), format)
+}
+
+fn find<'a>(files: &'a [ExtractedFile], path: &str) -> Option<&'a ExtractedFile> {
+    files.iter().find(|f| f.path.trim_end_matches('/') == path)
+}
+
+/// Checks the regular files every format can hold.
+fn assert_files(files: &[ExtractedFile]) {
+    assert_eq!(assert_contains_file(files, "hello.txt").data, HELLO);
+    assert!(find(files, "test-data/empty.txt").unwrap().data.is_empty());
+    assert_eq!(find(files, &long_name()).unwrap().data, b"deep");
+}
+
+#[test]
+fn test_write_zip() {
+    let files = round_trip(ArchiveFormat::Zip);
+    assert_files(&files);
+    assert!(find(&files, "test-data").unwrap().is_directory);
+
+    let hello = find(&files, "test-data/hello.txt").unwrap();
+    assert_eq!(hello.metadata.mtime, Some(MTIME));
+    assert_eq!(hello.metadata.mode, Some(0o640));
+
+    let link = find(&files, "test-data/link.txt").unwrap();
+    assert_eq!(link.metadata.link_target.as_deref(), Some("hello.txt"));
+}
+
+#[test]
+fn test_write_tar_preserves_metadata() {
+    let files = round_trip(ArchiveFormat::Tar);
+    assert_files(&files);
+    assert_eq!(files.len(), 5);
+    assert!(find(&files, "test-data").unwrap().is_directory);
+
+    let hello = find(&files, "test-data/hello.txt").unwrap();
+    assert_eq!(
+        hello.metadata,
+        EntryMetadata {
+            mtime: Some(MTIME),
+            mode: Some(0o640),
+            uid: Some(1000),
+            gid: Some(100),
+            owner: Some("alice".to_string()),
+            group: Some("users".to_string()),
+            link_target: None,
+            raw_name: Some(b"test-data/hello.txt".to_vec()),
+            ..EntryMetadata::default()
+        }
+    );
+
+    let link = find(&files, "test-data/link.txt").unwrap();
+    assert!(link.is_symlink());
+    assert_eq!(link.metadata.link_target.as_deref(), Some("hello.txt"));
+
+    // Missing metadata falls back to fixed defaults.
+    let empty = find(&files, "test-data/empty.txt").unwrap();
+    assert_eq!(empty.metadata.mtime, Some(0));
+    assert_eq!(empty.metadata.mode, Some(0o644));
+}
+
+#[test]
+fn test_write_all_tar_variants() {
+    let formats = [
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarBz2,
+        ArchiveFormat::TarXz,
+        ArchiveFormat::TarZst,
+        ArchiveFormat::TarLz4,
+        ArchiveFormat::TarZ,
+        ArchiveFormat::TarLzma,
+        ArchiveFormat::TarLzip,
+        ArchiveFormat::TarBr,
+        ArchiveFormat::TarSz,
+    ];
+    let expected = round_trip(ArchiveFormat::Tar);
+
+    for format in formats {
+        let files = round_trip(format);
+        assert_files(&files);
+        assert_eq!(files.len(), expected.len(), "{:?}", format);
+        for (file, expected) in files.iter().zip(&expected) {
+            assert_eq!(file.path, expected.path, "{:?}", format);
+            assert_eq!(file.metadata, expected.metadata, "{:?}", format);
+        }
+    }
+}
+
+#[test]
+fn test_write_ar_skips_directories_and_links() {
+    for format in [ArchiveFormat::Ar, ArchiveFormat::Deb] {
+        let files = round_trip(format);
+        assert_files(&files);
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().all(|f| !f.is_directory && !f.is_symlink()));
+
+        let hello = find(&files, "test-data/hello.txt").unwrap();
+        assert_eq!(hello.metadata.mtime, Some(MTIME));
+        assert_eq!(hello.metadata.mode, Some(0o640));
+        assert_eq!(hello.metadata.uid, Some(1000));
+        assert_eq!(hello.metadata.gid, Some(100));
+    }
+}
+
+#[test]
+fn test_write_7z() {
+    let files = round_trip(ArchiveFormat::SevenZ);
+    assert_files(&files);
+    assert!(find(&files, "test-data").unwrap().is_directory);
+
+    let hello = find(&files, "test-data/hello.txt").unwrap();
+    assert_eq!(hello.metadata.mtime, Some(MTIME));
+    assert_eq!(hello.metadata.mode, Some(0o640));
+
+    let link = find(&files, "test-data/link.txt").unwrap();
+    assert_eq!(link.metadata.link_target.as_deref(), Some("hello.txt"));
+}
+
+#[test]
+fn test_write_cab() {
+    let files = round_trip(ArchiveFormat::Cab);
+    assert_files(&files);
+    assert_eq!(files.len(), 3);
+
+    let hello = find(&files, "test-data/hello.txt").unwrap();
+    assert_eq!(hello.metadata.mtime, Some(MTIME));
+}
+
+#[test]
+#[cfg(feature = "rar-write")]
+fn test_write_rar() {
+    let files = round_trip(ArchiveFormat::Rar);
+    assert_files(&files);
+    assert!(find(&files, "test-data").unwrap().is_directory);
+
+    let hello = find(&files, "test-data/hello.txt").unwrap();
+    assert_eq!(hello.metadata.mtime, Some(MTIME));
+    assert_eq!(hello.metadata.mode, Some(0o640));
+}
+
+#[test]
+#[cfg(not(feature = "rar-write"))]
+fn test_write_rar_needs_feature() {
+    let result = ArchiveWriter::new().write_with_format(&sample_entries(), ArchiveFormat::Rar);
+    assert!(matches!(result, Err(ArchiveError::UnsupportedFormat(_))));
+}
+
+#[test]
+fn test_write_single_file_formats() {
+    let formats = [
+        ArchiveFormat::Gz,
+        ArchiveFormat::Bz2,
+        ArchiveFormat::Xz,
+        ArchiveFormat::Lz4,
+        ArchiveFormat::Zst,
+        ArchiveFormat::Z,
+        ArchiveFormat::Lzma,
+        ArchiveFormat::Lzip,
+        ArchiveFormat::Br,
+        ArchiveFormat::Sz,
+    ];
+    let data = HELLO.repeat(1000);
+    let entries = [ExtractedFile::file("hello.txt", data.clone())];
+
+    for format in formats {
+        let compressed = ArchiveWriter::new()
+            .write_with_format(&entries, format)
+            .unwrap();
+        assert!(compressed.len() < data.len(), "{:?}", format);
+
+        let files = ArchiveExtractor::new()
+            .extract_with_format(&compressed, format)
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].data, data, "{:?}", format);
+    }
+}
+
+#[test]
+fn test_write_single_file_empty() {
+    let entries = [ExtractedFile::file("empty.txt", Vec::new())];
+    for format in [ArchiveFormat::Xz, ArchiveFormat::Lzip, ArchiveFormat::Z] {
+        let compressed = ArchiveWriter::new()
+            .write_with_format(&entries, format)
+            .unwrap();
+        let files = ArchiveExtractor::new()
+            .extract_with_format(&compressed, format)
+            .unwrap();
+        assert!(files[0].data.is_empty(), "{:?}", format);
+    }
+}
+
+#[test]
+fn test_write_gz_stores_name() {
+    let entries = [ExtractedFile::file("docs/hello.txt", HELLO)];
+    let data = ArchiveWriter::new()
+        .with_format(ArchiveFormat::Gz)
+        .write(&entries)
+        .unwrap();
+
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Gz)
+        .unwrap();
+    assert_eq!(files[0].path, "hello.txt");
+    assert_eq!(files[0].data, HELLO);
+}
+
+#[test]
+fn test_write_single_file_rejects_multiple_entries() {
+    let result = ArchiveWriter::new().write_with_format(&sample_entries(), ArchiveFormat::Zst);
+    assert!(matches!(result, Err(ArchiveError::InvalidInput(_))));
+}
+
+#[test]
+fn test_compression_levels() {
+    let entries = [ExtractedFile::file("hello.txt", HELLO.repeat(1000))];
+
+    for format in [
+        ArchiveFormat::Zip,
+        ArchiveFormat::Gz,
+        #[cfg(feature = "rar-write")]
+        ArchiveFormat::Rar,
+    ] {
+        let stored = ArchiveWriter::new()
+            .with_compression_level(CompressionLevel::Store)
+            .write_with_format(&entries, format)
+            .unwrap();
+        let best = ArchiveWriter::new()
+            .with_compression_level(CompressionLevel::Best)
+            .write_with_format(&entries, format)
+            .unwrap();
+        assert!(stored.len() > HELLO.len() * 1000, "{:?}", format);
+        assert!(best.len() < stored.len(), "{:?}", format);
+
+        let files = ArchiveExtractor::new()
+            .extract_with_format(&stored, format)
+            .unwrap();
+        assert_eq!(files[0].data, HELLO.repeat(1000), "{:?}", format);
+    }
+
+    let data = ArchiveWriter::new()
+        .with_compression_level(CompressionLevel::Level(19))
+        .write_with_format(&entries, ArchiveFormat::Zst)
+        .unwrap();
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Zst)
+        .unwrap();
+    assert_eq!(files[0].data, HELLO.repeat(1000));
+}
+
+#[test]
+fn test_rewrite_extracted_archive() {
+    let zip = ArchiveWriter::new()
+        .write_with_format(&sample_entries(), ArchiveFormat::Zip)
+        .unwrap();
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&zip, ArchiveFormat::Zip)
+        .unwrap();
+
+    let tar = ArchiveWriter::new()
+        .write_with_format(&files, ArchiveFormat::TarXz)
+        .unwrap();
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&tar, ArchiveFormat::TarXz)
+        .unwrap();
+    assert_files(&files);
+    assert_eq!(
+        find(&files, "test-data/link.txt")
+            .unwrap()
+            .metadata
+            .link_target
+            .as_deref(),
+        Some("hello.txt")
+    );
+}
+
+// Deterministic output
+
+const EPOCH: u64 = 1_600_000_000;
+
+/// The sample entries in another order and with different ownership,
+/// permissions and timestamps, all of which deterministic mode normalizes.
+fn reordered_entries() -> Vec<ExtractedFile> {
+    let mut entries = sample_entries();
+    entries.reverse();
+    for entry in &mut entries {
+        entry.metadata.mtime = Some(MTIME + 3600);
+        entry.metadata.uid = Some(501);
+        entry.metadata.owner = Some("builder".to_string());
+        if !entry.is_directory && !entry.is_symlink() {
+            entry.metadata.mode = Some(0o600);
+        }
+    }
+    entries
+}
+
+fn write_deterministic(entries: &[ExtractedFile], format: ArchiveFormat) -> Vec<u8> {
+    ArchiveWriter::new()
+        .with_deterministic(EPOCH)
+        .write_with_format(entries, format)
+        .unwrap_or_else(|e| panic!("failed to write {:?}: {}", format, e))
+}
+
+#[test]
+fn test_deterministic_output_is_byte_identical() {
+    let formats = [
+        ArchiveFormat::Zip,
+        ArchiveFormat::Tar,
+        ArchiveFormat::Ar,
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarBz2,
+        ArchiveFormat::TarXz,
+        ArchiveFormat::TarZst,
+        ArchiveFormat::TarLz4,
+        ArchiveFormat::TarZ,
+        ArchiveFormat::TarLzma,
+        ArchiveFormat::TarLzip,
+        ArchiveFormat::TarBr,
+        ArchiveFormat::TarSz,
+        ArchiveFormat::SevenZ,
+        ArchiveFormat::Cab,
+        #[cfg(feature = "rar-write")]
+        ArchiveFormat::Rar,
+    ];
+
+    for format in formats {
+        let first = write_deterministic(&sample_entries(), format);
+        let second = write_deterministic(&sample_entries(), format);
+        assert_eq!(first, second, "{:?}", format);
+
+        let reordered = write_deterministic(&reordered_entries(), format);
+        assert_eq!(first, reordered, "{:?}", format);
+    }
+}
+
+#[test]
+fn test_deterministic_single_file_output() {
+    let entries = [
+        ExtractedFile::file("hello.txt", HELLO).with_metadata(EntryMetadata {
+            mtime: Some(MTIME),
+            ..EntryMetadata::default()
+        }),
+    ];
+    let renamed = [ExtractedFile::file("other/name.txt", HELLO)];
+
+    for format in [ArchiveFormat::Gz, ArchiveFormat::Xz, ArchiveFormat::Zst] {
+        assert_eq!(
+            write_deterministic(&entries, format),
+            write_deterministic(&renamed, format),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn test_deterministic_gzip_header() {
+    let entries = [
+        ExtractedFile::file("hello.txt", HELLO).with_metadata(EntryMetadata {
+            mtime: Some(MTIME),
+            ..EntryMetadata::default()
+        }),
+    ];
+
+    let data = write_deterministic(&entries, ArchiveFormat::Gz);
+    // No FNAME flag and a zero MTIME field.
+    assert_eq!(data[3], 0);
+    assert_eq!(data[4..8], [0, 0, 0, 0]);
+
+    let data = ArchiveWriter::new()
+        .write_with_format(&entries, ArchiveFormat::Gz)
+        .unwrap();
+    assert_ne!(data[3], 0);
+    assert_eq!(data[4..8], (MTIME as u32).to_le_bytes());
+}
+
+#[test]
+fn test_deterministic_normalizes_metadata() {
+    let mut entries = reordered_entries();
+    entries.push(
+        ExtractedFile::file("test-data/run.sh", b"#!/bin/sh\n".to_vec()).with_metadata(
+            EntryMetadata {
+                mtime: Some(EPOCH - 10),
+                mode: Some(0o700),
+                ..EntryMetadata::default()
+            },
+        ),
+    );
+    let data = write_deterministic(&entries, ArchiveFormat::Tar);
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Tar)
+        .unwrap();
+
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let mut sorted = paths.clone();
+    sorted.sort();
+    assert_eq!(paths, sorted);
+
+    let hello = find(&files, "test-data/hello.txt").unwrap();
+    assert_eq!(hello.metadata.mtime, Some(EPOCH));
+    assert_eq!(hello.metadata.mode, Some(0o644));
+    assert_eq!(hello.metadata.uid, Some(0));
+    assert_eq!(hello.metadata.gid, Some(0));
+    assert_eq!(hello.metadata.owner, None);
+    assert_eq!(hello.metadata.group, None);
+
+    // Older timestamps are kept, and execute bits survive normalization.
+    let script = find(&files, "test-data/run.sh").unwrap();
+    assert_eq!(script.metadata.mtime, Some(EPOCH - 10));
+    assert_eq!(script.metadata.mode, Some(0o755));
+
+    let dir = find(&files, "test-data").unwrap();
+    assert_eq!(dir.metadata.mode, Some(0o755));
+    let link = find(&files, "test-data/link.txt").unwrap();
+    assert_eq!(link.metadata.link_target.as_deref(), Some("hello.txt"));
+}
+
+#[test]
+fn test_deterministic_deb_keeps_member_order() {
+    let entries = vec![
+        ExtractedFile::file("debian-binary", b"2.0\n".to_vec()),
+        ExtractedFile::file("control.tar.gz", b"control".to_vec()),
+        ExtractedFile::file("data.tar.xz", b"data".to_vec()),
+    ];
+    let data = write_deterministic(&entries, ArchiveFormat::Deb);
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&data, ArchiveFormat::Ar)
+        .unwrap();
+
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["debian-binary", "control.tar.gz", "data.tar.xz"]);
+}
@@ -2,7 +2,10 @@
 
 mod common;
 
-use archive::{ArchiveExtractor, ArchiveFormat};
+use archive::{
+    ArchiveError, ArchiveExtractor, ArchiveFormat, ArchiveWriter, CompressionLevel, ExtractedFile,
+    ZipAnomaly,
+};
 use common::{assert_contains_file, read_test_archive};
 
 #[test]
@@ -177,3 +180,192 @@ fn test_potential_bomb_zip() {
         "Expected zip bomb to be caught by size limits"
     );
 }
+
+/// A stored ZIP of `a.txt` and `b.txt`, with the offsets of their central
+/// directory records.
+fn two_entry_zip() -> (Vec<u8>, Vec<usize>) {
+    let entries = vec![
+        ExtractedFile::file("a.txt", vec![b'a'; 64]),
+        ExtractedFile::file("b.txt", vec![b'b'; 64]),
+    ];
+    let zip = ArchiveWriter::new()
+        .with_compression_level(CompressionLevel::Store)
+        .write_with_format(&entries, ArchiveFormat::Zip)
+        .unwrap();
+    let records = zip
+        .windows(4)
+        .enumerate()
+        .filter(|(_, window)| *window == b"PK\x01\x02")
+        .map(|(i, _)| i)
+        .collect();
+    (zip, records)
+}
+
+#[test]
+fn test_zip_entries_sharing_data_rejected() {
+    let (mut zip, records) = two_entry_zip();
+    // Point b.txt's record at a.txt's local header.
+    zip[records[1] + 42..records[1] + 46].copy_from_slice(&0u32.to_le_bytes());
+
+    // The check comes before any size limit could be hit by inflating.
+    let result = ArchiveExtractor::new()
+        .with_max_total_size(1)
+        .extract_with_format(&zip, ArchiveFormat::Zip);
+    match result {
+        Err(ArchiveError::OverlappingEntries { path, other }) => {
+            let mut names = [path, other];
+            names.sort();
+            assert_eq!(names, ["a.txt", "b.txt"]);
+        }
+        other => panic!("expected OverlappingEntries, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_zip_entry_data_overlapping_next_header_rejected() {
+    let (mut zip, records) = two_entry_zip();
+    // Stretch a.txt's compressed size over b.txt's local header.
+    zip[records[0] + 20..records[0] + 24].copy_from_slice(&100u32.to_le_bytes());
+
+    let result = ArchiveExtractor::new().extract_with_format(&zip, ArchiveFormat::Zip);
+    assert!(matches!(
+        result,
+        Err(ArchiveError::OverlappingEntries { ref path, ref other })
+            if path == "b.txt" && other == "a.txt"
+    ));
+}
+
+#[test]
+fn test_zip_adjacent_entries_accepted() {
+    let (zip, _) = two_entry_zip();
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&zip, ArchiveFormat::Zip)
+        .unwrap();
+    assert_eq!(files.len(), 2);
+}
+
+/// Offset of the local header of the entry whose name is `name`.
+fn local_header(zip: &[u8], name: &[u8]) -> usize {
+    zip.windows(4)
+        .enumerate()
+        .find(|(i, window)| *window == b"PK\x03\x04" && zip[i + 30..].starts_with(name))
+        .map(|(i, _)| i)
+        .unwrap()
+}
+
+#[test]
+fn test_validate_zip_clean_archives() {
+    let extractor = ArchiveExtractor::new();
+    let (zip, _) = two_entry_zip();
+    assert_eq!(extractor.validate_zip(&zip).unwrap(), []);
+
+    // Stored nested archives carry their own end record inside entry data.
+    for name in ["basic.zip", "nested.zip", "deeply-nested.zip", "empty.zip"] {
+        let data = read_test_archive(name);
+        assert_eq!(extractor.validate_zip(&data).unwrap(), [], "{}", name);
+    }
+}
+
+#[test]
+fn test_validate_zip_local_name_mismatch() {
+    let (mut zip, _) = two_entry_zip();
+    let pos = local_header(&zip, b"b.txt");
+    zip[pos + 30] = b'c';
+
+    assert_eq!(
+        ArchiveExtractor::new().validate_zip(&zip).unwrap(),
+        [ZipAnomaly::NameMismatch {
+            path: "b.txt".into(),
+            local: "c.txt".into(),
+        }]
+    );
+}
+
+#[test]
+fn test_validate_zip_local_field_mismatches() {
+    let (mut zip, _) = two_entry_zip();
+    let pos = local_header(&zip, b"a.txt");
+    zip[pos + 8..pos + 10].copy_from_slice(&8u16.to_le_bytes());
+    zip[pos + 14..pos + 18].copy_from_slice(&0u32.to_le_bytes());
+    zip[pos + 18..pos + 22].copy_from_slice(&32u32.to_le_bytes());
+    zip[pos + 22..pos + 26].copy_from_slice(&1024u32.to_le_bytes());
+
+    let anomalies = ArchiveExtractor::new().validate_zip(&zip).unwrap();
+    assert!(matches!(
+        &anomalies[..],
+        [
+            ZipAnomaly::MethodMismatch {
+                local: 8,
+                central: 0,
+                ..
+            },
+            ZipAnomaly::CrcMismatch { local: 0, .. },
+            ZipAnomaly::CompressedSizeMismatch {
+                local: 32,
+                central: 64,
+                ..
+            },
+            ZipAnomaly::SizeMismatch {
+                local: 1024,
+                central: 64,
+                ..
+            },
+        ]
+    ));
+}
+
+#[test]
+fn test_validate_zip_prefix_and_trailing_data() {
+    let (zip, _) = two_entry_zip();
+    let mut data = vec![0u8; 100];
+    data.extend_from_slice(&zip);
+    data.extend_from_slice(b"trailer");
+
+    assert_eq!(
+        ArchiveExtractor::new().validate_zip(&data).unwrap(),
+        [
+            ZipAnomaly::PrefixData { size: 100 },
+            ZipAnomaly::TrailingData { size: 7 },
+        ]
+    );
+}
+
+#[test]
+fn test_validate_zip_multiple_end_records() {
+    let (mut zip, _) = two_entry_zip();
+    let end_record = zip[zip.len() - 22..].to_vec();
+    zip.extend_from_slice(&end_record);
+
+    assert_eq!(
+        ArchiveExtractor::new().validate_zip(&zip).unwrap(),
+        [ZipAnomaly::MultipleEndRecords { count: 2 }]
+    );
+}
+
+#[test]
+fn test_strict_zip_rejects_ambiguous_archive() {
+    let (mut zip, _) = two_entry_zip();
+    let pos = local_header(&zip, b"b.txt");
+    zip[pos + 30] = b'c';
+
+    // Lenient extraction goes by the central directory.
+    let files = ArchiveExtractor::new()
+        .extract_with_format(&zip, ArchiveFormat::Zip)
+        .unwrap();
+    assert_eq!(files[1].path, "b.txt");
+
+    let result = ArchiveExtractor::new()
+        .with_strict_zip(true)
+        .extract_with_format(&zip, ArchiveFormat::Zip);
+    match result {
+        Err(ArchiveError::AmbiguousZip(anomalies)) => assert_eq!(anomalies.len(), 1),
+        other => panic!("expected AmbiguousZip, got {:?}", other),
+    }
+
+    let (zip, _) = two_entry_zip();
+    let files = ArchiveExtractor::new()
+        .with_strict_zip(true)
+        .extract_with_format(&zip, ArchiveFormat::Zip)
+        .unwrap();
+    assert_eq!(files.len(), 2);
+}